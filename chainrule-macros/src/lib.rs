@@ -17,6 +17,18 @@ fn chainrule_crate() -> proc_macro2::TokenStream {
     }
 }
 
+/// Rewrites a function over `Tensor`s into a graph builder for `trace_fn`.
+///
+/// Arguments typed `f32` or `f64` are hyperparameters, not inputs: the builder
+/// takes them after the session and embeds each as a graph constant, so they
+/// are fixed at trace time and never differentiated. They work both in tensor
+/// arithmetic and as the scalar parameter of ops like `clamp` or `powf`.
+/// Trace such a function with a closure, e.g.
+/// `trace_fn::<f32>(|sess| f(sess, 0.5))`.
+///
+/// There is no dynamic control flow in the graph: `for` loops run at trace
+/// time, so their bounds must be plain Rust values known when tracing, and
+/// each iteration emits its own copy of the body's ops.
 #[proc_macro_attribute]
 pub fn trace(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let input_fn = parse_macro_input!(item as ItemFn);
@@ -27,14 +39,20 @@ pub fn trace(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let old_name = format_ident!("{}_old", fn_name);
     let inline_name = format_ident!("{}_inline", fn_name);
-    // all inputs must be of Tensor/Tracer type.
-    let arg_idents: Vec<Box<syn::Pat>> = fn_inputs
+    // inputs are Tensor/Tracer, except plain `f32`/`f64` hyperparameters.
+    let typed_args: Vec<&syn::PatType> = fn_inputs
         .iter()
         .filter_map(|arg| match arg {
-            syn::FnArg::Typed(pat) => Some(pat.pat.clone()),
+            syn::FnArg::Typed(pat) => Some(pat),
             _ => None,
         })
         .collect();
+    let arg_idents: Vec<Box<syn::Pat>> = typed_args.iter().map(|pat| pat.pat.clone()).collect();
+    let (scalar_args, tensor_args): (Vec<&syn::PatType>, Vec<&syn::PatType>) =
+        typed_args.iter().partition(|pat| is_scalar_type(&pat.ty));
+    let tensor_idents: Vec<&syn::Pat> = tensor_args.iter().map(|pat| &*pat.pat).collect();
+    let scalar_idents: Vec<&syn::Pat> = scalar_args.iter().map(|pat| &*pat.pat).collect();
+    let scalar_types: Vec<&syn::Type> = scalar_args.iter().map(|pat| &*pat.ty).collect();
     let fn_body = &input_fn.block;
     let sess_ident = syn::parse_str::<syn::Ident>("sess").unwrap();
 
@@ -53,21 +71,32 @@ pub fn trace(_attr: TokenStream, item: TokenStream) -> TokenStream {
         }
 
         #[allow(unused_parens)]
-        #fn_vis fn #fn_name<'a, D: #chainrule::Floating + 'static>(
-            sess: &mut #chainrule::TraceSession<'a, D>,
-        ) -> (Vec<#chainrule::identity::Id>, #chainrule::tracing::Tracer) {
-            #( let #arg_idents = { sess.input() }; )*
+        #fn_vis fn #fn_name<'a, D, G>(
+            sess: &mut #chainrule::TraceSession<'a, D, G>,
+            #( #scalar_idents: #scalar_types ),*
+        ) -> (Vec<#chainrule::identity::Id>, #chainrule::tracing::Tracer)
+        where
+            D: #chainrule::Floating + 'static,
+            G: #chainrule::identity::IdGenerator<Id = #chainrule::identity::Id>,
+        {
+            #( let #tensor_idents = { sess.input() }; )*
+            // scalars are baked into the graph, not inputs: never differentiated.
+            #( let #scalar_idents = { sess.constant(D::from_f64(f64::from(#scalar_idents))) }; )*
             let result = { #new_body };
-            (vec![#(#arg_idents.id()),*], result)
+            (vec![#(#tensor_idents.id()),*], result)
         }
 
         // inline variant for use inside other #[trace] functions:
         // reuses the same session and accepts Tracer args directly.
         #[allow(unused_parens)]
-        #fn_vis fn #inline_name<'a, D: #chainrule::Floating + 'static>(
-            sess: &mut #chainrule::TraceSession<'a, D>,
+        #fn_vis fn #inline_name<'a, D, G>(
+            sess: &mut #chainrule::TraceSession<'a, D, G>,
             #( #arg_idents: #chainrule::tracing::Tracer ),*
-        ) -> #chainrule::tracing::Tracer {
+        ) -> #chainrule::tracing::Tracer
+        where
+            D: #chainrule::Floating + 'static,
+            G: #chainrule::identity::IdGenerator<Id = #chainrule::identity::Id>,
+        {
             #( let _ = &#arg_idents; )*
             let result = { #new_body };
             result
@@ -76,6 +105,16 @@ pub fn trace(_attr: TokenStream, item: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
+/// `f32`/`f64` arguments are trace-time hyperparameters rather than inputs.
+fn is_scalar_type(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(p) => {
+            p.qself.is_none() && (p.path.is_ident("f32") || p.path.is_ident("f64"))
+        }
+        _ => false,
+    }
+}
+
 #[derive(Debug, Clone)]
 struct TraceRewriter {
     sess_ident: syn::Ident,
@@ -88,12 +127,51 @@ impl TraceRewriter {
         self.counter += 1;
         format_ident!("__{}_{}", prefix, idx)
     }
+
+    // `acc += rhs` lowers to `acc = sess.add(acc, rhs)`; Tracer is Copy so reassigning is fine.
+    fn lower_compound_assign(&mut self, place: Expr, rhs: Expr, method: &str) -> Expr {
+        let rhs = self.fold_expr(rhs);
+        let tmp_r = self.fresh("tmp_r");
+        let method = format_ident!("{}", method);
+        let sess = &self.sess_ident;
+        syn::parse_quote! {{
+            let #tmp_r = #rhs;
+            #place = #sess.#method(#place, #tmp_r);
+        }}
+    }
+}
+
+fn compound_assign_method(op: &BinOp) -> Option<&'static str> {
+    match op {
+        BinOp::AddAssign(_) => Some("add"),
+        BinOp::SubAssign(_) => Some("sub"),
+        BinOp::MulAssign(_) => Some("mul"),
+        BinOp::DivAssign(_) => Some("div"),
+        _ => None,
+    }
+}
+
+// the method forms of the arithmetic operators carry a `_t` suffix: method
+// calls are routed by name alone, so a bare `add` would also catch
+// `std::ops::Add::add` on plain values such as `n.add(1)` on a `usize`.
+fn session_method(method: &syn::Ident) -> syn::Ident {
+    let name = match method.to_string().as_str() {
+        "add_t" => "add",
+        "sub_t" => "sub",
+        "mul_t" => "mul",
+        "div_t" => "div",
+        _ => return method.clone(),
+    };
+    syn::Ident::new(name, method.span())
 }
 
 impl Fold for TraceRewriter {
     fn fold_expr(&mut self, expr: Expr) -> Expr {
         match expr {
             Expr::Binary(bin) => {
+                if let Some(method) = compound_assign_method(&bin.op) {
+                    return self.lower_compound_assign(*bin.left, *bin.right, method);
+                }
                 let lhs = self.fold_expr(*bin.left);
                 let rhs = self.fold_expr(*bin.right);
                 let tmp_l = self.fresh("tmp_l");
@@ -131,6 +209,18 @@ impl Fold for TraceRewriter {
                 }
             }
 
+            // `-2.0` is a single constant rather than `neg(constant(2.0))`.
+            Expr::Unary(u)
+                if matches!(u.op, UnOp::Neg(_))
+                    && matches!(&*u.expr, Expr::Lit(l) if matches!(l.lit, syn::Lit::Float(_))) =>
+            {
+                let lit = &u.expr;
+                let sess = &self.sess_ident;
+                syn::parse_quote! {{
+                    #sess.constant(D::from_f64(-#lit))
+                }}
+            }
+
             Expr::Unary(u) if matches!(u.op, UnOp::Neg(_)) => {
                 let inner = self.fold_expr(*u.expr);
                 let tmp_in = self.fresh("tmp_in");
@@ -177,9 +267,61 @@ impl Fold for TraceRewriter {
             }
 
             Expr::MethodCall(mc) => {
-                let receiver = self.fold_expr(*mc.receiver);
-                let args: Vec<_> = mc.args.into_iter().map(|a| self.fold_expr(a)).collect();
                 let method = mc.method.clone();
+                // ops taking scalars: their arguments are resolved to `D` at trace
+                // time through `ScalarArg`, whether they are plain Rust floats or
+                // hyperparameters already lifted into graph constants.
+                let takes_scalars = matches!(
+                    method.to_string().as_str(),
+                    "maximum_scalar"
+                        | "minimum_scalar"
+                        | "clamp"
+                        | "powf"
+                        | "exp_clamped"
+                        | "nan_to_num"
+                );
+                // ops whose arguments are all plain Rust values (axes, shapes,
+                // flags): passed through as written, so `[-1]` stays an array.
+                let static_args = matches!(
+                    method.to_string().as_str(),
+                    "sum"
+                        | "sum_stable"
+                        | "mean"
+                        | "mean_stable"
+                        | "max"
+                        | "max_with_tie_break"
+                        | "l1_norm"
+                        | "sum_squares"
+                        | "reshape"
+                        | "flatten"
+                        | "as_shape"
+                        | "expand_dims"
+                        | "slice"
+                        | "index"
+                        | "select_axis"
+                        | "broadcast"
+                        | "transpose"
+                        | "split"
+                        | "repeat_interleave"
+                        | "assert_shape"
+                        | "embedding"
+                        | "relu_with_boundary"
+                );
+                let receiver = self.fold_expr(*mc.receiver);
+                let args: Vec<Expr> = if takes_scalars {
+                    mc.args
+                        .into_iter()
+                        .map(|a| {
+                            let chainrule = chainrule_crate();
+                            let sess = &self.sess_ident;
+                            syn::parse_quote!(#chainrule::tracing::ScalarArg::scalar(#a, &*#sess))
+                        })
+                        .collect()
+                } else if static_args {
+                    mc.args.into_iter().collect()
+                } else {
+                    mc.args.into_iter().map(|a| self.fold_expr(a)).collect()
+                };
                 let recv_tmp = self.fresh("recv");
                 let out_tmp = self.fresh("tmp_out");
                 let arg_tmps: Vec<syn::Ident> =
@@ -187,23 +329,74 @@ impl Fold for TraceRewriter {
                 let sess = &self.sess_ident;
 
                 // List of ops we route through the session
-                let is_traced = matches!(
-                    method.to_string().as_str(),
-                    "matmul"
-                        | "t"
-                        | "transpose"
-                        | "reshape"
-                        | "broadcast"
-                        | "sum"
-                        | "exp"
-                        | "log"
-                        | "relu"
-                        | "div"
-                        | "max"
-                        | "mean"
-                );
+                let is_traced = takes_scalars
+                    || matches!(
+                        method.to_string().as_str(),
+                        "matmul"
+                            | "t"
+                            | "transpose"
+                            | "reshape"
+                            | "reshape_like"
+                            | "flatten"
+                            | "as_shape"
+                            | "expand_dims"
+                            | "assert_scalar"
+                            | "round"
+                            | "straight_through"
+                            | "gt_mask"
+                            | "lt_mask"
+                            | "eq_mask"
+                            | "neg"
+                            | "add_t"
+                            | "sub_t"
+                            | "mul_t"
+                            | "div_t"
+                            | "masked_mean"
+                            | "assert_shape"
+                            | "repeat_interleave"
+                            | "broadcast"
+                            | "sum"
+                            | "sum_stable"
+                            | "sum_all"
+                            | "mean_stable"
+                            | "mean_all"
+                            | "max_all"
+                            | "zeros_like"
+                            | "ones_like"
+                            | "where_positive"
+                            | "maximum"
+                            | "minimum"
+                            | "inverse"
+                            | "solve"
+                            | "det"
+                            | "logdet"
+                            | "cholesky"
+                            | "add_bias"
+                            | "erf"
+                            | "gelu"
+                            | "rsqrt"
+                            | "slice"
+                            | "index"
+                            | "select_axis"
+                            | "linear"
+                            | "exp"
+                            | "log"
+                            | "relu"
+                            | "relu_with_boundary"
+                            | "max"
+                            | "max_with_tie_break"
+                            | "mean"
+                            | "l1_norm"
+                            | "sum_squares"
+                            | "mean_softmax_cross_entropy"
+                            | "embedding"
+                            | "layer_norm"
+                            | "batch_norm"
+                            | "split"
+                    );
 
                 if is_traced {
+                    let method = session_method(&method);
                     syn::parse_quote! {{
                         let #recv_tmp = #receiver;
                         #( let #arg_tmps = #args; )*
@@ -219,6 +412,39 @@ impl Fold for TraceRewriter {
                     }}
                 }
             }
+            // `a[i]` lowers through the `TraceIndex` trait, so a tensor takes
+            // `sess.index(a, i)` while a plain Rust collection, e.g. the `Vec`
+            // from `split`, is indexed as usual. the index is a plain Rust
+            // value, so only constant tensor indices are supported.
+            Expr::Index(idx) => {
+                let receiver = self.fold_expr(*idx.expr);
+                let index = idx.index;
+                let recv_tmp = self.fresh("recv");
+                let out_tmp = self.fresh("tmp_index");
+                let sess = &self.sess_ident;
+                let chainrule = chainrule_crate();
+                syn::parse_quote! {{
+                    // borrowed, so indexing a Vec does not move it.
+                    let #recv_tmp = &#receiver;
+                    let #out_tmp = #chainrule::tracing::TraceIndex::trace_index(
+                        #recv_tmp,
+                        #index,
+                        &mut *#sess,
+                    );
+                    #out_tmp
+                }}
+            }
+            // `v[i] = rhs` assigns into a Rust place, which must stay a place.
+            Expr::Assign(mut assign) if matches!(*assign.left, Expr::Index(_)) => {
+                assign.right = Box::new(self.fold_expr(*assign.right));
+                Expr::Assign(assign)
+            }
+            // the iterator is plain Rust evaluated at trace time; only the body is traced.
+            Expr::ForLoop(mut for_loop) => {
+                for_loop.body = self.fold_block(for_loop.body);
+                Expr::ForLoop(for_loop)
+            }
+
             other => fold::fold_expr(self, other),
         }
     }
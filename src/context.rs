@@ -1,27 +1,47 @@
-use std::collections::HashMap;
-
 use crate::{Floating, identity::Id, tracing::TensorData};
 
+/// Live tensors during evaluation, stored in a dense slot per `Id`.
+///
+/// ids come from a counter, so indexing a `Vec` by them beats hashing. the
+/// storage is private; go through `get`, `checked_get`, `insert` and `take`.
 #[derive(Debug, Clone)]
 pub struct Context<D = f32> {
-    pub tensors: HashMap<Id, TensorData<D>>,
+    tensors: Vec<Option<TensorData<D>>>,
 }
 
 impl<D: Floating> Context<D> {
     pub fn new() -> Self {
+        Self { tensors: vec![] }
+    }
+
+    /// preallocates room for ids below `slots`.
+    pub fn with_slots(slots: usize) -> Self {
         Self {
-            tensors: HashMap::new(),
+            tensors: (0..slots).map(|_| None).collect(),
         }
     }
 
+    /// the tensor for `id`, if it has been inserted and not taken.
+    pub fn get(&self, id: &Id) -> Option<&TensorData<D>> {
+        self.tensors.get(id.as_usize()).and_then(Option::as_ref)
+    }
+
     pub fn checked_get(&self, id: &Id) -> &TensorData<D> {
-        self.tensors
-            .get(id)
+        self.get(id)
             .unwrap_or_else(|| panic!("tensor({id:?}) was not found in context."))
     }
 
     pub fn insert(&mut self, id: Id, tensor: TensorData<D>) {
-        self.tensors.insert(id, tensor);
+        let slot = id.as_usize();
+        if slot >= self.tensors.len() {
+            self.tensors.resize_with(slot + 1, || None);
+        }
+        self.tensors[slot] = Some(tensor);
+    }
+
+    /// moves a tensor out, freeing its slot.
+    pub fn take(&mut self, id: &Id) -> Option<TensorData<D>> {
+        self.tensors.get_mut(id.as_usize())?.take()
     }
 }
 
@@ -1,4 +1,9 @@
-use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::fmt::{Display, Formatter, Result as FmtResult, Write as _};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::Range,
+    rc::Rc,
+};
 
 use crate::{
     Floating,
@@ -6,11 +11,26 @@ use crate::{
     ops::Op,
 };
 
-pub type Node<T> = Vec<Box<dyn Op<T>>>;
+/// nodes are reference counted and never mutated in place, so cloning a graph
+/// (as `grad()` does before appending the reverse pass) shares every forward op
+/// instead of deep-copying it.
+pub type Node<T> = Vec<Rc<dyn Op<T>>>;
 
 #[derive(Debug, Clone)]
 pub struct Graph<DType = f32, G: IdGenerator = FreeList> {
     pub nodes: Node<DType>,
+    /// ids of inputs tagged as trainable parameters.
+    pub params: Vec<Id>,
+    /// maps the index of each node emitted by a vjp to the index of its forward node.
+    pub grad_sources: HashMap<usize, usize>,
+    /// node index ranges whose outputs the backward pass recomputes instead of
+    /// keeping alive; see `TraceSession::checkpoint`.
+    pub checkpoints: Vec<Range<usize>>,
+    /// number of nodes that existed before the latest reverse pass, or `None`
+    /// if the graph was never differentiated.
+    pub forward_len: Option<usize>,
+    /// node budget enforced by [`Graph::push`]; `None` means unbounded.
+    pub max_nodes: Option<usize>,
     generator: G,
 }
 
@@ -18,17 +38,358 @@ impl<D: Floating> Graph<D> {
     pub fn new() -> Self {
         Self {
             nodes: vec![],
+            params: vec![],
+            grad_sources: HashMap::new(),
+            checkpoints: vec![],
+            forward_len: None,
+            max_nodes: None,
             generator: FreeList::new(),
         }
     }
 
+    /// an empty graph that panics once more than `max_nodes` nodes are
+    /// pushed, to catch runaway construction such as an unbounded unroll.
+    /// the budget carries over to graphs derived from this one, e.g. by `grad`.
+    pub fn with_budget(max_nodes: usize) -> Self {
+        Self {
+            max_nodes: Some(max_nodes),
+            ..Self::new()
+        }
+    }
+}
+
+impl<D: Floating, G: IdGenerator<Id = Id>> Graph<D, G> {
+    /// builds an empty graph that draws node ids from `generator`.
+    pub fn with_generator(generator: G) -> Self {
+        Self {
+            nodes: vec![],
+            params: vec![],
+            grad_sources: HashMap::new(),
+            checkpoints: vec![],
+            forward_len: None,
+            max_nodes: None,
+            generator,
+        }
+    }
+
     pub fn push(&mut self, op: Box<dyn Op<D>>) {
-        self.nodes.push(op);
+        if let Some(max) = self.max_nodes
+            && self.nodes.len() >= max
+        {
+            panic!(
+                "graph exceeded {max} nodes while pushing `{}`; is a loop building the graph unbounded?",
+                op.name()
+            );
+        }
+        self.nodes.push(Rc::from(op));
+    }
+
+    /// swaps the node at `index` for `new_op`, e.g. to try a different
+    /// activation without re-tracing. `new_op` must read and write exactly
+    /// the same tensor ids, so the rest of the graph is left consistent.
+    pub fn replace_op(&mut self, index: usize, new_op: Box<dyn Op<D>>) {
+        let old = self.nodes.get(index).unwrap_or_else(|| {
+            panic!(
+                "replace_op: index {index} is out of range for {} nodes",
+                self.nodes.len()
+            )
+        });
+        assert_eq!(
+            old.inputs(),
+            new_op.inputs(),
+            "replace_op: {} reads different inputs than the {} it replaces",
+            new_op.name(),
+            old.name()
+        );
+        assert_eq!(
+            old.outputs(),
+            new_op.outputs(),
+            "replace_op: {} writes different outputs than the {} it replaces",
+            new_op.name(),
+            old.name()
+        );
+        self.nodes[index] = Rc::from(new_op);
     }
 
     pub fn fresh(&mut self) -> Id {
         self.generator.fresh()
     }
+
+    /// hands `id` back to the generator once no node refers to it any more.
+    pub fn release(&mut self, id: Id) {
+        self.generator.release(id);
+    }
+
+    pub fn is_param(&self, id: Id) -> bool {
+        self.params.contains(&id)
+    }
+
+    /// propagates concrete input shapes through every node's `shape_rule`,
+    /// returning the shape of every tensor id without evaluating anything.
+    pub fn infer_shapes(
+        &self,
+        inputs: &[Id],
+        input_shapes: &[Vec<usize>],
+    ) -> HashMap<Id, Vec<usize>> {
+        assert_eq!(
+            inputs.len(),
+            input_shapes.len(),
+            "infer_shapes: expected {} input shapes, got {}",
+            inputs.len(),
+            input_shapes.len()
+        );
+        let mut shapes: HashMap<Id, Vec<usize>> = inputs
+            .iter()
+            .copied()
+            .zip(input_shapes.iter().cloned())
+            .collect();
+
+        for node in &self.nodes {
+            let outs = node.outputs();
+            // inputs are seeded by the caller.
+            if outs.iter().all(|out| shapes.contains_key(out)) {
+                continue;
+            }
+            let in_shapes: Vec<_> = node
+                .inputs()
+                .iter()
+                .map(|id| {
+                    shapes.get(id).cloned().unwrap_or_else(|| {
+                        panic!("infer_shapes: no shape for {id:?} feeding {}", node.name())
+                    })
+                })
+                .collect();
+            shapes.extend(outs.into_iter().zip(node.shape_rule(&in_shapes)));
+        }
+        shapes
+    }
+
+    /// finds a dependency cycle among the nodes, as can happen when a graph is
+    /// assembled by hand with `push`.
+    ///
+    /// returns the tensor ids along the cycle, each one consumed by the node
+    /// producing the next and the last consumed by the producer of the first.
+    pub fn detect_cycle(&self) -> Option<Vec<Id>> {
+        let producers: HashMap<Id, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, node)| node.outputs().into_iter().map(move |out| (out, i)))
+            .collect();
+        let deps = |i: usize| -> Vec<(Id, usize)> {
+            self.nodes[i]
+                .inputs()
+                .into_iter()
+                .filter_map(|id| producers.get(&id).map(|&p| (id, p)))
+                .collect()
+        };
+
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            New,
+            Open,
+            Done,
+        }
+        let mut marks = vec![Mark::New; self.nodes.len()];
+
+        for root in 0..self.nodes.len() {
+            if marks[root] != Mark::New {
+                continue;
+            }
+            // iterative dfs; each frame keeps the tensor it was reached through.
+            let mut stack = vec![(root, None, deps(root))];
+            marks[root] = Mark::Open;
+            while let Some((node, _, pending)) = stack.last_mut() {
+                let node = *node;
+                let Some((via, next)) = pending.pop() else {
+                    marks[node] = Mark::Done;
+                    stack.pop();
+                    continue;
+                };
+                match marks[next] {
+                    Mark::New => {
+                        marks[next] = Mark::Open;
+                        stack.push((next, Some(via), deps(next)));
+                    }
+                    Mark::Open => {
+                        let start = stack.iter().position(|(n, _, _)| *n == next).unwrap();
+                        let mut cycle: Vec<Id> = stack[start + 1..]
+                            .iter()
+                            .filter_map(|(_, v, _)| *v)
+                            .collect();
+                        cycle.push(via);
+                        return Some(cycle);
+                    }
+                    Mark::Done => {}
+                }
+            }
+        }
+        None
+    }
+
+    /// merges constant nodes (see [`Op::const_value`]) holding the same value,
+    /// such as the many `1.0`s emitted by vjps, into the first of them and
+    /// rewires their consumers.
+    ///
+    /// a duplicate is kept if any consumer cannot be rewired (see
+    /// [`Op::with_inputs`]); the elementwise, broadcasting, reduction, reshape,
+    /// transpose and matmul ops can be, most others cannot yet. returns the
+    /// merged-away ids mapped to the surviving constant, so callers can remap
+    /// any ids they hold; [`crate::TraceableFn::deduplicate_constants`] does
+    /// this for the inputs and outputs. the merged-away ids are not released,
+    /// so a stale copy never aliases a node created later.
+    pub fn deduplicate_constants(&mut self) -> HashMap<Id, Id> {
+        let mut consumers: HashMap<Id, Vec<usize>> = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            for inp in node.inputs() {
+                consumers.entry(inp).or_default().push(i);
+            }
+        }
+
+        // keyed on the shape and exact bits, so `0.0` and `-0.0` stay apart.
+        let mut first: HashMap<(Vec<usize>, Vec<u64>), Id> = HashMap::new();
+        let mut merged: HashMap<Id, Id> = HashMap::new();
+        for node in &self.nodes {
+            let Some(value) = node.const_value() else {
+                continue;
+            };
+            let out = node.outputs()[0];
+            let value = (
+                value.shape().to_vec(),
+                value.iter().map(|a| a.as_f64().to_bits()).collect(),
+            );
+            match first.get(&value) {
+                None => {
+                    first.insert(value, out);
+                }
+                Some(&keep) => {
+                    let rewirable = consumers
+                        .get(&out)
+                        .into_iter()
+                        .flatten()
+                        .all(|&c| self.nodes[c].with_inputs(&self.nodes[c].inputs()).is_some());
+                    if rewirable {
+                        merged.insert(out, keep);
+                    }
+                }
+            }
+        }
+        if merged.is_empty() {
+            return merged;
+        }
+
+        let remap = |id: Id| merged.get(&id).copied().unwrap_or(id);
+        let mut new_index = vec![None; self.nodes.len()];
+        let mut nodes = Vec::with_capacity(self.nodes.len() - merged.len());
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.outputs().iter().any(|out| merged.contains_key(out)) {
+                continue;
+            }
+            let inputs = node.inputs();
+            let node = if inputs.iter().any(|id| merged.contains_key(id)) {
+                let inputs: Vec<Id> = inputs.into_iter().map(remap).collect();
+                Rc::from(
+                    node.with_inputs(&inputs)
+                        .expect("deduplicate_constants: consumers were checked to be rewirable"),
+                )
+            } else {
+                node.clone()
+            };
+            new_index[i] = Some(nodes.len());
+            nodes.push(node);
+        }
+        self.set_nodes(nodes, &new_index);
+        merged
+    }
+
+    /// swaps in a rewritten node list, carrying `grad_sources` and
+    /// `checkpoints` over. `new_index[i]` is where old node `i` ended up, or
+    /// `None` if it was dropped.
+    pub(crate) fn set_nodes(&mut self, nodes: Node<D>, new_index: &[Option<usize>]) {
+        // a range maps to the kept nodes inside it.
+        let kept_before = |i: usize| new_index[..i].iter().flatten().count();
+        self.checkpoints = self
+            .checkpoints
+            .iter()
+            .map(|r| kept_before(r.start)..kept_before(r.end))
+            .collect();
+        self.forward_len = self.forward_len.map(kept_before);
+        self.nodes = nodes;
+        self.grad_sources = self
+            .grad_sources
+            .iter()
+            .filter_map(|(&grad, &fwd)| Some((new_index[grad]?, new_index[fwd]?)))
+            .collect();
+    }
+
+    /// counts nodes by op name, handy for checking what `#[trace]` lowered to.
+    pub fn op_histogram(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for node in &self.nodes {
+            *counts.entry(node.name().to_string()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// renders the graph as SSA-style text, one `%out = op(%in, ..)` line per node.
+    pub fn to_ssa(&self) -> String {
+        self.nodes
+            .iter()
+            .map(|node| ssa_line(node.as_ref()) + "\n")
+            .collect()
+    }
+
+    /// renders the graph in graphviz DOT format.
+    ///
+    /// data edges are solid. nodes emitted while differentiating get a dashed
+    /// edge from the forward node whose vjp produced them.
+    pub fn to_dot(&self) -> String {
+        let producers: HashMap<Id, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, node)| node.outputs().into_iter().map(move |out| (out, i)))
+            .collect();
+
+        let mut dot = String::from("digraph {\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            writeln!(dot, "    n{i} [label=\"{i}: {}\"];", node.name()).unwrap();
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            for inp in node.inputs() {
+                if let Some(src) = producers.get(&inp) {
+                    writeln!(dot, "    n{src} -> n{i};").unwrap();
+                }
+            }
+        }
+        let mut grad_edges: Vec<_> = self.grad_sources.iter().collect();
+        grad_edges.sort_unstable();
+        for (grad, fwd) in grad_edges {
+            writeln!(dot, "    n{fwd} -> n{grad} [style=dashed];").unwrap();
+        }
+        dot.push('}');
+        dot
+    }
+
+    /// rebuilds the graph at `f64` precision, keeping every node id intact.
+    pub fn to_f64(&self) -> Graph<f64, G>
+    where
+        G: Clone,
+    {
+        Graph {
+            nodes: self
+                .nodes
+                .iter()
+                .map(|op| Rc::from(op.retype_f64()))
+                .collect(),
+            params: self.params.clone(),
+            grad_sources: self.grad_sources.clone(),
+            checkpoints: self.checkpoints.clone(),
+            forward_len: self.forward_len,
+            max_nodes: self.max_nodes,
+            generator: self.generator.clone(),
+        }
+    }
 }
 
 impl Default for Graph {
@@ -37,7 +398,7 @@ impl Default for Graph {
     }
 }
 
-impl<D: Floating> Display for Graph<D> {
+impl<D: Floating, G: IdGenerator> Display for Graph<D, G> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         for (i, node) in self.nodes.iter().enumerate() {
             writeln!(
@@ -51,3 +412,23 @@ impl<D: Floating> Display for Graph<D> {
         Ok(())
     }
 }
+
+/// one node as `%out = op(%in, ..) attrs`, without a trailing newline.
+pub(crate) fn ssa_line<D: Floating>(node: &dyn Op<D>) -> String {
+    let join = |ids: Vec<Id>| {
+        ids.iter()
+            .map(|id| format!("%{}", id.as_usize()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut text = format!("{} = {}", join(node.outputs()), node.name());
+    let inputs = node.inputs();
+    if !inputs.is_empty() {
+        write!(text, "({})", join(inputs)).unwrap();
+    }
+    if let Some(attrs) = node.attrs() {
+        write!(text, " {attrs}").unwrap();
+    }
+    text
+}
@@ -57,4 +57,28 @@ pub mod generators {
             self.freelist.push_back(id.0);
         }
     }
+
+    /// hands out strictly increasing ids and never reuses released ones, so
+    /// the same build sequence always yields the same ids.
+    #[derive(Debug, Clone, Default)]
+    pub struct Monotonic {
+        counter: usize,
+    }
+
+    impl Monotonic {
+        pub fn new() -> Self {
+            Self { counter: 0 }
+        }
+    }
+
+    impl IdGenerator for Monotonic {
+        type Id = identity::Id;
+
+        fn fresh(&mut self) -> Self::Id {
+            self.counter += 1;
+            identity::Id(self.counter)
+        }
+
+        fn release(&mut self, _id: Self::Id) {}
+    }
 }
@@ -50,9 +50,17 @@ use core::fmt::Debug;
 
 use num_traits::{Float, NumOps};
 
+use crate::identity::IdGenerator;
+
 /// Blanket floating scalar trait for tensors.
 pub trait Floating: Debug + Float + NumOps {
     fn from_f64(val: f64) -> Self;
+    /// widens to `f64`, e.g. to accumulate without losing precision. the
+    /// infallible form of `ToPrimitive::to_f64`.
+    fn as_f64(self) -> f64 {
+        self.to_f64()
+            .expect("a floating value should always convert to f64")
+    }
 }
 
 impl Floating for f32 {
@@ -80,7 +88,8 @@ pub use chainrule_macros::trace;
 
 pub use crate::graph::Graph;
 pub use crate::identity::Id;
-pub use crate::tracing::function::TraceableFn;
+pub use crate::tracing::compiled::CompiledFn;
+pub use crate::tracing::function::{GradOptions, TraceableFn, Unreached};
 /// Core user types: Tensor wrapper, session, function graph.
 pub use crate::tracing::{Tensor, TraceSession, Tracer};
 
@@ -97,19 +106,79 @@ pub use crate::tracing::{Tensor, TraceSession, Tracer};
 ///
 /// let t_f = trace_fn::<f32>(f);
 /// ```
-pub fn trace_fn<D>(builder: fn(&mut TraceSession<D>) -> (Vec<Id>, Tracer)) -> TraceableFn<D>
+///
+/// A function with scalar hyperparameters is traced through a closure that
+/// supplies them: `trace_fn::<f32>(|sess| f(sess, 0.5))`.
+pub fn trace_fn<D>(
+    builder: impl FnOnce(&mut TraceSession<D>) -> (Vec<Id>, Tracer),
+) -> TraceableFn<D>
+where
+    D: Floating + 'static,
+{
+    trace_fn_in(Graph::<D>::new(), builder)
+}
+
+/// Like [`trace_fn`], but traces into `graph` instead of a fresh default one,
+/// so its settings apply: e.g. [`Graph::with_budget`] to cap the node count,
+/// or [`Graph::with_generator`] with a
+/// [`Monotonic`](crate::identity::generators::Monotonic) generator for
+/// reproducible ids. `graph` is expected to be empty.
+///
+/// Example:
+/// ```rust,ignore
+/// let t_f = trace_fn_in(Graph::<f32>::with_budget(1_000), f);
+/// ```
+pub fn trace_fn_in<D, G>(
+    mut graph: Graph<D, G>,
+    builder: impl FnOnce(&mut TraceSession<D, G>) -> (Vec<Id>, Tracer),
+) -> TraceableFn<D, G>
+where
+    D: Floating + 'static,
+    G: IdGenerator<Id = Id>,
+{
+    let mut sess = TraceSession::new(&mut graph);
+
+    let (inputs, output) = builder(&mut sess);
+    TraceableFn {
+        graph,
+        inputs,
+        outputs: vec![output.id()],
+    }
+}
+
+/// Gradient of a graph built by hand, without `#[trace]`.
+///
+/// `f` receives the session and `n_inputs` fresh inputs and returns the
+/// output; the result is the [`TraceableFn::grad`] of that function.
+///
+/// Example:
+/// ```rust,ignore
+/// let df = chainrule::grad::<f32>(
+///     |sess, xs| {
+///         let sq = sess.mul(xs[0], xs[0]);
+///         sess.sum(sq, vec![], false)
+///     },
+///     1,
+/// );
+/// ```
+pub fn grad<D>(
+    f: impl FnOnce(&mut TraceSession<D>, Vec<Tracer>) -> Tracer,
+    n_inputs: usize,
+) -> TraceableFn<D>
 where
     D: Floating + 'static,
 {
     let mut g = Graph::<D>::new();
     let mut sess = TraceSession::new(&mut g);
 
-    let (inputs, output) = builder(&mut sess);
+    let inputs: Vec<Tracer> = (0..n_inputs).map(|_| sess.input()).collect();
+    let output = f(&mut sess, inputs.clone());
     TraceableFn {
         graph: g,
-        inputs,
+        inputs: inputs.iter().map(Tracer::id).collect(),
         outputs: vec![output.id()],
     }
+    .grad()
 }
 
 /// A prelude that brings in the most important items.
@@ -120,7 +189,7 @@ where
 /// ```
 pub mod prelude {
     pub use crate::tracing::tracer::Item as _;
-    pub use crate::{Tensor, trace, trace_fn};
+    pub use crate::{Tensor, trace, trace_fn, trace_fn_in};
 }
 
 #[cfg(test)]
@@ -141,6 +210,34 @@ mod tests {
         assert!(close, "Tensors are not close.\nA: {:?}\nB: {:?}", a, b);
     }
 
+    /// Checks `traced.grad()` against central differences of the summed
+    /// outputs, one element of one input at a time.
+    pub(crate) fn assert_grad_matches_fd(
+        traced: &crate::TraceableFn<f64>,
+        inputs: &[ndarray::ArrayD<f64>],
+        eps: f64,
+        tol: f64,
+    ) {
+        let total = |inputs: Vec<ndarray::ArrayD<f64>>| -> f64 {
+            traced.run_packed(inputs).iter().map(|out| out.sum()).sum()
+        };
+        let grads = traced.grad().run_packed(inputs.to_vec());
+        for (which, (grad, input)) in grads.iter().zip(inputs).enumerate() {
+            // an unreached input gets a scalar zero.
+            let grad = grad.broadcast(input.shape()).unwrap();
+            for (idx, &analytic) in grad.indexed_iter() {
+                let (mut plus, mut minus) = (inputs.to_vec(), inputs.to_vec());
+                plus[which][&idx] += eps;
+                minus[which][&idx] -= eps;
+                let numeric = (total(plus) - total(minus)) / (2. * eps);
+                assert!(
+                    (analytic - numeric).abs() < tol,
+                    "input {which}{idx:?}: analytic {analytic} vs numeric {numeric}"
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_add_op() {
         #[trace]
@@ -311,6 +408,34 @@ mod tests {
         assert_eq!(grad_x, Array::ones(x.dim()).into_dyn());
     }
 
+    #[test]
+    fn test_sum_full_reduction_keep_dims() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.sum(vec![], true)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let x = arr2(&[[1., 2., 3.], [4., 5., 6.]]).into_dyn();
+
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, arr2(&[[21.]]).into_dyn());
+
+        let (grad_x,) = traced.grad().eval()(&x);
+        assert_eq!(grad_x, Array::ones(x.dim()).into_dyn());
+    }
+
+    #[test]
+    #[should_panic(expected = "sum: duplicate axis 1 in [1, 1]")]
+    fn test_sum_rejects_duplicate_axes() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.sum(vec![1, 1], false)
+        }
+
+        let _ = trace_fn::<f32>(f);
+    }
+
     #[test]
     fn test_log_op() {
         #[trace]
@@ -410,4 +535,1668 @@ mod tests {
         let (grad2,) = grad_fn.grad().eval()(&x);
         assert_all_close(&grad2, &array![2.0, 2.0].into_dyn(), 1e-6);
     }
+
+    #[test]
+    fn test_div_grad_scales_by_upstream() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor, w: Tensor) -> Tensor {
+            (x / y * w).sum(vec![], false)
+        }
+
+        // y broadcasts along the rows of x, so its gradient sums over them.
+        let x = arr2(&[[1., 2.], [3., 4.]]).into_dyn();
+        let y = arr1(&[2., -4.]).into_dyn();
+        let w = arr2(&[[1., 0.5], [-1., 2.]]).into_dyn();
+        let (gx, gy, _) = trace_fn::<f32>(f).grad().eval()((&x, &y, &w));
+        assert_all_close(&gx, &(&w / &y), 1e-6);
+        let expected_gy = (-(&x * &w) / (&y * &y)).sum_axis(ndarray::Axis(0));
+        assert_all_close(&gy, &expected_gy, 1e-6);
+    }
+
+    /// Full Hessian of a scalar `f(x, y)` over vectors, ordered `[x.., y..]`,
+    /// one row per one-hot cotangent on the first derivative.
+    fn hessian(
+        f: &crate::TraceableFn<f32>,
+        x: &ndarray::ArrayD<f32>,
+        y: &ndarray::ArrayD<f32>,
+    ) -> Array<f32, Ix2> {
+        let n = x.len();
+        let grad = f.grad();
+        let mut h = Array::zeros((2 * n, 2 * n));
+        for row in 0..2 * n {
+            let mut seeds = vec![Array::zeros(x.raw_dim()), Array::zeros(y.raw_dim())];
+            seeds[row / n][row % n] = 1.;
+            let (hx, hy): (ndarray::ArrayD<f32>, ndarray::ArrayD<f32>) =
+                grad.grad_with_seeds(seeds).eval()((x, y));
+            // an input the row does not depend on comes back as a scalar zero.
+            let hx = hx.broadcast(x.raw_dim()).unwrap().to_owned();
+            let hy = hy.broadcast(y.raw_dim()).unwrap().to_owned();
+            for (col, v) in hx.iter().chain(hy.iter()).enumerate() {
+                h[[row, col]] = *v;
+            }
+        }
+        h
+    }
+
+    #[test]
+    fn test_hessian_mixed_partials_mul() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            (x * y).sum(vec![], false)
+        }
+
+        // H = [[0, I], [I, 0]]
+        let x = arr1(&[1., -2., 3.]).into_dyn();
+        let y = arr1(&[0.5, 4., -1.]).into_dyn();
+        let h = hessian(&trace_fn::<f32>(f), &x, &y);
+        let mut expected = Array::zeros((6, 6));
+        for i in 0..3 {
+            expected[[i, 3 + i]] = 1.;
+            expected[[3 + i, i]] = 1.;
+        }
+        assert_eq!(h, expected);
+
+        // `grad().grad()` differentiates the sum of both gradients: the row sums.
+        let (gx, gy) = trace_fn::<f32>(f).grad().grad().eval()((&x, &y));
+        assert_eq!(gx, Array::ones(3).into_dyn());
+        assert_eq!(gy, Array::ones(3).into_dyn());
+    }
+
+    #[test]
+    fn test_hessian_mixed_partials_mul_square() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            (x * x * y).sum(vec![], false)
+        }
+
+        // d2f/dx2 = diag(2y), d2f/dxdy = diag(2x), d2f/dy2 = 0
+        let x = arr1(&[1., -2., 3.]).into_dyn();
+        let y = arr1(&[0.5, 4., -1.]).into_dyn();
+        let h = hessian(&trace_fn::<f32>(f), &x, &y);
+        let mut expected = Array::zeros((6, 6));
+        for i in 0..3 {
+            expected[[i, i]] = 2. * y[i];
+            expected[[i, 3 + i]] = 2. * x[i];
+            expected[[3 + i, i]] = 2. * x[i];
+        }
+        assert_all_close(&h.clone().into_dyn(), &expected.into_dyn(), 1e-6);
+        assert_eq!(h, h.t());
+    }
+
+    #[test]
+    fn test_hessian_mixed_partials_div() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            (x / y).sum(vec![], false)
+        }
+
+        // d2f/dx2 = 0, d2f/dxdy = diag(-1/y^2), d2f/dy2 = diag(2x/y^3)
+        let x = arr1(&[1., -2., 3.]).into_dyn();
+        let y = arr1(&[0.5, 4., -1.]).into_dyn();
+        let h = hessian(&trace_fn::<f32>(f), &x, &y);
+        let mut expected = Array::zeros((6, 6));
+        for i in 0..3 {
+            let (xi, yi): (f32, f32) = (x[i], y[i]);
+            expected[[i, 3 + i]] = -1. / (yi * yi);
+            expected[[3 + i, i]] = -1. / (yi * yi);
+            expected[[3 + i, 3 + i]] = 2. * xi / (yi * yi * yi);
+        }
+        assert_all_close(&h.into_dyn(), &expected.into_dyn(), 1e-5);
+    }
+
+    /// a downstream op implementing only the required `Op` methods.
+    #[derive(Debug, Clone)]
+    struct MinimalOp {
+        inp: crate::Id,
+        out: crate::Id,
+    }
+
+    impl crate::ops::Op<f32> for MinimalOp {
+        fn eval(&self, ctx: &mut crate::context::Context<f32>) {
+            let x = ctx.checked_get(&self.inp).clone();
+            ctx.insert(self.out, x);
+        }
+        fn name(&self) -> &str {
+            "minimal"
+        }
+        fn vjp(
+            &self,
+            _g: &mut crate::Graph<f32>,
+            out_grads: &[crate::Id],
+        ) -> Option<Vec<crate::Id>> {
+            Some(out_grads.to_vec())
+        }
+        fn inputs(&self) -> Vec<crate::Id> {
+            vec![self.inp]
+        }
+        fn outputs(&self) -> Vec<crate::Id> {
+            vec![self.out]
+        }
+    }
+
+    fn minimal_fn() -> crate::TraceableFn<f32> {
+        use crate::{Graph, TraceSession, TraceableFn};
+
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let x = sess.input();
+        let out = g.fresh();
+        g.push(Box::new(MinimalOp { inp: x.id(), out }));
+        TraceableFn {
+            graph: g,
+            inputs: vec![x.id()],
+            outputs: vec![out],
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "minimal: retype_f64 is not implemented for this op")]
+    fn test_op_without_retype_f64_panics_on_upcast() {
+        let traced = minimal_fn();
+        let x = arr1(&[1., 2.]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, x);
+        let _ = traced.to_f64();
+    }
+
+    #[test]
+    #[should_panic(expected = "minimal: shape_rule is not implemented for this op")]
+    fn test_op_without_shape_rule_panics_in_shape_inference() {
+        let traced = minimal_fn();
+        let x = arr1(&[1., 2.]).into_dyn();
+        let (gx,) = traced.grad().eval()(&x);
+        assert_eq!(gx, arr1(&[1., 1.]).into_dyn());
+        let _ = traced.output_shapes(&[vec![2]]);
+    }
+
+    #[test]
+    fn test_to_f64_matches_f32() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            (x * y + 1.5).exp().sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let upcast = traced.to_f64();
+
+        let x = arr1(&[0.1f32, -0.2, 0.3]).into_dyn();
+        let y = arr1(&[0.4f32, 0.5, -0.6]).into_dyn();
+        let x64 = x.mapv(f64::from);
+        let y64 = y.mapv(f64::from);
+
+        let (out,) = traced.eval()((&x, &y));
+        let (out64,) = upcast.eval()((&x64, &y64));
+        assert_all_close(&out, &out64.mapv(|v| v as f32), 1e-5);
+
+        let (grad_x, _) = traced.grad().eval()((&x, &y));
+        let (grad_x64, _) = upcast.grad().eval()((&x64, &y64));
+        assert_all_close(&grad_x, &grad_x64.mapv(|v| v as f32), 1e-5);
+    }
+
+    #[test]
+    fn test_eval_batched_dense() {
+        #[trace]
+        fn dense(w: Tensor, x: Tensor, b: Tensor) -> Tensor {
+            x.matmul(w) + b
+        }
+
+        let traced = trace_fn::<f32>(dense);
+        let w = arr2(&[[1., 2.], [3., 4.]]);
+        let b = arr1(&[0.5, -0.5]);
+        let x = Array::from_shape_fn((8, 2), |(i, j)| (i * 2 + j) as f32);
+
+        let w_bc = w.broadcast((8, 2, 2)).unwrap().to_owned();
+        let b_bc = b.broadcast((8, 2)).unwrap().to_owned();
+        let (out,) = traced.eval_batched()((&w_bc, &x, &b_bc));
+
+        let expected = (x.dot(&w) + &b).into_dyn();
+        assert_all_close(&out, &expected, 1e-6);
+    }
+
+    #[test]
+    fn test_grad_params_only() {
+        use crate::{Graph, TraceSession, TraceableFn};
+
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let x = sess.input();
+        let w = sess.parameter();
+        let prod = sess.mul(x, w);
+        let y = sess.sum(prod, vec![], false);
+
+        let traced = TraceableFn {
+            graph: g,
+            inputs: vec![x.id(), w.id()],
+            outputs: vec![y.id()],
+        };
+
+        let x = arr1(&[1., 2., 3.]).into_dyn();
+        let w = arr1(&[4., 5., 6.]).into_dyn();
+        let (grad_w,) = traced.grad_params().eval()((&x, &w));
+        assert_eq!(grad_w, x);
+    }
+
+    #[test]
+    fn test_dense_backward_node_counts() {
+        #[trace]
+        fn dense(w: Tensor, x: Tensor, b: Tensor) -> Tensor {
+            x.matmul(w) + b
+        }
+
+        let traced = trace_fn::<f32>(dense);
+        assert_eq!(traced.count_backward_nodes(), 0);
+
+        // the implicit sum to a scalar: reshape and broadcast back; add: one
+        // reduce_to_like per operand; matmul: one transpose, one matmul and one
+        // reduce_to_like (for batch broadcasting) per operand. no vjp recomputes
+        // a forward value.
+        let grad = traced.grad();
+        assert_eq!(grad.count_backward_nodes(), 10);
+        let backward: Vec<&str> = grad
+            .graph
+            .grad_sources
+            .keys()
+            .map(|&i| grad.graph.nodes[i].name())
+            .collect();
+        let count = |name: &str| backward.iter().filter(|&&n| n == name).count();
+        assert_eq!(count("transpose_default"), 2);
+        assert_eq!(count("matmul"), 2);
+        assert_eq!(count("reduce_to_like"), 4);
+
+        // the whole gradient graph, forward pass included.
+        let histogram = grad.graph.op_histogram();
+        assert_eq!(histogram["matmul"], 3);
+        assert_eq!(histogram["transpose_default"], 2);
+    }
+
+    #[test]
+    fn test_to_dot_grad_edges() {
+        #[trace]
+        fn f(a: Tensor, b: Tensor) -> Tensor {
+            a.matmul(b)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let fwd_dot = traced.graph.to_dot();
+        assert!(fwd_dot.contains("n0 -> n2;"));
+        assert!(!fwd_dot.contains("style=dashed"));
+
+        // node 2 is the forward matmul; its vjp emits two transposes, two
+        // matmuls and two reduce_to_likes.
+        let bwd_dot = traced.grad().graph.to_dot();
+        let matmul_grad_edges = bwd_dot
+            .lines()
+            .filter(|l| l.trim_start().starts_with("n2 -> ") && l.contains("style=dashed"))
+            .count();
+        assert_eq!(matmul_grad_edges, 6);
+    }
+
+    #[test]
+    fn test_compound_assign() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            let mut acc = x;
+            for _ in 0..3 {
+                acc += x;
+            }
+            acc *= y;
+            acc -= y;
+            acc /= 2.0;
+            acc.sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let x = arr1(&[1., 2.]).into_dyn();
+        let y = arr1(&[3., 4.]).into_dyn();
+
+        // ((4x) * y - y) / 2
+        let (out,) = traced.eval()((&x, &y));
+        let expected = arr0(((4.0 * &x * &y - &y) / 2.0).sum()).into_dyn();
+        assert_all_close(&out, &expected, 1e-6);
+
+        let (grad_x, grad_y) = traced.grad().eval()((&x, &y));
+        assert_all_close(&grad_x, &(2.0 * &y), 1e-6);
+        assert_all_close(&grad_y, &((4.0 * &x - 1.0) / 2.0), 1e-6);
+    }
+
+    #[test]
+    fn test_for_loop_relu_stack() {
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            let layers = 2;
+            let mut h = x;
+            for _ in 0..layers + 1 {
+                h = (h * w).relu();
+            }
+            h.sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let relus = traced
+            .graph
+            .nodes
+            .iter()
+            .filter(|n| n.name() == "relu")
+            .count();
+        assert_eq!(relus, 3);
+
+        let x = arr1(&[1., -1., 2.]).into_dyn();
+        let w = arr1(&[2., 2., 0.5]).into_dyn();
+
+        // relu(relu(relu(x*w)*w)*w) = x * w^3 where x*w > 0
+        let (out,) = traced.eval()((&x, &w));
+        assert_all_close(&out, &arr0(8. + 0. + 0.25).into_dyn(), 1e-6);
+
+        let (grad_x, _) = traced.grad().eval()((&x, &w));
+        assert_all_close(&grad_x, &array![8., 0., 0.125].into_dyn(), 1e-6);
+    }
+
+    #[test]
+    fn test_flops_dense() {
+        #[trace]
+        fn dense(w: Tensor, x: Tensor, b: Tensor) -> Tensor {
+            x.matmul(w) + b
+        }
+
+        let traced = trace_fn::<f32>(dense);
+        // (4,2) @ (2,3): 2 * 4 * 3 * 2 = 48, plus one add per (4,3) output element.
+        let flops = traced.flops(&[vec![2, 3], vec![4, 2], vec![3]]);
+        assert_eq!(flops, 48 + 12);
+    }
+
+    #[test]
+    fn test_negative_axis_reductions() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.sum(vec![-1], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+
+        let x1 = arr1(&[1., 2., 3.]).into_dyn();
+        let (out,) = traced.eval()(&x1);
+        assert_eq!(out, arr0(6.).into_dyn());
+
+        let x2 = arr2(&[[1., 2., 3.], [4., 5., 6.]]).into_dyn();
+        let (out,) = traced.eval()(&x2);
+        assert_eq!(out, arr1(&[6., 15.]).into_dyn());
+        let (grad_x,) = traced.grad().eval()(&x2);
+        assert_eq!(grad_x, Array::ones(x2.dim()).into_dyn());
+
+        let x3 = Array::from_shape_fn((2, 2, 2), |(i, j, k)| (i * 4 + j * 2 + k) as f32).into_dyn();
+        let (out,) = traced.eval()(&x3);
+        assert_eq!(out, x3.sum_axis(ndarray::Axis(2)));
+
+        #[trace]
+        fn g(x: Tensor) -> Tensor {
+            x.mean(vec![-1], true).max(vec![-2], false)
+        }
+
+        let traced = trace_fn::<f32>(g);
+        let (out,) = traced.eval()(&x2);
+        assert_eq!(out, arr1(&[5.]).into_dyn());
+        let (grad_x,) = traced.grad().eval()(&x2);
+        let third = 1. / 3.;
+        assert_all_close(
+            &grad_x,
+            &array![[0., 0., 0.], [third, third, third]].into_dyn(),
+            1e-6,
+        );
+    }
+
+    #[test]
+    fn test_output_shapes_dense() {
+        #[trace]
+        fn dense(w: Tensor, x: Tensor, b: Tensor) -> Tensor {
+            x.matmul(w) + b
+        }
+
+        let traced = trace_fn::<f32>(dense);
+        let shapes = traced.output_shapes(&[vec![2, 3], vec![4, 2], vec![3]]);
+        assert_eq!(shapes, vec![vec![4, 3]]);
+
+        let grad_shapes = traced
+            .grad()
+            .output_shapes(&[vec![2, 3], vec![4, 2], vec![3]]);
+        assert_eq!(grad_shapes, vec![vec![2, 3], vec![4, 2], vec![3]]);
+    }
+
+    #[test]
+    fn test_infer_shapes_matches_eval() {
+        #[trace]
+        fn dense(w: Tensor, x: Tensor, b: Tensor) -> Tensor {
+            (x.matmul(w) + b).relu().mean(vec![-1], false)
+        }
+
+        // every intermediate of the forward and backward graphs, not just the outputs.
+        let w = Array::from_shape_fn((2, 3), |(i, j)| (i + j) as f32 - 1.).into_dyn();
+        let x = Array::from_shape_fn((4, 2), |(i, j)| (i * 2 + j) as f32 * 0.5).into_dyn();
+        let b = arr1(&[0.5, -1., 2.]).into_dyn();
+        let traced = trace_fn::<f32>(dense);
+        for f in [traced.clone(), traced.grad()] {
+            let shapes = f
+                .graph
+                .infer_shapes(&f.inputs, &[vec![2, 3], vec![4, 2], vec![3]]);
+            for node in &f.graph.nodes {
+                for out in node.outputs() {
+                    let value = f.eval_at((&w, &x, &b), out);
+                    assert_eq!(shapes[&out], value.shape(), "{}", node.name());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_monotonic_ids_reproducible() {
+        use crate::identity::IdGenerator;
+        use crate::identity::generators::{FreeList, Monotonic};
+        use crate::ops::{Add, Input, Mul};
+        use crate::{Graph, Id};
+
+        // a scratch id is released mid-build, as a pruning pass would.
+        fn build<G: IdGenerator<Id = Id>>(generator: G) -> Graph<f32, G> {
+            let mut g = Graph::with_generator(generator);
+            let (x, y) = (g.fresh(), g.fresh());
+            g.push(Box::new(Input::new(x)));
+            g.push(Box::new(Input::new(y)));
+            let scratch = g.fresh();
+            g.release(scratch);
+            let sum = g.fresh();
+            g.push(Box::new(Add::new(x, y, sum)));
+            let prod = g.fresh();
+            g.push(Box::new(Mul::new(sum, x, prod)));
+            g
+        }
+
+        let first = build(Monotonic::new()).to_string();
+        let second = build(Monotonic::new()).to_string();
+        assert_eq!(first.as_bytes(), second.as_bytes());
+        assert!(first.contains("add [Id(1), Id(2)] -> [Id(4)]"));
+
+        // the free list hands the released id straight back out.
+        let reused = build(FreeList::new()).to_string();
+        assert!(reused.contains("add [Id(1), Id(2)] -> [Id(3)]"));
+    }
+
+    #[test]
+    fn test_trace_into_monotonic_graph() {
+        use crate::Graph;
+        use crate::identity::generators::Monotonic;
+
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            (x * y + x).exp()
+        }
+
+        let first = trace_fn_in(Graph::<f32, _>::with_generator(Monotonic::new()), f);
+        let second = trace_fn_in(Graph::<f32, _>::with_generator(Monotonic::new()), f);
+        assert_eq!(first.graph.to_ssa(), second.graph.to_ssa());
+        let ids: Vec<usize> = first
+            .ops()
+            .flat_map(|(_, _, outs)| outs)
+            .map(|id| id.as_usize())
+            .collect();
+        assert!(ids.is_sorted());
+
+        let x = arr1(&[1., 2.]).into_dyn();
+        let y = arr1(&[0.5, -1.]).into_dyn();
+        let (out,) = first.eval()((&x, &y));
+        assert_all_close(&out, &(&x * &y + &x).mapv(f32::exp), 1e-6);
+    }
+
+    #[test]
+    fn test_bind_inputs() {
+        #[trace]
+        fn dense(w: Tensor, x: Tensor, b: Tensor) -> Tensor {
+            x.matmul(w) + b
+        }
+
+        let traced = trace_fn::<f32>(dense);
+        let w = arr2(&[[1., 2.], [3., 4.]]).into_dyn();
+        let b = arr1(&[0.5, -0.5]).into_dyn();
+        let x = arr2(&[[1., 1.], [2., 0.]]).into_dyn();
+
+        let serving = traced.bind_inputs(&[(0, w.clone()), (2, b.clone())]);
+        assert_eq!(serving.inputs, vec![traced.inputs[1]]);
+
+        let (out,) = serving.eval()(&x);
+        let (expected,) = traced.eval()((&w, &x, &b));
+        assert_eq!(out, expected);
+
+        // the bound weights are constants now, so only x gets a gradient.
+        let (grad_x,) = serving.grad().eval()(&x);
+        assert_eq!(grad_x, arr2(&[[3., 7.], [3., 7.]]).into_dyn());
+    }
+
+    #[test]
+    fn test_op_histogram() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor, z: Tensor) -> Tensor {
+            x + y + z
+        }
+
+        #[trace]
+        fn square(x: Tensor) -> Tensor {
+            x * x
+        }
+
+        let hist = trace_fn::<f32>(f).graph.op_histogram();
+        assert_eq!(hist["add"], 2);
+        assert_eq!(hist["input"], 3);
+        assert_eq!(hist.len(), 2);
+
+        let hist = trace_fn::<f32>(square).graph.op_histogram();
+        assert_eq!(hist["mul"], 1);
+        assert_eq!(hist.len(), 2);
+    }
+
+    #[test]
+    fn test_eval_flat_vec_and_shape() {
+        #[trace]
+        fn multiply(x: Tensor, y: Tensor) -> Tensor {
+            x * y
+        }
+
+        let f = trace_fn::<f32>(multiply);
+        let x = (vec![1., 2., 3., 4., 5., 6.], vec![2, 3]);
+        let y = (vec![2.; 6], vec![2, 3]);
+
+        let (out,) = f.eval()((&x, &y));
+        assert_eq!(out, arr2(&[[2., 4., 6.], [8., 10., 12.]]).into_dyn());
+    }
+
+    #[test]
+    #[should_panic(expected = "5 values do not fill a tensor of shape [2, 3]")]
+    fn test_eval_flat_vec_shape_mismatch() {
+        #[trace]
+        fn multiply(x: Tensor, y: Tensor) -> Tensor {
+            x * y
+        }
+
+        let f = trace_fn::<f32>(multiply);
+        let x = (vec![1.; 5], vec![2, 3]);
+        let _: (ndarray::ArrayD<f32>,) = f.eval()((&x, &x));
+    }
+
+    #[test]
+    fn test_to_ssa() {
+        #[trace]
+        fn f(w: Tensor, x: Tensor) -> Tensor {
+            x.matmul(w) * 2.0
+        }
+
+        let traced = trace_fn::<f32>(f);
+        assert_eq!(
+            traced.graph.to_ssa(),
+            "%1 = input\n\
+             %2 = input\n\
+             %3 = matmul(%2, %1)\n\
+             %4 = const 2.0\n\
+             %5 = mul(%3, %4)\n"
+        );
+    }
+
+    #[test]
+    fn test_is_linear_flags() {
+        use crate::Graph;
+        use crate::ops::{
+            Add, Input, MatMul, Mul, Neg, Op, Reshape, Sub, Sum, TransposeDefault,
+            broadcast::Broadcast, exp::Exp, relu::ReLU,
+        };
+
+        let mut g = Graph::<f32>::new();
+        let [a, b, c] = [g.fresh(), g.fresh(), g.fresh()];
+        let linear: Vec<Box<dyn Op<f32>>> = vec![
+            Box::new(Add::new(a, b, c)),
+            Box::new(Sub::new(a, b, c)),
+            Box::new(Neg::new(a, c)),
+            Box::new(TransposeDefault::new(a, c)),
+            Box::new(Reshape::new(a, c, vec![4])),
+            Box::new(Sum::new(a, c, vec![0], false)),
+            Box::new(Broadcast::new(a, c, vec![2, 2])),
+        ];
+        let nonlinear: Vec<Box<dyn Op<f32>>> = vec![
+            Box::new(Mul::new(a, b, c)),
+            Box::new(MatMul::new(a, b, c)),
+            Box::new(Exp::new(a, c)),
+            Box::new(ReLU::new(a, c)),
+            Box::new(Input::new(c)),
+        ];
+        for op in &linear {
+            assert!(op.is_linear(), "{} should be linear", op.name());
+        }
+        for op in &nonlinear {
+            assert!(!op.is_linear(), "{} should not be linear", op.name());
+        }
+    }
+
+    #[test]
+    fn test_compiled_matches_eval() {
+        #[trace]
+        fn mlp(w1: Tensor, w2: Tensor, x: Tensor) -> Tensor {
+            x.matmul(w1).relu().matmul(w2).sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(mlp);
+        let w1 = Array::from_shape_fn((3, 4), |(i, j)| (i as f32 - j as f32) * 0.3).into_dyn();
+        let w2 = Array::from_shape_fn((4, 2), |(i, j)| (i + j) as f32 * 0.1).into_dyn();
+        let x = Array::from_shape_fn((5, 3), |(i, j)| (i * 3 + j) as f32 * 0.2).into_dyn();
+
+        let compiled = traced.compile();
+        let (out,) = compiled.eval()((&w1, &w2, &x));
+        let (expected,) = traced.eval()((&w1, &w2, &x));
+        assert_eq!(out, expected);
+
+        let grad = traced.grad();
+        let compiled_grad = grad.compile();
+        // nodes that only feed the summed forward output are pruned.
+        assert!(compiled_grad.len() < grad.graph.nodes.len());
+
+        let outs: (_, _, _) = compiled_grad.eval()((&w1, &w2, &x));
+        let expected: (_, _, _) = grad.eval()((&w1, &w2, &x));
+        assert_eq!(outs, expected);
+    }
+
+    #[test]
+    fn test_sum_stable_accumulation() {
+        #[trace]
+        fn naive(x: Tensor) -> Tensor {
+            x.sum(vec![], false)
+        }
+
+        #[trace]
+        fn stable(x: Tensor) -> Tensor {
+            x.sum_stable(vec![], false)
+        }
+
+        #[trace]
+        fn stable_mean(x: Tensor) -> Tensor {
+            x.mean_stable(vec![0], false)
+        }
+
+        let n = 10_000_000;
+        let x = Array::from_elem(n, 0.1f32).into_dyn();
+        let exact = n as f64 * 0.1f32 as f64;
+
+        let (naive,) = trace_fn::<f32>(naive).eval()(&x);
+        let (stable,) = trace_fn::<f32>(stable).eval()(&x);
+        let naive_err = (naive.item() as f64 - exact).abs();
+        let stable_err = (stable.item() as f64 - exact).abs();
+        assert!(
+            stable_err < naive_err,
+            "stable error {stable_err} should beat naive error {naive_err}"
+        );
+        assert!(stable_err / exact < 1e-7);
+
+        let (mean,) = trace_fn::<f32>(stable_mean).eval()(&x);
+        assert_eq!(mean.item(), 0.1f32);
+    }
+
+    #[test]
+    fn test_grad_output_selects_one_output() {
+        use crate::{Graph, TraceSession, TraceableFn};
+
+        // outputs: (x * y, x * x)
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let x = sess.input();
+        let y = sess.input();
+        let xy = sess.mul(x, y);
+        let xx = sess.mul(x, x);
+
+        let traced = TraceableFn {
+            graph: g,
+            inputs: vec![x.id(), y.id()],
+            outputs: vec![xy.id(), xx.id()],
+        };
+
+        let x = arr1(&[1., 2.]).into_dyn();
+        let y = arr1(&[3., 5.]).into_dyn();
+
+        let (dx, dy) = traced.grad_output(0).eval()((&x, &y));
+        assert_eq!(dx, y);
+        assert_eq!(dy, x);
+
+        let (dx, dy) = traced.grad_output(1).eval()((&x, &y));
+        assert_eq!(dx, arr1(&[2., 4.]).into_dyn());
+        assert_eq!(dy, arr0(0.).into_dyn());
+
+        // grad() still sums both outputs.
+        let (dx, _) = traced.grad().eval()((&x, &y));
+        assert_eq!(dx, arr1(&[5., 9.]).into_dyn());
+    }
+
+    #[test]
+    fn test_traceable_fn_debug_summary() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            (x * y).sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        assert_eq!(
+            format!("{traced:?}"),
+            "TraceableFn { ops: 4, inputs: 2, outputs: 1 }"
+        );
+    }
+
+    #[test]
+    fn test_reduction_axis_forms() {
+        #[trace]
+        fn arrays(x: Tensor) -> Tensor {
+            x.mean([1], false) + x.max([1], false) + x.sum([-1], false)
+        }
+
+        #[trace]
+        fn vecs(x: Tensor) -> Tensor {
+            x.mean(vec![1], false) + x.max(vec![1], false) + x.sum(vec![-1], false)
+        }
+
+        #[trace]
+        fn slices(x: Tensor) -> Tensor {
+            x.mean(&[1][..], false) + x.max(&[1][..], false) + x.sum(&[-1][..], false)
+        }
+
+        let x = arr2(&[[1., 5., 3.], [4., 0., 2.]]).into_dyn();
+        let expected = arr1(&[3. + 5. + 9., 2. + 4. + 6.]).into_dyn();
+        for f in [arrays, vecs, slices] {
+            let (out,) = trace_fn::<f32>(f).eval()(&x);
+            assert_eq!(out, expected);
+        }
+    }
+
+    #[test]
+    fn test_grad_shares_forward_nodes() {
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            (x.matmul(w).exp() * 2.0).sum([], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let grad = traced.grad();
+
+        // the forward ops are shared with the traced fn, not copied.
+        let fwd = traced.graph.nodes.len();
+        assert!(grad.graph.nodes.len() > fwd);
+        for (a, b) in traced.graph.nodes.iter().zip(&grad.graph.nodes[..fwd]) {
+            assert!(std::rc::Rc::ptr_eq(a, b));
+        }
+
+        let x = arr2(&[[0.1, 0.2], [0.3, -0.4]]).into_dyn();
+        let w = arr2(&[[0.5, -0.1], [0.2, 0.3]]).into_dyn();
+        let (gx, gw) = grad.eval()((&x, &w));
+
+        let e = x.clone().into_dimensionality::<Ix2>().unwrap();
+        let e = e
+            .dot(&w.clone().into_dimensionality::<Ix2>().unwrap())
+            .mapv(f32::exp)
+            * 2.0;
+        let w2 = w.into_dimensionality::<Ix2>().unwrap();
+        let x2 = x.into_dimensionality::<Ix2>().unwrap();
+        assert_all_close(&gx, &e.dot(&w2.t()).into_dyn(), 1e-5);
+        assert_all_close(&gw, &x2.t().dot(&e).into_dyn(), 1e-5);
+    }
+
+    #[test]
+    fn test_eval_with_varying_batch() {
+        #[trace]
+        fn dense(w: Tensor, x: Tensor, b: Tensor) -> Tensor {
+            x.matmul(w) + b
+        }
+
+        #[trace]
+        fn flatten_back(x: Tensor, y: Tensor) -> Tensor {
+            x.reshape_like(y) * 2.0
+        }
+
+        let dense = trace_fn::<f32>(dense);
+        let grad = dense.grad();
+        let w = arr2(&[[1., 2.], [3., 4.]]).into_dyn();
+        let b = arr1(&[1., -1.]).into_dyn();
+        for batch in [2, 5] {
+            let x = Array::from_shape_fn((batch, 2), |(i, j)| (i + j) as f32).into_dyn();
+            let (out,) = dense.eval()((&w, &x, &b));
+            let expected = x
+                .clone()
+                .into_dimensionality::<Ix2>()
+                .unwrap()
+                .dot(&w.clone().into_dimensionality::<Ix2>().unwrap())
+                + &b;
+            assert_eq!(out, expected.into_dyn());
+
+            let (_, _, gb) = grad.eval()((&w, &x, &b));
+            assert_eq!(gb, arr1(&[batch as f32, batch as f32]).into_dyn());
+        }
+
+        let f = trace_fn::<f32>(flatten_back);
+        for batch in [2, 5] {
+            let x = Array::from_shape_fn(batch * 3, |i| i as f32).into_dyn();
+            let y = Array::<f32, _>::zeros((batch, 3)).into_dyn();
+            let (out,) = f.eval()((&x, &y));
+            assert_eq!(out.shape(), &[batch, 3]);
+        }
+    }
+
+    #[test]
+    fn test_eval_at_intermediate() {
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            x.matmul(w).relu()
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let pre_activation = traced.graph.nodes.last().unwrap().inputs()[0];
+
+        let x = arr2(&[[1., -2.], [0.5, 1.]]).into_dyn();
+        let w = arr2(&[[1., 0.], [0., 1.]]).into_dyn();
+        assert_eq!(traced.eval_at((&x, &w), pre_activation), x);
+        assert_eq!(traced.eval_at((&x, &w), traced.inputs[1]), w);
+
+        let (out,) = traced.eval()((&x, &w));
+        assert_eq!(out, arr2(&[[1., 0.], [0.5, 1.]]).into_dyn());
+    }
+
+    #[test]
+    fn test_traceable_fn_introspection() {
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            x.matmul(w).relu()
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let [x, w] = traced.input_ids() else {
+            panic!("expected two inputs")
+        };
+        let [y] = traced.output_ids() else {
+            panic!("expected one output")
+        };
+
+        let ops: Vec<_> = traced.ops().collect();
+        let names: Vec<&str> = ops.iter().map(|(name, _, _)| *name).collect();
+        assert_eq!(names, ["input", "input", "matmul", "relu"]);
+
+        let (_, mm_in, mm_out) = &ops[2];
+        assert_eq!(mm_in, &[*x, *w]);
+        let (_, relu_in, relu_out) = &ops[3];
+        assert_eq!(relu_in, mm_out);
+        assert_eq!(relu_out, &[*y]);
+    }
+
+    #[test]
+    fn test_grad_with_seeds() {
+        use crate::{Graph, TraceSession, TraceableFn};
+
+        // outputs: (x * y, exp(x))
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let x = sess.input();
+        let y = sess.input();
+        let xy = sess.mul(x, y);
+        let ex = sess.exp(x);
+
+        let traced = TraceableFn {
+            graph: g,
+            inputs: vec![x.id(), y.id()],
+            outputs: vec![xy.id(), ex.id()],
+        };
+
+        let x = arr1(&[0.5, -1., 2.]).into_dyn();
+        let y = arr1(&[3., 4., -2.]).into_dyn();
+
+        let ones = vec![Array::ones(3).into_dyn(), Array::ones(3).into_dyn()];
+        let (gx, gy) = traced.grad_with_seeds(ones).eval()((&x, &y));
+        let (ex, ey) = traced.grad().eval()((&x, &y));
+        assert_all_close(&gx, &ex, 1e-6);
+        assert_all_close(&gy, &ey, 1e-6);
+
+        // d(x * y) scaled by the first seed; the second output is ignored.
+        let seeds = vec![arr1(&[1., 2., 3.]).into_dyn(), Array::zeros(3).into_dyn()];
+        let (gx, gy) = traced.grad_with_seeds(seeds).eval()((&x, &y));
+        assert_eq!(gx, arr1(&[3., 8., -6.]).into_dyn());
+        assert_eq!(gy, arr1(&[0.5, -2., 6.]).into_dyn());
+    }
+
+    #[test]
+    fn test_peak_memory() {
+        #[trace]
+        fn chain(x: Tensor) -> Tensor {
+            x.exp().exp().sum([], false)
+        }
+
+        #[trace]
+        fn branch(x: Tensor) -> Tensor {
+            x.exp() * x.log()
+        }
+
+        // the input plus two chained exps; the first is freed before the sum.
+        assert_eq!(trace_fn::<f32>(chain).peak_memory(&[vec![4]]), 4 + 4 + 4);
+        // the input, exp(x) and log(x) are all alive when the product is made.
+        assert_eq!(trace_fn::<f32>(branch).peak_memory(&[vec![2, 5]]), 40);
+    }
+
+    #[test]
+    fn test_jvp_eval_matches_finite_difference() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            x * y.exp() + x.matmul(y)
+        }
+
+        let traced = trace_fn::<f64>(f);
+        let x = arr2(&[[0.5, -1.], [2., 0.25]]).into_dyn();
+        let y = arr2(&[[0.1, 0.3], [-0.2, 0.4]]).into_dyn();
+        let vx = arr2(&[[1., 0.5], [-1., 2.]]).into_dyn();
+        let vy = arr2(&[[0.3, -0.7], [0.2, 1.]]).into_dyn();
+
+        let (out, tangent) =
+            traced.jvp_eval(vec![x.clone(), y.clone()], vec![vx.clone(), vy.clone()]);
+        let (expected,) = traced.eval()((&x, &y));
+        assert_eq!(out[0], expected);
+
+        let h = 1e-6;
+        let (xp, yp) = (&x + &(&vx * h), &y + &(&vy * h));
+        let (xm, ym) = (&x - &(&vx * h), &y - &(&vy * h));
+        let (plus,) = traced.eval()((&xp, &yp));
+        let (minus,) = traced.eval()((&xm, &ym));
+        let fd = (plus - minus) / (2. * h);
+        for (a, b) in tangent[0].iter().zip(&fd) {
+            assert!((a - b).abs() < 1e-6, "jvp {a} vs finite difference {b}");
+        }
+    }
+
+    #[test]
+    fn test_jvp_is_one_graph_through_reductions() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            (x * x).mean(vec![1], false) + y.sum(vec![], false).exp()
+        }
+
+        let traced = trace_fn::<f64>(f);
+        let jvp = traced.jvp();
+        assert_eq!(jvp.inputs.len(), 4);
+        assert_eq!(jvp.outputs.len(), 2);
+
+        let x = arr2(&[[0.5, -1., 2.], [0.25, 1.5, -0.5]]).into_dyn();
+        let y = arr1(&[0.1, -0.3]).into_dyn();
+        let vx = arr2(&[[1., 0.5, -2.], [-1., 2., 0.3]]).into_dyn();
+        let vy = arr1(&[0.7, -0.2]).into_dyn();
+        let (_, tangent) =
+            traced.jvp_eval(vec![x.clone(), y.clone()], vec![vx.clone(), vy.clone()]);
+
+        // d mean(x^2, 1) = mean(2 x vx, 1); d exp(sum y) = exp(sum y) sum(vy)
+        let expected =
+            (&x * &vx * 2.).mean_axis(ndarray::Axis(1)).unwrap() + y.sum().exp() * vy.sum();
+        for (a, b) in tangent[0].iter().zip(&expected) {
+            assert!((a - b).abs() < 1e-12, "jvp {a} vs {b}");
+        }
+    }
+
+    #[test]
+    fn test_detect_cycle() {
+        use crate::{Graph, ops::Neg};
+
+        let mut g = Graph::<f32>::new();
+        let (x, y) = (g.fresh(), g.fresh());
+        g.push(Neg::boxed(x, y));
+        g.push(Neg::boxed(y, x));
+        let cycle = g.detect_cycle().expect("the two negations feed each other");
+        assert_eq!(cycle.len(), 2);
+        assert!(cycle.contains(&x) && cycle.contains(&y));
+
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            x.matmul(w).relu() + x
+        }
+        assert_eq!(trace_fn::<f32>(f).grad().graph.detect_cycle(), None);
+    }
+
+    #[trace]
+    fn ignores_y(x: Tensor, y: Tensor) -> Tensor {
+        x * x
+    }
+
+    #[test]
+    fn test_grad_with_unreached_zero() {
+        use crate::GradOptions;
+
+        let x = arr1(&[1., 2.]).into_dyn();
+        let y = arr1(&[3., 4.]).into_dyn();
+        let (gx, gy) = trace_fn::<f32>(ignores_y)
+            .grad_with(GradOptions::default())
+            .eval()((&x, &y));
+        assert_eq!(gx, arr1(&[2., 4.]).into_dyn());
+        assert_eq!(gy, arr0(0.).into_dyn());
+    }
+
+    #[test]
+    #[should_panic(expected = "grad: input 1 does not reach any differentiated output")]
+    fn test_grad_with_unreached_error() {
+        use crate::{GradOptions, Unreached};
+
+        let _ = trace_fn::<f32>(ignores_y).grad_with(GradOptions {
+            unreached: Unreached::Error,
+        });
+    }
+
+    #[test]
+    fn test_method_form_arithmetic() {
+        #[trace]
+        fn method(x: Tensor, y: Tensor, z: Tensor) -> Tensor {
+            x.add_t(y).mul_t(z).sub_t(x).div_t(y).sum(vec![], false)
+        }
+        #[trace]
+        fn operator(x: Tensor, y: Tensor, z: Tensor) -> Tensor {
+            (((x + y) * z - x) / y).sum(vec![], false)
+        }
+
+        let x = arr1(&[1., 2., 3.]).into_dyn();
+        let y = arr1(&[2., 4., 5.]).into_dyn();
+        let z = arr1(&[-1., 0.5, 3.]).into_dyn();
+        let method = trace_fn::<f32>(method);
+        let operator = trace_fn::<f32>(operator);
+
+        assert_eq!(method.graph.to_ssa(), operator.graph.to_ssa());
+        let (out,) = method.eval()((&x, &y, &z));
+        let expected = arr0((((&x + &y) * &z - &x) / &y).sum()).into_dyn();
+        assert_all_close(&out, &expected, 1e-6);
+    }
+
+    #[test]
+    fn test_std_arithmetic_methods_inside_trace() {
+        use std::ops::Add;
+
+        // `n.add(1)` on a `usize` is std arithmetic, not a traced op.
+        #[trace]
+        fn second_row(x: Tensor) -> Tensor {
+            let n: usize = 0;
+            let second = n.add(1);
+            let parts = x.split(vec![1, 1], 0);
+            parts[second]
+        }
+
+        let x = arr2(&[[1., 2.], [3., 4.]]).into_dyn();
+        let (out,) = trace_fn::<f32>(second_row).eval()(&x);
+        assert_eq!(out, arr2(&[[3., 4.]]).into_dyn());
+    }
+
+    #[test]
+    fn test_grad_from_closure() {
+        let df = crate::grad::<f32>(
+            |sess, xs| {
+                let sq = sess.mul(xs[0], xs[0]);
+                sess.sum(sq, vec![], false)
+            },
+            1,
+        );
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            (x * x).sum(vec![], false)
+        }
+
+        let x = arr1(&[1., -2., 3.]).into_dyn();
+        let (gx,) = df.eval()(&x);
+        assert_eq!(gx, 2. * &x);
+        assert_eq!(df.graph.to_ssa(), trace_fn::<f32>(f).grad().graph.to_ssa());
+    }
+
+    #[test]
+    fn test_reduce_all_shorthands() {
+        #[trace]
+        fn short(x: Tensor) -> Tensor {
+            x.sum_all() + x.mean_all() * x.max_all()
+        }
+        #[trace]
+        fn long(x: Tensor) -> Tensor {
+            x.sum(vec![], false) + x.mean(vec![], false) * x.max(vec![], false)
+        }
+
+        let x = arr2(&[[1., -2.], [4., 3.]]).into_dyn();
+        let short = trace_fn::<f32>(short);
+        assert_eq!(short.graph.to_ssa(), trace_fn::<f32>(long).graph.to_ssa());
+        let (out,) = short.eval()(&x);
+        assert_eq!(out, arr0(6. + 1.5 * 4.).into_dyn());
+    }
+
+    #[test]
+    fn test_replace_op() {
+        use crate::ops::exp::Exp;
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.relu().sum(vec![], false)
+        }
+
+        let mut traced = trace_fn::<f32>(f);
+        let index = traced
+            .ops()
+            .position(|(name, _, _)| name == "relu")
+            .unwrap();
+        let (inputs, outputs) = (
+            traced.graph.nodes[index].inputs(),
+            traced.graph.nodes[index].outputs(),
+        );
+        traced
+            .graph
+            .replace_op(index, Box::new(Exp::new(inputs[0], outputs[0])));
+
+        let x = arr1(&[0., -1., 2.]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_all_close(&out, &arr0(x.exp().sum()).into_dyn(), 1e-6);
+        let (gx,) = traced.grad().eval()(&x);
+        assert_all_close(&gx, &x.exp(), 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "replace_op: relu reads different inputs than the relu it replaces")]
+    fn test_replace_op_rejects_rewiring() {
+        use crate::ops::relu::ReLU;
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.relu()
+        }
+
+        let mut traced = trace_fn::<f32>(f);
+        let index = traced
+            .ops()
+            .position(|(name, _, _)| name == "relu")
+            .unwrap();
+        let out = traced.graph.nodes[index].outputs()[0];
+        let stray = traced.graph.fresh();
+        traced
+            .graph
+            .replace_op(index, Box::new(ReLU::new(stray, out)));
+    }
+
+    #[test]
+    fn test_trace_scalar_hyperparameter() {
+        #[trace]
+        fn f(x: Tensor, temperature: f32) -> Tensor {
+            (x / temperature).exp().sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(|sess| f(sess, 2.0));
+        assert_eq!(traced.inputs.len(), 1);
+
+        let x = arr1(&[1., -2., 4.]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_all_close(&out, &arr0((&x / 2.).exp().sum()).into_dyn(), 1e-6);
+        let (gx,) = traced.grad().eval()(&x);
+        assert_all_close(&gx, &((&x / 2.).exp() / 2.), 1e-6);
+    }
+
+    #[test]
+    fn test_scalar_hyperparameter_feeds_scalar_ops() {
+        #[trace]
+        fn f(x: Tensor, lo: f64, hi: f64, eps: f32) -> Tensor {
+            let w = x.ones_like();
+            x.clamp(lo, hi) + x.maximum_scalar(hi) * x.layer_norm(w, w, vec![0], eps)
+        }
+
+        let traced = trace_fn::<f64>(|sess| f(sess, -1.0, 1.0, 1e-5));
+        assert_eq!(traced.inputs.len(), 1);
+        let hist = traced.graph.op_histogram();
+        assert_eq!(hist["layer_norm"], 1);
+
+        let x = arr1(&[-3., 0.5, 2.]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        let mean = x.mean().unwrap();
+        let var = x.mapv(|v| (v - mean) * (v - mean)).mean().unwrap();
+        let expected = x.mapv(|v| v.clamp(-1., 1.))
+            + x.mapv(|v| v.max(1.)) * x.mapv(|v| (v - mean) / (var + 1e-5).sqrt() + 1.);
+        for (o, e) in out.iter().zip(expected.iter()) {
+            assert!((o - e).abs() < 1e-9, "{out:?} vs {expected:?}");
+        }
+    }
+
+    #[test]
+    fn test_structural_hash() {
+        #[trace]
+        fn add(x: Tensor, y: Tensor) -> Tensor {
+            x + y * 2.0
+        }
+        #[trace]
+        fn mul(x: Tensor, y: Tensor) -> Tensor {
+            x * y * 2.0
+        }
+        #[trace]
+        fn add_3(x: Tensor, y: Tensor) -> Tensor {
+            x + y * 3.0
+        }
+
+        let h = trace_fn::<f32>(add).structural_hash();
+        assert_eq!(h, trace_fn::<f32>(add).structural_hash());
+        assert_ne!(h, trace_fn::<f32>(mul).structural_hash());
+        assert_ne!(h, trace_fn::<f32>(add_3).structural_hash());
+
+        // same structure under different id numbering.
+        let mut g = crate::Graph::<f32>::new();
+        let _ = (g.fresh(), g.fresh(), g.fresh());
+        let mut sess = crate::TraceSession::new(&mut g);
+        let (inputs, out) = add(&mut sess);
+        let shifted = crate::TraceableFn {
+            inputs,
+            outputs: vec![out.id()],
+            graph: g,
+        };
+        assert_ne!(shifted.graph.to_ssa(), trace_fn::<f32>(add).graph.to_ssa());
+        assert_eq!(shifted.structural_hash(), h);
+    }
+
+    #[test]
+    fn test_structural_hash_covers_op_parameters() {
+        use crate::ops::{max::TieBreak, relu::ReluBoundary};
+        use crate::{TraceSession, Tracer};
+
+        fn hash(build: impl FnOnce(&mut TraceSession<f32>, Tracer) -> Tracer) -> u64 {
+            let mut g = crate::Graph::<f32>::new();
+            let mut sess = TraceSession::new(&mut g);
+            let x = sess.input();
+            let out = build(&mut sess, x);
+            crate::TraceableFn {
+                inputs: vec![x.id()],
+                outputs: vec![out.id()],
+                graph: g,
+            }
+            .structural_hash()
+        }
+
+        let pairs: Vec<(u64, u64)> = vec![
+            (
+                hash(|s, x| s.sum(x, vec![0], false)),
+                hash(|s, x| s.sum(x, vec![1], true)),
+            ),
+            (
+                hash(|s, x| s.sum(x, vec![0], false)),
+                hash(|s, x| s.sum_stable(x, vec![0], false)),
+            ),
+            (
+                hash(|s, x| s.mean(x, vec![0], false)),
+                hash(|s, x| s.mean(x, vec![0], true)),
+            ),
+            (
+                hash(|s, x| s.l1_norm(x, vec![0], false)),
+                hash(|s, x| s.l1_norm(x, vec![1], false)),
+            ),
+            (
+                hash(|s, x| s.maximum_scalar(x, 0.0)),
+                hash(|s, x| s.maximum_scalar(x, 5.0)),
+            ),
+            (
+                hash(|s, x| s.reshape(x, vec![2, 3])),
+                hash(|s, x| s.reshape(x, vec![3, 2])),
+            ),
+            (
+                hash(|s, x| s.broadcast(x, vec![2, 3])),
+                hash(|s, x| s.broadcast(x, vec![4, 3])),
+            ),
+            (
+                hash(|s, x| s.transpose(x, 0, 1)),
+                hash(|s, x| s.transpose(x, 1, 2)),
+            ),
+            (
+                hash(|s, x| s.embedding(x, vec![0, 1])),
+                hash(|s, x| s.embedding(x, vec![1, 0])),
+            ),
+            (
+                hash(|s, x| s.split(x, vec![1, 2], 0)[0]),
+                hash(|s, x| s.split(x, vec![2, 1], 0)[0]),
+            ),
+            (
+                hash(|s, x| s.repeat_interleave(x, 2, 0)),
+                hash(|s, x| s.repeat_interleave(x, 3, 0)),
+            ),
+            (
+                hash(|s, x| s.relu_with_boundary(x, ReluBoundary::Zero)),
+                hash(|s, x| s.relu_with_boundary(x, ReluBoundary::One)),
+            ),
+            (
+                hash(|s, x| s.max_with_tie_break(x, vec![0], false, TieBreak::Distribute)),
+                hash(|s, x| s.max_with_tie_break(x, vec![0], false, TieBreak::First)),
+            ),
+            (hash(|s, x| s.slice(x, 0, 1..2)), hash(|s, x| s.index(x, 1))),
+            (
+                hash(|s, x| {
+                    let c = s.constant_tensor(arr1(&[1., 2.]).into_dyn());
+                    s.add(x, c)
+                }),
+                hash(|s, x| {
+                    let c = s.constant_tensor(arr1(&[1., 3.]).into_dyn());
+                    s.add(x, c)
+                }),
+            ),
+        ];
+        for (i, (a, b)) in pairs.into_iter().enumerate() {
+            assert_ne!(a, b, "pair {i} collides");
+        }
+    }
+
+    #[test]
+    fn test_grad_accumulate_matches_full_batch() {
+        #[trace]
+        fn loss(x: Tensor, w: Tensor) -> Tensor {
+            let y = x.matmul(w);
+            (y * y).sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(loss);
+        let x1 = arr2(&[[1., 2.], [0.5, -1.]]).into_dyn();
+        let x2 = arr2(&[[3., -2.]]).into_dyn();
+        let full = arr2(&[[1., 2.], [0.5, -1.], [3., -2.]]).into_dyn();
+        let w = arr2(&[[0.5, -1., 2.], [1., 0.25, -0.5]]).into_dyn();
+
+        let accumulated = traced.grad_accumulate([(&x1, &w), (&x2, &w)]);
+        let (_, gw): (ndarray::ArrayD<f32>, ndarray::ArrayD<f32>) =
+            traced.grad().eval()((&full, &w));
+        assert_all_close(&accumulated[1], &gw, 1e-5);
+        assert_eq!(accumulated[0].shape(), x1.shape());
+    }
+
+    #[test]
+    fn test_lift_constants() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x + 1.0
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let lifted = traced.lift_constants();
+        assert_eq!(lifted.inputs.len(), 2);
+        assert_eq!(lifted.inputs[0], traced.inputs[0]);
+        assert!(!lifted.graph.op_histogram().contains_key("const"));
+
+        let x = arr1(&[1., 2., 3.]).into_dyn();
+        let (one, five) = (arr0(1.).into_dyn(), arr0(5.).into_dyn());
+        let (out,) = lifted.eval()((&x, &one));
+        let (expected,) = traced.eval()(&x);
+        assert_eq!(out, expected);
+        let (out,) = lifted.eval()((&x, &five));
+        assert_eq!(out, arr1(&[6., 7., 8.]).into_dyn());
+
+        // the broadcast constant receives the summed upstream gradient.
+        let (gx, gc) = lifted.grad().eval()((&x, &one));
+        assert_eq!(gx, arr1(&[1., 1., 1.]).into_dyn());
+        assert_eq!(gc, arr0(3.).into_dyn());
+    }
+
+    #[test]
+    fn test_deduplicate_constants() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.log().sum(vec![], false)
+        }
+
+        let grad = trace_fn::<f32>(f).grad();
+        let x = arr1(&[1., 2., 4.]).into_dyn();
+        let (before,) = grad.eval()(&x);
+
+        // the seed and log's vjp each emit their own 1.0.
+        assert_eq!(grad.graph.op_histogram()["const"], 2);
+        let deduped = grad.deduplicate_constants();
+        assert_eq!(deduped.graph.op_histogram()["const"], 1);
+        assert_eq!(deduped.graph.nodes.len(), grad.graph.nodes.len() - 1);
+
+        let (after,) = deduped.eval()(&x);
+        assert_eq!(before, after);
+        assert_eq!(after, arr1(&[1., 0.5, 0.25]).into_dyn());
+        assert_eq!(
+            deduped.count_backward_nodes(),
+            grad.count_backward_nodes() - 1
+        );
+    }
+
+    #[test]
+    fn test_deduplicate_constants_remaps_outputs() {
+        use crate::{Graph, TraceSession, TraceableFn};
+
+        // the second output is a constant equal to an earlier one.
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let x = sess.input();
+        let two = sess.constant(2.);
+        let y = sess.mul(x, two);
+        let also_two = sess.constant(2.);
+        let f = TraceableFn {
+            graph: g,
+            inputs: vec![x.id()],
+            outputs: vec![y.id(), also_two.id()],
+        };
+
+        let deduped = f.deduplicate_constants();
+        assert_eq!(deduped.outputs, vec![y.id(), two.id()]);
+        let x = arr1(&[1., 3.]).into_dyn();
+        let (out, c) = deduped.eval()(&x);
+        assert_eq!(out, arr1(&[2., 6.]).into_dyn());
+        assert_eq!(c, arr0(2.).into_dyn());
+
+        // the merged-away id is not handed out again by later passes.
+        let grad = deduped.grad();
+        assert!(
+            grad.graph
+                .nodes
+                .iter()
+                .all(|n| !n.outputs().contains(&also_two.id()))
+        );
+        let (gx,) = grad.eval()(&x);
+        assert_eq!(gx, arr1(&[2., 2.]).into_dyn());
+    }
+
+    #[test]
+    fn test_checkpoint_recomputes_block_in_backward() {
+        use crate::{Graph, Id, TraceSession, TraceableFn};
+
+        fn build(checkpoint: bool) -> (TraceableFn<f32>, Vec<Id>) {
+            let mut g = Graph::<f32>::new();
+            let mut sess = TraceSession::new(&mut g);
+            let x = sess.input();
+            let block = |sess: &mut TraceSession<f32>| {
+                let a = sess.exp(x);
+                let b = sess.mul(a, x);
+                sess.mul(b, b)
+            };
+            let h = if checkpoint {
+                sess.checkpoint(block)
+            } else {
+                block(&mut sess)
+            };
+            let y = sess.sum(h, vec![], false);
+            let region: Vec<Id> = g
+                .checkpoints
+                .iter()
+                .flat_map(|r| g.nodes[r.clone()].iter())
+                .flat_map(|n| n.outputs())
+                .collect();
+            let f = TraceableFn {
+                graph: g,
+                inputs: vec![x.id()],
+                outputs: vec![y.id()],
+            };
+            (f, region)
+        }
+
+        let (plain, _) = build(false);
+        let (remat, region) = build(true);
+        assert_eq!(region.len(), 3);
+
+        let x = arr1(&[0.5, -1., 2.]).into_dyn();
+        let (expected,) = plain.grad().eval()(&x);
+        let grad = remat.grad();
+        let (got,) = grad.eval()(&x);
+        assert_all_close(&got, &expected, 1e-6);
+        let (compiled,) = grad.compile().eval()(&x);
+        assert_all_close(&compiled, &expected, 1e-6);
+
+        // the backward pass reads only the replayed copies.
+        let fwd_len = remat.graph.nodes.len();
+        assert!(grad.graph.op_histogram()["remat"] >= 3);
+        for node in &grad.graph.nodes[fwd_len..] {
+            assert!(
+                node.inputs().iter().all(|id| !region.contains(id)),
+                "{} reads a checkpointed forward value",
+                node.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_eval_scalar() {
+        #[trace]
+        fn loss(x: Tensor) -> Tensor {
+            (x * x).sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(loss);
+        let x = arr1(&[1., 2., 3.]).into_dyn();
+        let value: f32 = traced.eval_scalar()(&x);
+        assert_eq!(value, 14.);
+    }
+
+    #[test]
+    #[should_panic(expected = "eval_scalar: expected a rank-0 output, got shape [3]")]
+    fn test_eval_scalar_rejects_tensor_output() {
+        #[trace]
+        fn square(x: Tensor) -> Tensor {
+            x * x
+        }
+
+        let x = arr1(&[1., 2., 3.]).into_dyn();
+        let _ = trace_fn::<f32>(square).eval_scalar()(&x);
+    }
+
+    #[test]
+    fn test_keep_dims_multi_axis_grads_match_finite_differences() {
+        // layer-norm style: reduce over [0, 2] keeping dims, broadcast back.
+        #[trace]
+        fn centered(x: Tensor, w: Tensor) -> Tensor {
+            ((x - x.mean(vec![0, 2], true)) * w).sum(vec![], false)
+        }
+        #[trace]
+        fn scaled(x: Tensor, w: Tensor) -> Tensor {
+            (x * x.sum(vec![0, -1], true) * w).sum(vec![], false)
+        }
+
+        let x = Array::from_shape_fn((2, 3, 4), |(i, j, k)| {
+            ((i * 12 + j * 4 + k) as f64 * 0.37).sin()
+        })
+        .into_dyn();
+        let w = Array::from_shape_fn((2, 3, 4), |(i, j, k)| (i + 2 * j) as f64 - k as f64 * 0.5)
+            .into_dyn();
+
+        for traced in [trace_fn::<f64>(centered), trace_fn::<f64>(scaled)] {
+            assert_grad_matches_fd(&traced, &[x.clone(), w.clone()], 1e-6, 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_negative_literal_is_a_single_constant() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x + (-2.0)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let hist = traced.graph.op_histogram();
+        assert!(!hist.contains_key("neg"));
+        assert_eq!(hist["const"], 1);
+
+        let x = arr1(&[1., 2.]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, arr1(&[-1., 0.]).into_dyn());
+    }
+
+    #[test]
+    fn test_specialize_folds_frozen_submodel() {
+        #[trace]
+        fn model(w1: Tensor, w2: Tensor, b: Tensor, x: Tensor) -> Tensor {
+            x.matmul(w1.matmul(w2)) + b.exp()
+        }
+
+        let traced = trace_fn::<f32>(model);
+        let w1 = arr2(&[[1., 0.], [2., 1.]]).into_dyn();
+        let w2 = arr2(&[[0.5, 1.], [1., -1.]]).into_dyn();
+        let b = arr1(&[0., 1.]).into_dyn();
+        let x = arr2(&[[1., 2.], [3., 4.]]).into_dyn();
+
+        let served = traced.specialize(&[(0, w1.clone()), (1, w2.clone()), (2, b.clone())]);
+        assert_eq!(served.inputs, vec![traced.inputs[3]]);
+        // input, the folded w1 @ w2 and exp(b), the live matmul and the add.
+        let hist = served.graph.op_histogram();
+        assert_eq!(served.graph.nodes.len(), 5);
+        assert_eq!(hist["const_tensor"], 2);
+        assert!(!hist.contains_key("exp"));
+        assert!(served.graph.nodes.len() < traced.graph.nodes.len());
+
+        let (out,) = served.eval()(&x);
+        let (expected,) = traced.eval()((&w1, &w2, &b, &x));
+        assert_eq!(out, expected);
+
+        let (gx,) = served.grad().eval()(&x);
+        let (_, _, _, expected_gx) = traced.grad().eval()((&w1, &w2, &b, &x));
+        assert_eq!(gx, expected_gx);
+    }
+
+    #[test]
+    fn test_print_annotated_tags_backward_nodes() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            x * y
+        }
+
+        let traced = trace_fn::<f32>(f);
+        assert!(
+            traced
+                .print_annotated()
+                .lines()
+                .all(|l| l.starts_with("[fwd] "))
+        );
+
+        let grad = traced.grad();
+        let text = grad.print_annotated();
+        let fwd = text.lines().filter(|l| l.starts_with("[fwd] ")).count();
+        let bwd = text.lines().filter(|l| l.starts_with("[bwd] ")).count();
+        assert_eq!(fwd, 3);
+        // the sum to a scalar and its seed, plus the six vjp nodes.
+        assert_eq!(grad.count_backward_nodes(), 6);
+        assert_eq!(bwd, 2 + 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "graph exceeded 10 nodes while pushing `add`")]
+    fn test_graph_budget_stops_runaway_construction() {
+        use crate::{Graph, TraceSession};
+
+        let mut g = Graph::<f32>::with_budget(10);
+        let mut sess = TraceSession::new(&mut g);
+        let mut x = sess.input();
+        // the input plus nine adds fit; the tenth add does not.
+        for _ in 0..1000 {
+            x = sess.add(x, x);
+        }
+    }
+
+    #[test]
+    fn test_graph_budget_allows_graphs_within_it() {
+        use crate::{Graph, TraceSession};
+
+        let mut g = Graph::<f32>::with_budget(10);
+        let mut sess = TraceSession::new(&mut g);
+        let mut x = sess.input();
+        for _ in 0..9 {
+            x = sess.add(x, x);
+        }
+        assert_eq!(g.nodes.len(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "graph exceeded 3 nodes")]
+    fn test_trace_fn_in_applies_the_graph_budget() {
+        use crate::Graph;
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            (x * x).exp().exp()
+        }
+
+        let _ = trace_fn_in(Graph::<f32>::with_budget(3), f);
+    }
 }
@@ -1,10 +1,16 @@
 use crate::{
-    graph::Graph, identity::Id, ops::sum::ReduceToLike, primitive_binary_op, tracing::TensorData,
+    Floating, TraceSession, Tracer,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+    ops::sum::ReduceToLike,
+    primitive_binary_op,
+    tracing::TensorData,
 };
 
 primitive_binary_op!(
     Add,
     disp: "add",
+    linear: true,
     fwd: |x: &TensorData<D>, y: &TensorData<D>| x + y,
     vjp: |this: &Add, g: &mut Graph<D>, og: Id| {
         let grad_lhs = {
@@ -21,6 +27,22 @@ primitive_binary_op!(
     }
 );
 
+impl Tracer {
+    pub fn add_bias(&self, _bias: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// `x + bias` for a `[batch, features]` activation and a `[features]`
+    /// bias. The bias broadcasts over the batch axis, and its gradient is the
+    /// upstream gradient summed over that axis.
+    #[must_use]
+    pub fn add_bias(&mut self, x: Tracer, bias: Tracer) -> Tracer {
+        self.add(x, bias)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use chainrule_macros::trace;
@@ -40,4 +62,25 @@ mod tests {
         let expected = &x + &y;
         assert_eq!(out, expected);
     }
+
+    #[test]
+    fn test_add_bias_grad_sums_over_batch() {
+        #[trace]
+        fn f(x: crate::Tensor, bias: crate::Tensor, w: crate::Tensor) -> crate::Tensor {
+            (x.add_bias(bias) * w).sum(vec![], false)
+        }
+
+        let traced = crate::trace_fn::<f32>(f);
+        let x = ndarray::arr2(&[[1., 2., 3.], [4., 5., 6.]]).into_dyn();
+        let bias = ndarray::arr1(&[0.5, -1., 2.]).into_dyn();
+        // w is the upstream gradient reaching x + bias.
+        let w = ndarray::arr2(&[[1., 2., 3.], [10., 20., 30.]]).into_dyn();
+
+        let (out,) = traced.eval()((&x, &bias, &w));
+        assert_eq!(out, ndarray::arr0(((&x + &bias) * &w).sum()).into_dyn());
+
+        let (gx, gb, _) = traced.grad().eval()((&x, &bias, &w));
+        assert_eq!(gx, w);
+        assert_eq!(gb, w.sum_axis(ndarray::Axis(0)));
+    }
 }
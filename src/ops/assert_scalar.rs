@@ -0,0 +1,120 @@
+use crate::{
+    Floating, TraceSession, Tracer,
+    context::Context,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+    ops::Op,
+};
+
+/// Identity that panics unless its input is rank-0, so a loss can state that
+/// it returns a scalar and fail at the first call otherwise.
+#[derive(Debug, Clone)]
+pub struct AssertScalar {
+    inp: Id,
+    out: Id,
+}
+
+impl AssertScalar {
+    pub fn new(inp: Id, out: Id) -> Self {
+        Self { inp, out }
+    }
+}
+
+fn check_scalar(shape: &[usize]) {
+    assert!(
+        shape.is_empty(),
+        "assert_scalar: expected a rank-0 tensor, got shape {shape:?}"
+    );
+}
+
+impl<D: Floating> Op<D> for AssertScalar {
+    fn name(&self) -> &'static str {
+        "assert_scalar"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let t = ctx.checked_get(&self.inp);
+        check_scalar(t.shape());
+        ctx.insert(self.out, t.clone());
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // identity: the upstream gradient passes straight through
+        let og = *out_grads.first()?;
+        Some(vec![og])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        check_scalar(&input_shapes[0]);
+        vec![vec![]]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+}
+
+impl Tracer {
+    pub fn assert_scalar(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    #[must_use]
+    pub fn assert_scalar(&mut self, a: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(AssertScalar::new(a.id(), out), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr1;
+
+    use crate::prelude::*;
+
+    #[trace]
+    fn loss(x: Tensor) -> Tensor {
+        (x * x).sum([], false).assert_scalar()
+    }
+
+    #[trace]
+    fn not_a_loss(x: Tensor) -> Tensor {
+        (x * x).assert_scalar()
+    }
+
+    #[test]
+    fn test_assert_scalar_passes_through() {
+        let traced = trace_fn::<f32>(loss);
+        let x = arr1(&[1., 2.]).into_dyn();
+
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, ndarray::arr0(5.).into_dyn());
+        let (grad_x,) = traced.grad().eval()(&x);
+        assert_eq!(grad_x, arr1(&[2., 4.]).into_dyn());
+    }
+
+    #[test]
+    #[should_panic(expected = "assert_scalar: expected a rank-0 tensor, got shape [2]")]
+    fn test_assert_scalar_rejects_vector() {
+        let x = arr1(&[1., 2.]).into_dyn();
+        let _: (ndarray::ArrayD<f32>,) = trace_fn::<f32>(not_a_loss).eval()(&x);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::{
+    Floating, TraceSession, Tracer,
+    context::Context,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+    ops::Op,
+};
+
+/// Identity that panics unless its input has exactly the `expected` shape,
+/// so a plumbing mistake fails where it happens rather than at a later broadcast.
+#[derive(Debug, Clone)]
+pub struct AssertShape {
+    inp: Id,
+    out: Id,
+    expected: Vec<usize>,
+}
+
+impl AssertShape {
+    pub fn new(inp: Id, out: Id, expected: impl Into<Vec<usize>>) -> Self {
+        Self {
+            inp,
+            out,
+            expected: expected.into(),
+        }
+    }
+
+    fn check(&self, shape: &[usize]) {
+        assert!(
+            shape == self.expected,
+            "assert_shape: expected shape {:?}, got {shape:?} (tensor {:?})",
+            self.expected,
+            self.inp
+        );
+    }
+}
+
+impl<D: Floating> Op<D> for AssertShape {
+    fn name(&self) -> &'static str {
+        "assert_shape"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let t = ctx.checked_get(&self.inp);
+        self.check(t.shape());
+        ctx.insert(self.out, t.clone());
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // identity: the upstream gradient passes straight through
+        let og = *out_grads.first()?;
+        Some(vec![og])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        self.check(&input_shapes[0]);
+        vec![self.expected.clone()]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("{:?}", self.expected))
+    }
+}
+
+impl Tracer {
+    pub fn assert_shape(&self, _expected: impl Into<Vec<usize>>) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    #[must_use]
+    pub fn assert_shape(&mut self, a: Tracer, expected: impl Into<Vec<usize>>) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(AssertShape::new(a.id(), out, expected), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array, arr2};
+
+    use crate::prelude::*;
+
+    #[trace]
+    fn dense(x: Tensor, w: Tensor) -> Tensor {
+        x.matmul(w).assert_shape([2, 3]).relu()
+    }
+
+    #[test]
+    fn test_assert_shape_passes_through() {
+        let x = arr2(&[[1., -1.], [2., 0.5]]).into_dyn();
+        let w = Array::ones((2, 3)).into_dyn();
+        let traced = trace_fn::<f32>(dense);
+
+        let (out,) = traced.eval()((&x, &w));
+        assert_eq!(out, arr2(&[[0., 0., 0.], [2.5, 2.5, 2.5]]).into_dyn());
+        let (_, grad_w) = traced.grad().eval()((&x, &w));
+        assert_eq!(grad_w, arr2(&[[2., 2., 2.], [0.5, 0.5, 0.5]]).into_dyn());
+    }
+
+    #[test]
+    #[should_panic(expected = "assert_shape: expected shape [2, 3], got [2, 4]")]
+    fn test_assert_shape_mismatch() {
+        let x = arr2(&[[1., -1.], [2., 0.5]]).into_dyn();
+        let w = Array::ones((2, 4)).into_dyn();
+        let _: (ndarray::ArrayD<f32>,) = trace_fn::<f32>(dense).eval()((&x, &w));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{
+        Mul, Op,
+        layer_norm::{LayerNormInputGrad, LayerNormXHat, normalize},
+        numel,
+        sum::ReduceToLike,
+    },
+    tracing::TensorData,
+};
+
+/// epsilon added to the variance before the square root, as in PyTorch.
+pub const BATCH_NORM_EPS: f64 = 1e-5;
+
+/// statistics `batch_norm` normalizes with.
+// only passed once at trace time, so the variant size gap does not matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum BatchNormMode<D: Floating> {
+    /// mean and (biased) variance of the current batch, over axis 0.
+    Train,
+    /// fixed running statistics, embedded in the graph as constants.
+    /// both must broadcast against the input.
+    Inference {
+        running_mean: TensorData<D>,
+        running_var: TensorData<D>,
+    },
+}
+
+/// Train-mode batch normalization over the leading (batch) axis:
+/// `gamma * (x - mean) / sqrt(var + eps) + beta`.
+///
+/// for `(N, C, H, W)` activations, move channels last and flatten to
+/// `(N * H * W, C)` first so the statistics are per channel.
+#[derive(Debug, Clone)]
+pub struct BatchNorm<D: Floating> {
+    inp: Id,
+    gamma: Id,
+    beta: Id,
+    out: Id,
+    eps: D,
+}
+
+impl<D: Floating> BatchNorm<D> {
+    pub fn new(inp: Id, gamma: Id, beta: Id, out: Id, eps: D) -> Self {
+        Self {
+            inp,
+            gamma,
+            beta,
+            out,
+            eps,
+        }
+    }
+}
+
+const BATCH_AXIS: [isize; 1] = [0];
+
+impl<D: Floating + 'static> Op<D> for BatchNorm<D> {
+    fn name(&self) -> &'static str {
+        "batch_norm"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        assert!(
+            x.ndim() >= 1,
+            "batch_norm: input needs a leading batch axis, got shape {:?}",
+            x.shape()
+        );
+        let gamma = ctx.checked_get(&self.gamma);
+        let beta = ctx.checked_get(&self.beta);
+        let (x_hat, _) = normalize(x, &BATCH_AXIS, self.eps);
+        ctx.insert(self.out, x_hat * gamma + beta);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // same closed form as layer_norm, with the statistics over the batch axis
+        let og = *out_grads.first()?;
+
+        let grad_x = g.fresh();
+        g.push(Box::new(LayerNormInputGrad::new(
+            self.inp,
+            self.gamma,
+            og,
+            grad_x,
+            BATCH_AXIS.to_vec(),
+            self.eps,
+        )));
+
+        let grad_gamma = {
+            let x_hat = g.fresh();
+            g.push(Box::new(LayerNormXHat::new(
+                self.inp,
+                x_hat,
+                BATCH_AXIS.to_vec(),
+                self.eps,
+            )));
+            let scaled = g.fresh();
+            g.push(Box::new(Mul::new(og, x_hat, scaled)));
+            let out = g.fresh();
+            g.push(Box::new(ReduceToLike::new(scaled, self.gamma, out)));
+            out
+        };
+
+        let grad_beta = g.fresh();
+        g.push(Box::new(ReduceToLike::new(og, self.beta, grad_beta)));
+
+        Some(vec![grad_x, grad_gamma, grad_beta])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp, self.gamma, self.beta]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        // mean, center, square, variance sum, scale, affine
+        7 * numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(BatchNorm::new(
+            self.inp,
+            self.gamma,
+            self.beta,
+            self.out,
+            self.eps.as_f64(),
+        ))
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("eps={:?}", self.eps))
+    }
+}
+
+impl Tracer {
+    pub fn batch_norm<D: Floating>(
+        &self,
+        _gamma: Tracer,
+        _beta: Tracer,
+        _mode: BatchNormMode<D>,
+    ) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// batch normalization of `x`, then `* gamma + beta`.
+    ///
+    /// train mode emits a fused [`BatchNorm`]; inference mode lowers to plain
+    /// elementwise ops around the running statistics.
+    pub fn batch_norm(
+        &mut self,
+        x: Tracer,
+        gamma: Tracer,
+        beta: Tracer,
+        mode: BatchNormMode<D>,
+    ) -> Tracer {
+        match mode {
+            BatchNormMode::Train => {
+                let out = self.g.fresh();
+                let eps = D::from_f64(BATCH_NORM_EPS);
+                self.emit(BatchNorm::new(x.id(), gamma.id(), beta.id(), out, eps), out)
+            }
+            BatchNormMode::Inference {
+                running_mean,
+                running_var,
+            } => {
+                let eps = D::from_f64(BATCH_NORM_EPS);
+                let mean = self.constant_tensor(running_mean);
+                let inv_std = self.constant_tensor(running_var.mapv(|v| (v + eps).sqrt().recip()));
+                let centered = self.sub(x, mean);
+                let x_hat = self.mul(centered, inv_std);
+                let scaled = self.mul(x_hat, gamma);
+                self.add(scaled, beta)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BatchNormMode;
+    use crate::{
+        Graph, TraceSession, TraceableFn, tests::assert_grad_matches_fd, tracing::tracer::Item as _,
+    };
+    use ndarray::{ArrayD, arr1, arr2};
+
+    fn traced(mode: BatchNormMode<f64>) -> TraceableFn<f64> {
+        // loss = sum(batch_norm(x, gamma, beta) * w)
+        let mut g = Graph::<f64>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let [x, gamma, beta, w] = [(); 4].map(|_| sess.input());
+        let y = sess.batch_norm(x, gamma, beta, mode);
+        let weighted = sess.mul(y, w);
+        let loss = sess.sum(weighted, vec![], false);
+        TraceableFn {
+            graph: g,
+            inputs: vec![x.id(), gamma.id(), beta.id(), w.id()],
+            outputs: vec![loss.id()],
+        }
+    }
+
+    fn args() -> [ArrayD<f64>; 4] {
+        [
+            arr2(&[[1., 2., 3.], [2., -2., 5.], [0.5, 1., -1.], [3., 0., 2.]]).into_dyn(),
+            arr1(&[0.5, 1.5, -1.]).into_dyn(),
+            arr1(&[0.1, 0.2, 0.3]).into_dyn(),
+            arr2(&[[1., -2., 0.5], [3., 1., -1.], [0., 2., 1.], [-1., 1., 2.]]).into_dyn(),
+        ]
+    }
+
+    fn grad_check(f: &TraceableFn<f64>) {
+        assert_grad_matches_fd(f, &args(), 1e-6, 1e-5);
+    }
+
+    #[test]
+    fn test_batch_norm_train_grad_check() {
+        grad_check(&traced(BatchNormMode::Train));
+    }
+
+    #[test]
+    fn test_batch_norm_inference_uses_running_stats() {
+        let f = traced(BatchNormMode::Inference {
+            running_mean: arr1(&[1., 0., 2.]).into_dyn(),
+            running_var: arr1(&[4., 1., 0.25]).into_dyn(),
+        });
+        grad_check(&f);
+
+        let x = arr2(&[[3., 1., 2.5]]).into_dyn();
+        let ones = arr1(&[1., 1., 1.]).into_dyn();
+        let zeros = arr1(&[0., 0., 0.]).into_dyn();
+        let (out,) = f.eval()((&x, &ones, &zeros, &ones));
+        assert!((out.item() - (1. + 1. + 1.)).abs() < 1e-4);
+    }
+}
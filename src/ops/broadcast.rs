@@ -1,7 +1,8 @@
 use crate::{
     Floating, Id, Tracer,
     context::Context,
-    ops::{Op, sum::ReduceToLike},
+    identity::IdGenerator,
+    ops::{Op, broadcast_shapes, sum::ReduceToLike},
     tracing::session::TraceSession,
 };
 
@@ -30,9 +31,13 @@ impl<D: Floating> Op<D> for Broadcast {
 
     fn eval(&self, ctx: &mut Context<D>) {
         let t = ctx.checked_get(&self.inp);
-        let t = t
-            .broadcast(self.target_shape.clone())
-            .expect("failed to broadcast. dimension mismatch");
+        let t = t.broadcast(self.target_shape.clone()).unwrap_or_else(|| {
+            panic!(
+                "broadcast: cannot broadcast {:?} to {:?}",
+                t.shape(),
+                self.target_shape
+            )
+        });
 
         ctx.insert(self.out, t.to_owned());
     }
@@ -52,13 +57,48 @@ impl<D: Floating> Op<D> for Broadcast {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+
+    fn shape_rule(&self, _: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![self.target_shape.clone()]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("{:?}", self.target_shape))
+    }
+    fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        Some(Box::new(Self {
+            inp: inputs[0],
+            ..self.clone()
+        }))
+    }
 }
 
-impl<D: Floating + 'static> TraceSession<'_, D> {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
     #[must_use]
+    /// broadcasts `t` to `shape`. when `t`'s shape is known at trace time (see
+    /// [`static_shape`](Self::static_shape)) an incompatible target fails here
+    /// rather than at eval.
     pub fn broadcast(&mut self, t: Tracer, shape: impl Into<Vec<usize>>) -> Tracer {
+        let shape = shape.into();
+        if let Some(current) = self.static_shape(t) {
+            assert!(
+                broadcast_shapes(&current, &shape).as_ref() == Some(&shape),
+                "broadcast: cannot broadcast {current:?} to {shape:?}"
+            );
+        }
         let out = self.g.fresh();
-        // self.emit(Broadcast::new(t.id(), out, shape, t.shape()), out)
         self.emit(Broadcast::new(t.id(), out, shape), out)
     }
 }
@@ -91,9 +131,13 @@ impl<D: Floating> Op<D> for BroadcastLike {
     fn eval(&self, ctx: &mut Context<D>) {
         let x = ctx.checked_get(&self.inp);
         let like = ctx.checked_get(&self.like);
-        let y = x
-            .broadcast(like.shape())
-            .expect("broadcast_like: incompatible shapes");
+        let y = x.broadcast(like.shape()).unwrap_or_else(|| {
+            panic!(
+                "broadcast_like: cannot broadcast {:?} to {:?}",
+                x.shape(),
+                like.shape()
+            )
+        });
         ctx.insert(self.out, y.to_owned());
     }
 
@@ -111,4 +155,92 @@ impl<D: Floating> Op<D> for BroadcastLike {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        // [inp, like]
+        vec![input_shapes[1].clone()]
+    }
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+    fn is_linear(&self) -> bool {
+        true
+    }
+    fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        Some(Box::new(Self::new(inputs[0], inputs[1], self.out)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array, arr1};
+
+    use super::BroadcastLike;
+    use crate::{Graph, TraceSession, context::Context, ops::Op, prelude::*};
+
+    #[test]
+    #[should_panic(expected = "broadcast: cannot broadcast [2, 3] to [4, 3]")]
+    fn test_broadcast_mismatch_reports_shapes() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.broadcast([4, 3])
+        }
+
+        let x = Array::<f32, _>::zeros((2, 3)).into_dyn();
+        let _: (ndarray::ArrayD<f32>,) = trace_fn::<f32>(f).eval()(&x);
+    }
+
+    #[test]
+    #[should_panic(expected = "broadcast_like: cannot broadcast [3] to [2, 4]")]
+    fn test_broadcast_like_mismatch_reports_shapes() {
+        let mut g = Graph::<f32>::new();
+        let (inp, like, out) = (g.fresh(), g.fresh(), g.fresh());
+        let mut ctx = Context::<f32>::new();
+        ctx.insert(inp, arr1(&[1., 2., 3.]).into_dyn());
+        ctx.insert(like, Array::zeros((2, 4)).into_dyn());
+        BroadcastLike::new(inp, like, out).eval(&mut ctx);
+    }
+
+    #[test]
+    #[should_panic(expected = "broadcast: cannot broadcast [3] to [4]")]
+    fn test_broadcast_mismatch_fails_at_trace_time() {
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let c = sess.constant_tensor(arr1(&[1., 2., 3.]).into_dyn());
+        let ok = sess.broadcast(c, [2, 3]);
+        assert_eq!(sess.static_shape(ok), Some(vec![2, 3]));
+        // nothing has been evaluated yet.
+        let _ = sess.broadcast(c, [4]);
+    }
+
+    #[test]
+    fn test_static_shape_unknown_downstream_of_inputs() {
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let x = sess.input();
+        let one = sess.constant(1.);
+        let y = sess.add(x, one);
+        assert_eq!(sess.static_shape(one), Some(vec![]));
+        assert_eq!(sess.static_shape(x), None);
+        assert_eq!(sess.static_shape(y), None);
+        // still traces: the check is deferred to eval.
+        let _ = sess.broadcast(y, [4, 3]);
+    }
+
+    #[test]
+    fn test_static_lookups_see_nodes_emitted_after_earlier_lookups() {
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let c = sess.constant(2.);
+        assert_eq!(sess.static_value(c), Some(ndarray::arr0(2.).into_dyn()));
+        let d = sess.mul(c, c);
+        let x = sess.input();
+        let y = sess.add(d, x);
+        assert_eq!(sess.static_value(d), Some(ndarray::arr0(4.).into_dyn()));
+        assert_eq!(sess.static_shape(d), Some(vec![]));
+        assert_eq!(sess.static_value(y), None);
+        assert_eq!(sess.static_shape(y), None);
+    }
 }
@@ -0,0 +1,269 @@
+use crate::{
+    Floating, TraceSession, Tracer,
+    context::Context,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+    ops::{
+        Add, Const, MatMul, Mul, Op,
+        linalg::{check_square, cholesky_lower, map_matrices},
+        numel,
+        solve::Solve,
+        transpose::TransposeDefault,
+    },
+};
+
+/// lower-triangular `l` with `l @ l^T = a`, for each symmetric positive
+/// definite matrix over the last two axes. Only the lower triangle of `a` is
+/// read.
+#[derive(Debug, Clone)]
+pub struct Cholesky {
+    inp: Id,
+    out: Id,
+}
+
+impl Cholesky {
+    pub fn new(inp: Id, out: Id) -> Self {
+        Self { inp, out }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for Cholesky {
+    fn name(&self) -> &'static str {
+        "cholesky"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let a = ctx.checked_get(&self.inp);
+        let result = map_matrices("cholesky", a, |b, m| {
+            cholesky_lower(m).unwrap_or_else(|| {
+                panic!(
+                    "cholesky: matrix {b} of a {:?} input is not positive definite",
+                    a.shape()
+                )
+            })
+        });
+        ctx.insert(self.out, result);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // with P = phi(l^T @ og) and S = l^{-T} P l^{-1}, where phi keeps the
+        // lower triangle and halves the diagonal, the gradient for a symmetric
+        // input is (S + S^T) / 2. Both triangular solves go through Solve.
+        let og = *out_grads.first()?;
+        let l_t = g.fresh();
+        g.push(Box::new(TransposeDefault::new(self.out, l_t)));
+        let lt_og = g.fresh();
+        g.push(Box::new(MatMul::new(l_t, og, lt_og)));
+        let p = g.fresh();
+        g.push(Box::new(TrilHalfDiag::new(lt_og, p)));
+
+        // S = (l^{-T} (l^{-T} P)^T)^T
+        let y = g.fresh();
+        g.push(Box::new(Solve::new(l_t, p, y)));
+        let y_t = g.fresh();
+        g.push(Box::new(TransposeDefault::new(y, y_t)));
+        let s_t = g.fresh();
+        g.push(Box::new(Solve::new(l_t, y_t, s_t)));
+        let s = g.fresh();
+        g.push(Box::new(TransposeDefault::new(s_t, s)));
+
+        let both = g.fresh();
+        g.push(Box::new(Add::new(s, s_t, both)));
+        let half = g.fresh();
+        g.push(Box::new(Const::new(D::from_f64(0.5), half)));
+        let out = g.fresh();
+        g.push(Box::new(Mul::new(both, half, out)));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        check_square("cholesky", &input_shapes[0]);
+        vec![input_shapes[0].clone()]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        // n^3 / 3 per matrix
+        let n = *input_shapes[0].last().unwrap() as u64;
+        numel(&input_shapes[0]) * n / 3
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+/// lower triangle of each matrix over the last two axes, with the diagonal
+/// halved. Used by the Cholesky gradient.
+#[derive(Debug, Clone)]
+pub struct TrilHalfDiag {
+    inp: Id,
+    out: Id,
+}
+
+impl TrilHalfDiag {
+    pub fn new(inp: Id, out: Id) -> Self {
+        Self { inp, out }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for TrilHalfDiag {
+    fn name(&self) -> &'static str {
+        "tril_half_diag"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let mut t = ctx.checked_get(&self.inp).clone();
+        let rank = t.ndim();
+        check_square("tril_half_diag", t.shape());
+        let half = D::from_f64(0.5);
+        for (idx, v) in t.indexed_iter_mut() {
+            let (i, j) = (idx[rank - 2], idx[rank - 1]);
+            if j > i {
+                *v = D::zero();
+            } else if i == j {
+                *v = *v * half;
+            }
+        }
+        ctx.insert(self.out, t);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // an elementwise mask, so it is its own adjoint
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(TrilHalfDiag::new(og, out)));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+}
+
+impl Tracer {
+    pub fn cholesky(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// lower Cholesky factor of each symmetric positive definite matrix over
+    /// the last two axes of `a`. Only the lower triangle is read, and the
+    /// gradient is symmetrised to match.
+    #[must_use]
+    pub fn cholesky(&mut self, a: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Cholesky::new(a.id(), out), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array, arr2};
+
+    use crate::{prelude::*, tests::assert_grad_matches_fd, tracing::TensorData};
+
+    #[trace]
+    fn weighted_cholesky(a: Tensor, w: Tensor) -> Tensor {
+        (a.cholesky() * w).sum(vec![], false)
+    }
+
+    #[test]
+    fn test_cholesky_forward() {
+        #[trace]
+        fn f(a: Tensor) -> Tensor {
+            let l = a.cholesky();
+            l.matmul(l.t())
+        }
+
+        let a = Array::from_shape_vec(
+            (2, 3, 3),
+            vec![
+                4., 2., 0.4, 2., 5., 1., 0.4, 1., 3., //
+                9., -3., 1., -3., 2., 0., 1., 0., 6., //
+            ],
+        )
+        .unwrap()
+        .into_dyn();
+        let (out,) = trace_fn::<f64>(f).eval()(&a);
+        for (got, want) in out.iter().zip(a.iter()) {
+            assert!((got - want).abs() < 1e-12, "{out:?}");
+        }
+    }
+
+    #[test]
+    fn test_cholesky_grad_matches_finite_differences() {
+        let a = arr2(&[[4., 2., 0.4], [2., 5., 1.], [0.4, 1., 3.]]).into_dyn();
+        let w = arr2(&[[1., 0., 0.], [-2., 0.5, 0.], [0.25, 3., -1.]]).into_dyn();
+        let traced = trace_fn::<f64>(weighted_cholesky);
+        let (grad_a, _) = traced.grad().eval()((&a, &w));
+        assert!(
+            (grad_a.clone() - grad_a.t())
+                .iter()
+                .all(|d| d.abs() < 1e-12)
+        );
+
+        // the input is taken to be symmetric, so check through `s + s^T`,
+        // where perturbing one element of `s` keeps `a` symmetric.
+        #[trace]
+        fn symmetrized(s: Tensor, w: Tensor) -> Tensor {
+            ((s + s.t()).cholesky() * w).sum(vec![], false)
+        }
+        let half = a.mapv(|v| v / 2.);
+        assert_grad_matches_fd(&trace_fn::<f64>(symmetrized), &[half, w], 1e-6, 1e-6);
+    }
+
+    #[test]
+    fn test_cholesky_grad_through_gram_matrix() {
+        // a = x @ x^T + shift + shift^T is SPD for any x given this diagonal
+        // shift, and stays symmetric when one element of either is perturbed,
+        // so plain elementwise finite differences check the full chain.
+        #[trace]
+        fn f(x: Tensor, shift: Tensor, w: Tensor) -> Tensor {
+            let a = x.matmul(x.t()) + shift + shift.t();
+            (a.cholesky() * w).sum(vec![], false)
+        }
+
+        let x = arr2(&[[1., 0.5, -0.3], [0.2, 1.5, 0.1], [-0.4, 0.3, 0.8]]).into_dyn();
+        let shift = arr2(&[[1.5, 0., 0.], [0., 1.5, 0.], [0., 0., 1.5]]).into_dyn();
+        let w = arr2(&[[1., 0., 0.], [-2., 0.5, 0.], [0.25, 3., -1.]]).into_dyn();
+        let traced = trace_fn::<f64>(f);
+        assert_grad_matches_fd(&traced, &[x, shift, w], 1e-6, 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "cholesky: matrix 0 of a [2, 2] input is not positive definite")]
+    fn test_cholesky_not_positive_definite() {
+        let a = arr2(&[[1., 2.], [2., 1.]]).into_dyn();
+        let w = arr2(&[[1., 0.], [1., 1.]]).into_dyn();
+        let _: (TensorData<f64>,) = trace_fn::<f64>(weighted_cholesky).eval()((&a, &w));
+    }
+}
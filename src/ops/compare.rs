@@ -0,0 +1,147 @@
+use ndarray::Zip;
+
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer, identity::IdGenerator, ops::broadcast_shapes,
+    primitive_binary_op, tracing::TensorData,
+};
+
+/// elementwise `pick(x, y)` as a 0/1 mask, broadcasting like the arithmetic ops.
+fn compare<D: Floating>(
+    name: &str,
+    x: &TensorData<D>,
+    y: &TensorData<D>,
+    pick: impl Fn(D, D) -> bool,
+) -> TensorData<D> {
+    let shape = broadcast_shapes(x.shape(), y.shape()).unwrap_or_else(|| {
+        panic!(
+            "{name}: cannot broadcast {:?} with {:?}",
+            x.shape(),
+            y.shape()
+        )
+    });
+    let (x, y) = (x.broadcast(&*shape).unwrap(), y.broadcast(&*shape).unwrap());
+    Zip::from(&x)
+        .and(&y)
+        .map_collect(|&a, &b| if pick(a, b) { D::one() } else { D::zero() })
+}
+
+// the masks are piecewise constant, so none of these pass a gradient back.
+primitive_binary_op!(
+    Greater,
+    disp: "gt",
+    fwd: |x: &TensorData<D>, y: &TensorData<D>| compare("gt", x, y, |a, b| a > b),
+    vjp: |_: &Greater, _: &mut Graph<D>, _: Id| vec![]
+);
+
+primitive_binary_op!(
+    Less,
+    disp: "lt",
+    fwd: |x: &TensorData<D>, y: &TensorData<D>| compare("lt", x, y, |a, b| a < b),
+    vjp: |_: &Less, _: &mut Graph<D>, _: Id| vec![]
+);
+
+primitive_binary_op!(
+    Equal,
+    disp: "eq",
+    fwd: |x: &TensorData<D>, y: &TensorData<D>| compare("eq", x, y, |a, b| a == b),
+    vjp: |_: &Equal, _: &mut Graph<D>, _: Id| vec![]
+);
+
+// the `_mask` suffix keeps these apart from `PartialOrd::gt` and
+// `PartialEq::eq`: `#[trace]` routes method calls by name, so a bare `eq`
+// would also catch `a.eq(&b)` on plain Rust values.
+impl Tracer {
+    pub fn gt_mask(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn lt_mask(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn eq_mask(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    #[must_use]
+    pub fn gt_mask(&mut self, a: Tracer, b: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Greater::new(a.id(), b.id(), out), out)
+    }
+
+    #[must_use]
+    pub fn lt_mask(&mut self, a: Tracer, b: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Less::new(a.id(), b.id(), out), out)
+    }
+
+    #[must_use]
+    pub fn eq_mask(&mut self, a: Tracer, b: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Equal::new(a.id(), b.id(), out), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_compare_masks() {
+        #[trace]
+        fn gt(x: Tensor, y: Tensor) -> Tensor {
+            x.gt_mask(y)
+        }
+        #[trace]
+        fn lt(x: Tensor, y: Tensor) -> Tensor {
+            x.lt_mask(y)
+        }
+        #[trace]
+        fn eq(x: Tensor, y: Tensor) -> Tensor {
+            x.eq_mask(y)
+        }
+
+        let x = arr1(&[1., 2., 3.]).into_dyn();
+        let y = arr1(&[2., 2., 2.]).into_dyn();
+        let (out,) = trace_fn::<f32>(gt).eval()((&x, &y));
+        assert_eq!(out, arr1(&[0., 0., 1.]).into_dyn());
+        let (out,) = trace_fn::<f32>(lt).eval()((&x, &y));
+        assert_eq!(out, arr1(&[1., 0., 0.]).into_dyn());
+        let (out,) = trace_fn::<f32>(eq).eval()((&x, &y));
+        assert_eq!(out, arr1(&[0., 1., 0.]).into_dyn());
+
+        // broadcasts like the arithmetic ops.
+        let m = arr2(&[[1., 2., 3.], [3., 2., 1.]]).into_dyn();
+        let (out,) = trace_fn::<f32>(gt).eval()((&m, &y));
+        assert_eq!(out, arr2(&[[0., 0., 1.], [1., 0., 0.]]).into_dyn());
+    }
+
+    #[test]
+    fn test_compare_has_no_gradient() {
+        #[trace]
+        fn f(x: Tensor, y: Tensor) -> Tensor {
+            x.gt_mask(y) * x
+        }
+
+        let x = arr1(&[1., 2., 3.]).into_dyn();
+        let y = arr1(&[2., 2., 2.]).into_dyn();
+        let (gx, _) = trace_fn::<f32>(f).grad().eval()((&x, &y));
+        assert_eq!(gx, arr1(&[0., 0., 1.]).into_dyn());
+    }
+
+    #[test]
+    fn test_std_comparisons_inside_trace_are_left_alone() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            let shape = vec![3usize];
+            assert!(shape.eq(&vec![3]));
+            x
+        }
+
+        let x = arr1(&[1., 2., 3.]).into_dyn();
+        let (out,) = trace_fn::<f32>(f).eval()(&x);
+        assert_eq!(out, x);
+    }
+}
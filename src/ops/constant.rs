@@ -1,4 +1,13 @@
-use crate::{Floating, context::Context, graph::Graph, identity::Id, ops::Op};
+use std::hash::{DefaultHasher, Hash as _, Hasher as _};
+
+use crate::{
+    Floating, TraceSession, Tracer,
+    context::Context,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+    ops::{Op, broadcast::BroadcastLike},
+    tracing::TensorData,
+};
 
 #[derive(Debug, Clone)]
 pub struct Const<D: Floating> {
@@ -22,6 +31,21 @@ impl<D: Floating + 'static> Op<D> for Const<D> {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+    fn shape_rule(&self, _: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![vec![]]
+    }
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Const::boxed(self.value.as_f64(), self.out)
+    }
+    fn attrs(&self) -> Option<String> {
+        Some(format!("{:?}", self.value))
+    }
+    fn const_value(&self) -> Option<TensorData<D>> {
+        Some(ndarray::arr0(self.value).into_dyn())
+    }
     fn eval(&self, ctx: &mut Context<D>) {
         use ndarray::arr0;
         ctx.insert(self.out, arr0(self.value).into_dyn());
@@ -34,3 +58,172 @@ impl<D: Floating + 'static> Op<D> for Const<D> {
         "const"
     }
 }
+
+// A constant holding a full tensor, e.g. fixed pretrained weights.
+#[derive(Debug, Clone)]
+pub struct ConstTensor<D: Floating> {
+    pub value: TensorData<D>,
+    pub out: Id,
+}
+
+impl<D: Floating> ConstTensor<D> {
+    pub fn new(value: TensorData<D>, out: Id) -> Self {
+        Self { value, out }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for ConstTensor<D> {
+    fn inputs(&self) -> Vec<Id> {
+        vec![]
+    }
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+    fn shape_rule(&self, _: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![self.value.shape().to_vec()]
+    }
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(ConstTensor::new(self.value.mapv(D::as_f64), self.out))
+    }
+    fn const_value(&self) -> Option<TensorData<D>> {
+        Some(self.value.clone())
+    }
+    fn eval(&self, ctx: &mut Context<D>) {
+        ctx.insert(self.out, self.value.clone());
+    }
+    fn vjp(&self, _g: &mut Graph<D>, _out_grads: &[Id]) -> Option<Vec<Id>> {
+        None
+    }
+
+    fn name(&self) -> &'static str {
+        "const_tensor"
+    }
+
+    /// the shape and a hash of the value bits, so two constants that differ
+    /// only in their contents are still told apart without printing them.
+    fn attrs(&self) -> Option<String> {
+        let mut h = DefaultHasher::new();
+        for a in self.value.iter() {
+            a.as_f64().to_bits().hash(&mut h);
+        }
+        Some(format!("{:?} #{:016x}", self.value.shape(), h.finish()))
+    }
+}
+
+/// zeros shaped like `like` at runtime.
+#[derive(Debug, Clone)]
+pub struct ZerosLike {
+    pub like: Id,
+    pub out: Id,
+}
+
+impl ZerosLike {
+    pub fn new(like: Id, out: Id) -> Self {
+        Self { like, out }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for ZerosLike {
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.like]
+    }
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+    fn eval(&self, ctx: &mut Context<D>) {
+        let like = ctx.checked_get(&self.like);
+        ctx.insert(self.out, TensorData::zeros(like.shape()));
+    }
+    fn vjp(&self, _g: &mut Graph<D>, _out_grads: &[Id]) -> Option<Vec<Id>> {
+        // only the shape of `like` is read
+        None
+    }
+
+    fn name(&self) -> &'static str {
+        "zeros_like"
+    }
+}
+
+impl Tracer {
+    pub fn zeros_like(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn ones_like(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// zeros with `t`'s runtime shape; no gradient flows back to `t`.
+    #[must_use]
+    pub fn zeros_like(&mut self, t: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(ZerosLike::new(t.id(), out), out)
+    }
+
+    /// ones with `t`'s runtime shape; no gradient flows back to `t`.
+    #[must_use]
+    pub fn ones_like(&mut self, t: Tracer) -> Tracer {
+        let one = self.constant(D::one());
+        let out = self.g.fresh();
+        self.emit(BroadcastLike::new(one.id(), t.id(), out), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr0, arr1, arr2};
+
+    use crate::{Graph, TraceSession, TraceableFn, prelude::*};
+
+    #[test]
+    fn test_const_tensor() {
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let x = sess.input();
+        let w = sess.constant_tensor(arr1(&[1., 2., 3.]).into_dyn());
+        let y = sess.mul(x, w);
+
+        let traced = TraceableFn {
+            graph: g,
+            inputs: vec![x.id()],
+            outputs: vec![y.id()],
+        };
+
+        let x = arr1(&[4., 5., 6.]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, arr1(&[4., 10., 18.]).into_dyn());
+
+        let (grad_x,) = traced.grad().eval()(&x);
+        assert_eq!(grad_x, arr1(&[1., 2., 3.]).into_dyn());
+    }
+
+    #[test]
+    fn test_zeros_like_ones_like() {
+        #[trace]
+        fn count(x: Tensor) -> Tensor {
+            x.ones_like().sum(vec![], false) + x.zeros_like().sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(count);
+        let x = arr2(&[[1., 2., 3.], [4., 5., 6.]]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, arr0(x.len() as f32).into_dyn());
+
+        // only x's shape is read, so it gets no gradient.
+        let (gx,) = traced.grad().eval()(&x);
+        assert!(gx.iter().all(|&v| v == 0.));
+    }
+}
@@ -0,0 +1,229 @@
+use ndarray::Axis;
+
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Op, numel},
+    tracing::TensorData,
+};
+
+/// Batch-mean softmax cross-entropy of `(batch, classes)` logits against
+/// target distributions of the same shape, fused into one scalar op.
+#[derive(Debug, Clone)]
+pub struct MeanSoftmaxCrossEntropy {
+    logits: Id,
+    targets: Id,
+    out: Id,
+}
+
+impl MeanSoftmaxCrossEntropy {
+    pub fn new(logits: Id, targets: Id, out: Id) -> Self {
+        Self {
+            logits,
+            targets,
+            out,
+        }
+    }
+}
+
+fn check_shapes(logits: &[usize], targets: &[usize]) {
+    assert!(
+        logits.len() == 2,
+        "mean_softmax_cross_entropy: logits should be (batch, classes), got shape {logits:?}"
+    );
+    assert_eq!(
+        logits, targets,
+        "mean_softmax_cross_entropy: targets should match the logits shape"
+    );
+}
+
+// row-wise log softmax, shifted by the row max for stability
+fn log_softmax<D: Floating>(logits: &TensorData<D>) -> TensorData<D> {
+    let row_max = logits
+        .fold_axis(Axis(1), D::neg_infinity(), |acc, &x| acc.max(x))
+        .insert_axis(Axis(1));
+    let shifted = logits - &row_max;
+    let lse = shifted
+        .mapv(|x| x.exp())
+        .sum_axis(Axis(1))
+        .mapv(|s| s.ln())
+        .insert_axis(Axis(1));
+    shifted - &lse
+}
+
+impl<D: Floating + 'static> Op<D> for MeanSoftmaxCrossEntropy {
+    fn name(&self) -> &'static str {
+        "mean_softmax_cross_entropy"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let logits = ctx.checked_get(&self.logits);
+        let targets = ctx.checked_get(&self.targets);
+        check_shapes(logits.shape(), targets.shape());
+
+        let batch = D::from_f64(logits.shape()[0] as f64);
+        let total = (targets * &log_softmax(logits)).sum();
+        ctx.insert(self.out, TensorData::from_elem(vec![], -total / batch));
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d/dlogits  = og * (softmax(logits) - targets) / batch
+        // d/dtargets = -og * log_softmax(logits) / batch
+        let og = *out_grads.first()?;
+        let (grad_logits, grad_targets) = (g.fresh(), g.fresh());
+        g.push(Box::new(MeanSoftmaxCrossEntropyGrad {
+            logits: self.logits,
+            targets: self.targets,
+            og,
+            grad_logits,
+            grad_targets,
+        }));
+        Some(vec![grad_logits, grad_targets])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.logits, self.targets]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        check_shapes(&input_shapes[0], &input_shapes[1]);
+        vec![vec![]]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        // shift, exp, sum and the target-weighted sum per logit
+        4 * numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+// Backward helper: both input gradients of the fused loss in one pass
+#[derive(Debug, Clone)]
+pub struct MeanSoftmaxCrossEntropyGrad {
+    logits: Id,
+    targets: Id,
+    og: Id,
+    grad_logits: Id,
+    grad_targets: Id,
+}
+
+impl<D: Floating + 'static> Op<D> for MeanSoftmaxCrossEntropyGrad {
+    fn name(&self) -> &'static str {
+        "mean_softmax_cross_entropy_grad"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let logits = ctx.checked_get(&self.logits);
+        let targets = ctx.checked_get(&self.targets);
+        let og = ctx.checked_get(&self.og);
+        assert!(
+            og.len() == 1,
+            "mean_softmax_cross_entropy: expected a scalar output gradient, got shape {:?}",
+            og.shape()
+        );
+
+        let batch = D::from_f64(logits.shape()[0] as f64);
+        let scale = *og.iter().next().unwrap() / batch;
+        let log_probs = log_softmax(logits);
+        let grad_logits = (log_probs.mapv(|lp| lp.exp()) - targets).mapv(|d| d * scale);
+        let grad_targets = log_probs.mapv(|lp| -lp * scale);
+
+        ctx.insert(self.grad_logits, grad_logits);
+        ctx.insert(self.grad_targets, grad_targets);
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, _out_grads: &[Id]) -> Option<Vec<Id>> {
+        // a silent `None` here would make `grad().grad()` return zeros.
+        panic!("second-order gradient not supported for mean_softmax_cross_entropy")
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.logits, self.targets, self.og]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.grad_logits, self.grad_targets]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone(), input_shapes[1].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+impl Tracer {
+    pub fn mean_softmax_cross_entropy(&self, _targets: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    pub fn mean_softmax_cross_entropy(&mut self, logits: Tracer, targets: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(
+            MeanSoftmaxCrossEntropy::new(logits.id(), targets.id(), out),
+            out,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_mean_softmax_cross_entropy_matches_composed() {
+        use crate::prelude::*;
+        use ndarray::arr2;
+
+        #[trace]
+        fn fused(logits: Tensor, targets: Tensor) -> Tensor {
+            logits.mean_softmax_cross_entropy(targets)
+        }
+
+        #[trace]
+        fn composed(logits: Tensor, targets: Tensor) -> Tensor {
+            let log_probs = logits - logits.exp().sum(vec![1], true).log();
+            -(targets * log_probs)
+                .sum(vec![1], false)
+                .mean(vec![0], false)
+        }
+
+        let fused = trace_fn::<f64>(fused);
+        let composed = trace_fn::<f64>(composed);
+        let logits = arr2(&[[1.0, 2.0, 0.5], [-1.0, 0.0, 3.0]]).into_dyn();
+        let targets = arr2(&[[0.0, 1.0, 0.0], [0.2, 0.3, 0.5]]).into_dyn();
+
+        let (loss,) = fused.eval()((&logits, &targets));
+        let (expected,) = composed.eval()((&logits, &targets));
+        assert_eq!(loss.shape(), &[] as &[usize]);
+        assert!((&loss - &expected).iter().all(|d| d.abs() < 1e-12));
+
+        let (gl, gt) = fused.grad().eval()((&logits, &targets));
+        let (el, et) = composed.grad().eval()((&logits, &targets));
+        assert!((&gl - &el).iter().all(|d| d.abs() < 1e-12));
+        assert!((&gt - &et).iter().all(|d| d.abs() < 1e-12));
+    }
+
+    #[test]
+    #[should_panic(expected = "second-order gradient not supported for mean_softmax_cross_entropy")]
+    fn test_mean_softmax_cross_entropy_second_order_panics() {
+        use crate::prelude::*;
+
+        #[trace]
+        fn fused(logits: Tensor, targets: Tensor) -> Tensor {
+            logits.mean_softmax_cross_entropy(targets)
+        }
+
+        let _ = trace_fn::<f64>(fused).grad().grad();
+    }
+}
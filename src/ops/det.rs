@@ -0,0 +1,249 @@
+use ndarray::{ArrayView2, Axis};
+
+use crate::{
+    Floating, TraceSession, Tracer,
+    context::Context,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+    ops::{
+        Mul, Op,
+        inverse::Inverse,
+        linalg::{Lu, check_square},
+        numel,
+        sum::ReshapeForBroadcast,
+        transpose::TransposeDefault,
+    },
+    tracing::TensorData,
+};
+
+/// one value per square matrix over the last two axes of `a`.
+fn per_matrix<D: Floating>(
+    name: &str,
+    a: &TensorData<D>,
+    f: impl Fn(ArrayView2<D>) -> D,
+) -> TensorData<D> {
+    let shape = a.shape();
+    let n = check_square(name, shape);
+    let batch_shape = shape[..shape.len() - 2].to_vec();
+    let stacked = a
+        .to_shape((numel(&batch_shape) as usize, n, n))
+        .expect("reshape should succeed because the number of elements is preserved");
+    let values: Vec<D> = stacked.axis_iter(Axis(0)).map(f).collect();
+    TensorData::from_shape_vec(batch_shape, values)
+        .expect("one value per matrix should fill the batch shape")
+}
+
+/// `scale[..., None, None] * inv(a)^T`, the gradient shared by det and logdet.
+fn scaled_inverse_transpose<D: Floating + 'static>(g: &mut Graph<D>, scale: Id, a: Id) -> Id {
+    let inv = g.fresh();
+    g.push(Box::new(Inverse::new(a, inv)));
+    let inv_t = g.fresh();
+    g.push(Box::new(TransposeDefault::new(inv, inv_t)));
+    let scale = {
+        let out = g.fresh();
+        g.push(Box::new(ReshapeForBroadcast::new(
+            scale,
+            out,
+            [-2, -1],
+            false,
+        )));
+        out
+    };
+    let out = g.fresh();
+    g.push(Box::new(Mul::new(scale, inv_t, out)));
+    out
+}
+
+/// determinant of each square matrix over the last two axes.
+#[derive(Debug, Clone)]
+pub struct Det {
+    inp: Id,
+    out: Id,
+}
+
+impl Det {
+    pub fn new(inp: Id, out: Id) -> Self {
+        Self { inp, out }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for Det {
+    fn name(&self) -> &'static str {
+        "det"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let a = ctx.checked_get(&self.inp);
+        let result = per_matrix("det", a, |m| Lu::new(m).det());
+        ctx.insert(self.out, result);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d det(a) / da = det(a) * inv(a)^T
+        let og = *out_grads.first()?;
+        let scale = g.fresh();
+        g.push(Box::new(Mul::new(og, self.out, scale)));
+        Some(vec![scaled_inverse_transpose(g, scale, self.inp)])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let shape = &input_shapes[0];
+        check_square("det", shape);
+        vec![shape[..shape.len() - 2].to_vec()]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        let n = *input_shapes[0].last().unwrap() as u64;
+        numel(&input_shapes[0]) * n * 2 / 3
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+/// log of the absolute determinant of each square matrix over the last two
+/// axes, computed from the LU pivots so it does not overflow like `det`.
+#[derive(Debug, Clone)]
+pub struct LogDet {
+    inp: Id,
+    out: Id,
+}
+
+impl LogDet {
+    pub fn new(inp: Id, out: Id) -> Self {
+        Self { inp, out }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for LogDet {
+    fn name(&self) -> &'static str {
+        "logdet"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let a = ctx.checked_get(&self.inp);
+        let result = per_matrix("logdet", a, |m| Lu::new(m).log_abs_det());
+        ctx.insert(self.out, result);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d log|det(a)| / da = inv(a)^T
+        let og = *out_grads.first()?;
+        Some(vec![scaled_inverse_transpose(g, og, self.inp)])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let shape = &input_shapes[0];
+        check_square("logdet", shape);
+        vec![shape[..shape.len() - 2].to_vec()]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        let n = *input_shapes[0].last().unwrap() as u64;
+        numel(&input_shapes[0]) * n * 2 / 3
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+impl Tracer {
+    pub fn det(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn logdet(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// determinant of each square matrix over the last two axes of `a`.
+    #[must_use]
+    pub fn det(&mut self, a: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Det::new(a.id(), out), out)
+    }
+
+    /// `ln |det(a)|` for each square matrix over the last two axes of `a`.
+    /// the gradient is `inv(a)^T`, so a singular `a` panics in the backward pass.
+    #[must_use]
+    pub fn logdet(&mut self, a: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(LogDet::new(a.id(), out), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array, arr0, arr1, arr2};
+
+    use crate::{prelude::*, tests::assert_grad_matches_fd};
+
+    #[test]
+    fn test_det_logdet_forward() {
+        #[trace]
+        fn det(a: Tensor) -> Tensor {
+            a.det()
+        }
+        #[trace]
+        fn logdet(a: Tensor) -> Tensor {
+            a.logdet()
+        }
+
+        // the second matrix needs a row swap, so its sign comes from pivoting.
+        let a = Array::from_shape_vec((2, 2, 2), vec![2., 1., 1., 3., 0., 2., 3., 1.])
+            .unwrap()
+            .into_dyn();
+        let (d,) = trace_fn::<f64>(det).eval()(&a);
+        assert_eq!(d, arr1(&[5., -6.]).into_dyn());
+        let (ld,) = trace_fn::<f64>(logdet).eval()(&a);
+        assert!((ld[0] - 5f64.ln()).abs() < 1e-12 && (ld[1] - 6f64.ln()).abs() < 1e-12);
+
+        let singular = arr2(&[[1., 2.], [2., 4.]]).into_dyn();
+        let (d,) = trace_fn::<f64>(det).eval()(&singular);
+        assert_eq!(d, arr0(0.).into_dyn());
+
+        // NaN propagates through pivoting instead of panicking.
+        let nan = arr2(&[[1., f64::NAN], [2., 4.]]).into_dyn();
+        let (d,) = trace_fn::<f64>(det).eval()(&nan);
+        assert!(d.item().is_nan());
+    }
+
+    #[test]
+    fn test_logdet_grad_spd() {
+        #[trace]
+        fn f(a: Tensor) -> Tensor {
+            a.logdet()
+        }
+        let a = arr2(&[[4., 1., 0.5], [1., 3., 0.2], [0.5, 0.2, 2.]]).into_dyn();
+        assert_grad_matches_fd(&trace_fn::<f64>(f), &[a], 1e-6, 1e-6);
+    }
+
+    #[test]
+    fn test_det_grad() {
+        #[trace]
+        fn f(a: Tensor) -> Tensor {
+            a.det()
+        }
+        let a = arr2(&[[0., 2., 1.], [1., 0.5, 0.], [3., 1., 5.]]).into_dyn();
+        assert_grad_matches_fd(&trace_fn::<f64>(f), &[a], 1e-6, 1e-6);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     Graph, Id,
-    ops::{Const, Mul, Neg},
+    ops::{Const, Mul, Neg, sum::ReduceToLike},
     primitive_binary_op,
     tracing::TensorData,
 };
@@ -22,8 +22,10 @@ primitive_binary_op!(
             out
         };
         let grad_lhs = {
+            let prod = g.fresh();
+            g.push(Box::new(Mul::new(og, inv_rhs, prod)));
             let out = g.fresh();
-            g.push(Box::new(Mul::new(og, inv_rhs, out)));
+            g.push(Box::new(ReduceToLike::new(prod, this.lhs, out)));
             out
         };
         // d/dy (x/y) = -x / y^2
@@ -38,8 +40,12 @@ primitive_binary_op!(
             out
         };
         let grad_rhs = {
+            let local = g.fresh();
+            g.push(Box::new(Div::new(neg_x, y2, local)));
+            let prod = g.fresh();
+            g.push(Box::new(Mul::new(og, local, prod)));
             let out = g.fresh();
-            g.push(Box::new(Div::new(neg_x, y2, out)));
+            g.push(Box::new(ReduceToLike::new(prod, this.rhs, out)));
             out
         };
         vec![grad_lhs, grad_rhs]
@@ -0,0 +1,216 @@
+use ndarray::Axis;
+
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Op, numel},
+    tracing::TensorData,
+};
+
+/// Row lookup into a `(vocab, dim)` table with fixed indices, giving `(n, dim)`.
+#[derive(Debug, Clone)]
+pub struct Embedding {
+    table: Id,
+    out: Id,
+    indices: Vec<usize>,
+}
+
+impl Embedding {
+    pub fn new(table: Id, out: Id, indices: impl Into<Vec<usize>>) -> Self {
+        Self {
+            table,
+            out,
+            indices: indices.into(),
+        }
+    }
+}
+
+fn check_table(shape: &[usize], indices: &[usize]) {
+    assert!(
+        shape.len() == 2,
+        "embedding: table should be (vocab, dim), got shape {shape:?}"
+    );
+    if let Some(bad) = indices.iter().find(|&&i| i >= shape[0]) {
+        panic!(
+            "embedding: index {bad} is out of range for a vocab of {}",
+            shape[0]
+        );
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for Embedding {
+    fn name(&self) -> &'static str {
+        "embedding"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let table = ctx.checked_get(&self.table);
+        check_table(table.shape(), &self.indices);
+        ctx.insert(self.out, table.select(Axis(0), &self.indices));
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // scatter-add og rows back into a zero table; repeated indices accumulate
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(EmbeddingGrad::new(
+            og,
+            self.table,
+            out,
+            self.indices.clone(),
+        )));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.table]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        check_table(&input_shapes[0], &self.indices);
+        vec![vec![self.indices.len(), input_shapes[0][1]]]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("indices={:?}", self.indices))
+    }
+}
+
+// Backward helper: scatter-add `og` rows into a zero table shaped like `table`
+#[derive(Debug, Clone)]
+pub struct EmbeddingGrad {
+    og: Id,
+    table: Id,
+    out: Id,
+    indices: Vec<usize>,
+}
+
+impl EmbeddingGrad {
+    pub fn new(og: Id, table: Id, out: Id, indices: Vec<usize>) -> Self {
+        Self {
+            og,
+            table,
+            out,
+            indices,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for EmbeddingGrad {
+    fn name(&self) -> &'static str {
+        "embedding_grad"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let og = ctx.checked_get(&self.og);
+        let table = ctx.checked_get(&self.table);
+        assert_eq!(
+            og.shape(),
+            &[self.indices.len(), table.shape()[1]],
+            "embedding grad: og should have one row per index"
+        );
+
+        let mut grad = TensorData::<D>::zeros(table.shape());
+        for (row, &i) in og.axis_iter(Axis(0)).zip(&self.indices) {
+            grad.index_axis_mut(Axis(0), i)
+                .zip_mut_with(&row, |acc, &v| *acc = *acc + v);
+        }
+        ctx.insert(self.out, grad);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // scatter-add is linear in og, and its adjoint is the gather again;
+        // `table` only provides a shape.
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(Embedding::new(og, out, self.indices.clone())));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.og, self.table]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[1].clone()]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        numel(&input_shapes[0])
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("indices={:?}", self.indices))
+    }
+}
+
+impl Tracer {
+    pub fn embedding(&self, _indices: impl Into<Vec<usize>>) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// rows `indices` of the `(vocab, dim)` `table`, stacked into `(n, dim)`.
+    pub fn embedding(&mut self, table: Tracer, indices: impl Into<Vec<usize>>) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Embedding::new(table.id(), out, indices), out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_embedding_accumulates_repeated_indices() {
+        use crate::prelude::*;
+        use ndarray::arr2;
+
+        #[trace]
+        fn f(table: Tensor) -> Tensor {
+            let rows = table.embedding(vec![1, 3, 1]);
+            (rows * rows).sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let table = arr2(&[[1., 2.], [3., 4.], [5., 6.], [7., 8.]]).into_dyn();
+
+        let (out,) = traced.eval()(&table);
+        assert_eq!(out.item(), 2. * (9. + 16.) + 49. + 64.);
+
+        // row 1 is looked up twice, so its gradient is 2 * 2 * row.
+        let (grad,) = traced.grad().eval()(&table);
+        assert_eq!(
+            grad,
+            arr2(&[[0., 0.], [12., 16.], [0., 0.], [14., 16.]]).into_dyn()
+        );
+    }
+}
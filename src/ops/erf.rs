@@ -0,0 +1,129 @@
+use std::f64::consts::{FRAC_1_SQRT_2, FRAC_2_SQRT_PI};
+
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    identity::IdGenerator,
+    ops::{Const, Mul, exp::Exp, neg::Neg},
+    simple_unary_op,
+    tracing::TensorData,
+};
+
+simple_unary_op!(
+    Erf,
+    disp: "erf",
+    fwd: |x: &TensorData<D>| x.mapv(|a| D::from_f64(libm::erf(a.as_f64()))),
+    vjp: |this: &Erf, g: &mut Graph<D>, og: Id| {
+        // d/dx erf(x) = 2/sqrt(pi) * exp(-x^2)
+        let sq = g.fresh();
+        g.push(Box::new(Mul::new(this.inp, this.inp, sq)));
+        let neg = g.fresh();
+        g.push(Box::new(Neg::new(sq, neg)));
+        let gauss = g.fresh();
+        g.push(Box::new(Exp::new(neg, gauss)));
+        let scale = g.fresh();
+        g.push(Box::new(Const::new(D::from_f64(FRAC_2_SQRT_PI), scale)));
+        let deriv = g.fresh();
+        g.push(Box::new(Mul::new(gauss, scale, deriv)));
+        let out = g.fresh();
+        g.push(Box::new(Mul::new(og, deriv, out)));
+        out
+    }
+);
+
+impl Tracer {
+    pub fn erf(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn gelu(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    #[must_use]
+    pub fn erf(&mut self, a: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Erf::new(a.id(), out), out)
+    }
+
+    /// exact GELU, `x * (1 + erf(x / sqrt(2))) / 2`, rather than the tanh
+    /// approximation.
+    #[must_use]
+    pub fn gelu(&mut self, x: Tracer) -> Tracer {
+        let inv_sqrt2 = self.constant(D::from_f64(FRAC_1_SQRT_2));
+        let scaled = self.mul(x, inv_sqrt2);
+        let e = self.erf(scaled);
+        let one = self.constant(D::one());
+        let shifted = self.add(e, one);
+        let half = self.constant(D::from_f64(0.5));
+        let gate = self.mul(shifted, half);
+        self.mul(x, gate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr1;
+
+    use crate::{prelude::*, tests::assert_grad_matches_fd};
+
+    #[test]
+    fn test_erf_forward() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.erf()
+        }
+
+        // reference values from a high-precision table.
+        let x = arr1(&[0., 1e-8, 0.5, 1., 2., 2.5, 4., -1.5, -5.5]).into_dyn();
+        let want = [
+            0.,
+            1.128_379_167_095_512_6e-8,
+            0.520_499_877_813_046_5,
+            0.842_700_792_949_714_9,
+            0.995_322_265_018_952_7,
+            0.999_593_047_982_555,
+            0.999_999_984_582_742_1,
+            -0.966_105_146_475_310_7,
+            -0.999_999_999_999_992_7,
+        ];
+        let (out,) = trace_fn::<f64>(f).eval()(&x);
+        for (got, want) in out.iter().zip(want) {
+            assert!((got - want).abs() < 1e-15, "{got} vs {want}");
+        }
+    }
+
+    #[test]
+    fn test_erf_grad_matches_finite_differences() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.erf()
+        }
+
+        let traced = trace_fn::<f64>(f);
+        let x = arr1(&[-3., -1.2, -0.3, 0., 0.7, 2.4, 2.6]).into_dyn();
+        assert_grad_matches_fd(&traced, &[x], 1e-6, 1e-8);
+    }
+
+    #[test]
+    fn test_gelu_exact() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.gelu()
+        }
+
+        let x = arr1(&[-2., -0.5, 0., 1., 3.]).into_dyn();
+        let (out,) = trace_fn::<f64>(f).eval()(&x);
+        // reference: x * Phi(x) for the standard normal CDF Phi.
+        let want = [
+            -0.045_500_263_896_358_42,
+            -0.154_268_769_362_993_44,
+            0.,
+            0.841_344_746_068_542_9,
+            2.995_950_305_905_11,
+        ];
+        for (got, want) in out.iter().zip(want) {
+            assert!((got - want).abs() < 1e-12, "{out:?}");
+        }
+    }
+}
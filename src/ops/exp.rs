@@ -1,5 +1,10 @@
 use crate::{
-    Floating, Graph, Id, TraceSession, Tracer, ops::Mul, simple_unary_op, tracing::TensorData,
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Mul, Op, scalar_minmax::ScalarBoundMask},
+    simple_unary_op,
+    tracing::TensorData,
 };
 
 simple_unary_op!(
@@ -15,15 +20,109 @@ simple_unary_op!(
     }
 );
 
+/// `exp(min(x, max))`: an exponential that saturates instead of overflowing.
+///
+/// the gradient is zero wherever the clamp is active (`x >= max`).
+#[derive(Debug, Clone)]
+pub struct ExpClamped<D: Floating> {
+    pub inp: Id,
+    pub out: Id,
+    pub max: D,
+}
+
+impl<D: Floating> ExpClamped<D> {
+    pub fn new(inp: Id, out: Id, max: D) -> Self {
+        Self { inp, out, max }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for ExpClamped<D> {
+    fn name(&self) -> &'static str {
+        "exp_clamped"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let max = self.max;
+        ctx.insert(self.out, x.mapv(|a| a.min(max).exp()));
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // grad = og * exp(min(x, max)) * 1[x<max], reusing the forward output
+        let og = *out_grads.first()?;
+        let mask = g.fresh();
+        g.push(Box::new(ScalarBoundMask::new(
+            self.inp, mask, self.max, false,
+        )));
+        let local = g.fresh();
+        g.push(Box::new(Mul::new(self.out, mask, local)));
+        let prod = g.fresh();
+        g.push(Box::new(Mul::new(og, local, prod)));
+        Some(vec![prod])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(ExpClamped::new(self.inp, self.out, self.max.as_f64()))
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("max={:?}", self.max))
+    }
+}
+
 impl Tracer {
     pub fn exp(&self) -> Tracer {
         panic!("dummy operation - only allowed inside #[trace] function")
     }
+    pub fn exp_clamped(&self, _max: f64) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
 }
 
-impl<D: Floating + 'static> TraceSession<'_, D> {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
     pub fn exp(&mut self, a: Tracer) -> Tracer {
         let out = self.g.fresh();
         self.emit(Exp::new(a.id(), out), out)
     }
+
+    pub fn exp_clamped(&mut self, a: Tracer, max: D) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(ExpClamped::new(a.id(), out, max), out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_exp_clamped_saturates() {
+        use crate::prelude::*;
+        use ndarray::arr1;
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.exp_clamped(10.0)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let x = arr1(&[0., 1000.]).into_dyn();
+
+        let (out,) = traced.eval()(&x);
+        assert!(out.iter().all(|v| v.is_finite()));
+        assert_eq!(out, arr1(&[1., 10f32.exp()]).into_dyn());
+
+        let (grad_x,) = traced.grad().eval()(&x);
+        assert_eq!(grad_x, arr1(&[1., 0.]).into_dyn());
+    }
 }
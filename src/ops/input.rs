@@ -24,6 +24,22 @@ impl<D: Floating + 'static> Op<D> for Input {
         vec![self.out]
     }
 
+    fn shape_rule(&self, _: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        panic!("input: shape is runtime data and must be supplied by the caller")
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_input(&self) -> bool {
+        true
+    }
+
     fn eval(&self, _ctx: &mut Context<D>) {
         // no-op: input tensors are already loaded into Context by TraceableFn::eval
     }
@@ -0,0 +1,164 @@
+use ndarray::Array2;
+
+use crate::{
+    Floating, TraceSession, Tracer,
+    context::Context,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+    ops::{
+        MatMul, Op,
+        linalg::{Lu, check_square, map_matrices},
+        neg::Neg,
+        transpose::TransposeDefault,
+    },
+};
+
+/// Inverse of each square matrix over the last two axes.
+#[derive(Debug, Clone)]
+pub struct Inverse {
+    inp: Id,
+    out: Id,
+}
+
+impl Inverse {
+    pub fn new(inp: Id, out: Id) -> Self {
+        Self { inp, out }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for Inverse {
+    fn name(&self) -> &'static str {
+        "inverse"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let a = ctx.checked_get(&self.inp);
+        let result = map_matrices("inverse", a, |b, m| {
+            Lu::new(m)
+                .solve(Array2::eye(m.nrows()).view())
+                .unwrap_or_else(|| {
+                    panic!("inverse: matrix {b} of a {:?} input is singular", a.shape())
+                })
+        });
+        ctx.insert(self.out, result);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d/dA inv(A) contracted with og = -inv(A)^T @ og @ inv(A)^T
+        let og = *out_grads.first()?;
+        let inv_t = g.fresh();
+        g.push(Box::new(TransposeDefault::new(self.out, inv_t)));
+        let left = g.fresh();
+        g.push(Box::new(MatMul::new(inv_t, og, left)));
+        let both = g.fresh();
+        g.push(Box::new(MatMul::new(left, inv_t, both)));
+        let out = g.fresh();
+        g.push(Box::new(Neg::new(both, out)));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        check_square("inverse", &input_shapes[0]);
+        vec![input_shapes[0].clone()]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        // roughly 2n^3 per matrix: the LU factorisation plus n solves
+        let shape = &input_shapes[0];
+        let n = shape[shape.len() - 1] as u64;
+        let batch: u64 = shape[..shape.len() - 2].iter().product::<usize>() as u64;
+        2 * batch * n * n * n
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+impl Tracer {
+    pub fn inverse(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// inverse of each square matrix over the last two axes of `a`.
+    /// panics at eval if any of them is singular.
+    #[must_use]
+    pub fn inverse(&mut self, a: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Inverse::new(a.id(), out), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array, arr2};
+
+    use crate::{prelude::*, tests::assert_grad_matches_fd};
+
+    #[trace]
+    fn weighted_inverse(a: Tensor, w: Tensor) -> Tensor {
+        (a.inverse() * w).sum(vec![], false)
+    }
+
+    #[test]
+    fn test_inverse_forward() {
+        #[trace]
+        fn f(a: Tensor) -> Tensor {
+            a.inverse().matmul(a)
+        }
+
+        let a = Array::from_shape_vec(
+            (2, 3, 3),
+            vec![
+                4., 1., 0., 1., 3., 1., 0., 1., 2., // diagonally dominant
+                0., 2., 1., 1., 0., 0., 3., 1., 5., // needs a row swap
+            ],
+        )
+        .unwrap()
+        .into_dyn();
+        let (out,) = trace_fn::<f64>(f).eval()(&a);
+        let eye = Array::from_shape_fn((2, 3, 3), |(_, i, j)| if i == j { 1. } else { 0. });
+        for (x, e) in out.iter().zip(eye.iter()) {
+            assert!((x - e).abs() < 1e-12, "{out:?}");
+        }
+    }
+
+    #[test]
+    fn test_inverse_grad_matches_finite_differences() {
+        let a = arr2(&[[4., 1., 0.5], [1., 3., 1.], [0., 1., 2.]]).into_dyn();
+        let w = arr2(&[[1., -2., 0.5], [0.25, 1., 3.], [-1., 2., 1.]]).into_dyn();
+        let traced = trace_fn::<f64>(weighted_inverse);
+        assert_grad_matches_fd(&traced, &[a, w], 1e-6, 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "inverse: matrix 0 of a [2, 2] input is singular")]
+    fn test_inverse_singular() {
+        let a = arr2(&[[1., 2.], [2., 4.]]).into_dyn();
+        let w = arr2(&[[1., 1.], [1., 1.]]).into_dyn();
+        let _: (ndarray::ArrayD<f64>,) = trace_fn::<f64>(weighted_inverse).eval()((&a, &w));
+    }
+
+    #[test]
+    fn test_inverse_of_nan_matrix_is_nan() {
+        #[trace]
+        fn f(a: Tensor) -> Tensor {
+            a.inverse()
+        }
+
+        // pivoting compares with a total order, so NaN does not panic there.
+        let a = arr2(&[[f64::NAN, 1.], [2., 3.]]).into_dyn();
+        let (out,) = trace_fn::<f64>(f).eval()(&a);
+        assert!(out.iter().all(|v| v.is_nan()), "{out:?}");
+    }
+}
@@ -0,0 +1,357 @@
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{
+        Mul, Op, normalize_axes, numel,
+        sum::{ReduceToLike, sum_over},
+        unique_axes,
+    },
+    tracing::{ScalarArg, TensorData},
+};
+
+/// `gamma * (x - mean) / sqrt(var + eps) + beta`, with the mean and (biased)
+/// variance taken over `axis`; `gamma` and `beta` broadcast against `x`.
+#[derive(Debug, Clone)]
+pub struct LayerNorm<D: Floating> {
+    inp: Id,
+    gamma: Id,
+    beta: Id,
+    out: Id,
+    axis: Vec<isize>,
+    eps: D,
+}
+
+impl<D: Floating> LayerNorm<D> {
+    pub fn new(inp: Id, gamma: Id, beta: Id, out: Id, axis: impl Into<Vec<isize>>, eps: D) -> Self {
+        Self {
+            inp,
+            gamma,
+            beta,
+            out,
+            axis: unique_axes("layer_norm", axis),
+            eps,
+        }
+    }
+}
+
+/// `(x_hat, 1 / sqrt(var + eps))`, the latter keeping dims so it broadcasts.
+pub(crate) fn normalize<D: Floating>(
+    x: &TensorData<D>,
+    axis: &[isize],
+    eps: D,
+) -> (TensorData<D>, TensorData<D>) {
+    let axis = normalize_axes("layer_norm", axis, x.ndim());
+    let n = D::from_f64(reduced_count(x.shape(), &axis) as f64);
+    let mean = sum_over(x.clone(), &axis, true).mapv(|s| s / n);
+    let centered = x - &mean;
+    let var = sum_over(centered.mapv(|c| c * c), &axis, true).mapv(|s| s / n);
+    let inv_std = var.mapv(|v| D::one() / (v + eps).sqrt());
+    (centered * &inv_std, inv_std)
+}
+
+fn reduced_count(shape: &[usize], axis: &[usize]) -> usize {
+    if axis.is_empty() {
+        shape.iter().product()
+    } else {
+        axis.iter().map(|&ax| shape[ax]).product()
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for LayerNorm<D> {
+    fn name(&self) -> &'static str {
+        "layer_norm"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let gamma = ctx.checked_get(&self.gamma);
+        let beta = ctx.checked_get(&self.beta);
+        let (x_hat, _) = normalize(x, &self.axis, self.eps);
+        ctx.insert(self.out, x_hat * gamma + beta);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // grad_x     = closed form below (LayerNormInputGrad)
+        // grad_gamma = reduce_to_like(og * x_hat, like=gamma)
+        // grad_beta  = reduce_to_like(og, like=beta)
+        let og = *out_grads.first()?;
+
+        let grad_x = g.fresh();
+        g.push(Box::new(LayerNormInputGrad::new(
+            self.inp,
+            self.gamma,
+            og,
+            grad_x,
+            self.axis.clone(),
+            self.eps,
+        )));
+
+        let grad_gamma = {
+            let x_hat = g.fresh();
+            g.push(Box::new(LayerNormXHat::new(
+                self.inp,
+                x_hat,
+                self.axis.clone(),
+                self.eps,
+            )));
+            let scaled = g.fresh();
+            g.push(Box::new(Mul::new(og, x_hat, scaled)));
+            let out = g.fresh();
+            g.push(Box::new(ReduceToLike::new(scaled, self.gamma, out)));
+            out
+        };
+
+        let grad_beta = g.fresh();
+        g.push(Box::new(ReduceToLike::new(og, self.beta, grad_beta)));
+
+        Some(vec![grad_x, grad_gamma, grad_beta])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp, self.gamma, self.beta]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        normalize_axes("layer_norm", &self.axis, input_shapes[0].len());
+        vec![input_shapes[0].clone()]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        // mean, center, square, variance sum, scale, affine
+        7 * numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(LayerNorm::new(
+            self.inp,
+            self.gamma,
+            self.beta,
+            self.out,
+            self.axis.clone(),
+            self.eps.as_f64(),
+        ))
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("axis={:?} eps={:?}", self.axis, self.eps))
+    }
+}
+
+// Backward helper: the normalized input x_hat
+#[derive(Debug, Clone)]
+pub struct LayerNormXHat<D: Floating> {
+    inp: Id,
+    out: Id,
+    axis: Vec<isize>,
+    eps: D,
+}
+
+impl<D: Floating> LayerNormXHat<D> {
+    pub fn new(inp: Id, out: Id, axis: Vec<isize>, eps: D) -> Self {
+        Self {
+            inp,
+            out,
+            axis,
+            eps,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for LayerNormXHat<D> {
+    fn name(&self) -> &'static str {
+        "layer_norm_x_hat"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let (x_hat, _) = normalize(ctx.checked_get(&self.inp), &self.axis, self.eps);
+        ctx.insert(self.out, x_hat);
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, _out_grads: &[Id]) -> Option<Vec<Id>> {
+        // a silent `None` here would make `grad().grad()` return zeros.
+        panic!("second-order gradient not supported for layer_norm")
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(LayerNormXHat::new(
+            self.inp,
+            self.out,
+            self.axis.clone(),
+            self.eps.as_f64(),
+        ))
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("axis={:?} eps={:?}", self.axis, self.eps))
+    }
+}
+
+// Backward helper: closed-form input gradient. with g = og * gamma and N the
+// reduced count, grad_x = inv_std / N * (N * g - sum(g) - x_hat * sum(g * x_hat)).
+#[derive(Debug, Clone)]
+pub struct LayerNormInputGrad<D: Floating> {
+    inp: Id,
+    gamma: Id,
+    og: Id,
+    out: Id,
+    axis: Vec<isize>,
+    eps: D,
+}
+
+impl<D: Floating> LayerNormInputGrad<D> {
+    pub fn new(inp: Id, gamma: Id, og: Id, out: Id, axis: Vec<isize>, eps: D) -> Self {
+        Self {
+            inp,
+            gamma,
+            og,
+            out,
+            axis,
+            eps,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for LayerNormInputGrad<D> {
+    fn name(&self) -> &'static str {
+        "layer_norm_input_grad"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let gamma = ctx.checked_get(&self.gamma);
+        let og = ctx.checked_get(&self.og);
+
+        let (x_hat, inv_std) = normalize(x, &self.axis, self.eps);
+        let axis = normalize_axes("layer_norm", &self.axis, x.ndim());
+        let n = D::from_f64(reduced_count(x.shape(), &axis) as f64);
+
+        let g = og * gamma;
+        let sum_g = sum_over(g.clone(), &axis, true);
+        let sum_g_x_hat = sum_over(&g * &x_hat, &axis, true);
+        let grad = (g.mapv(|v| v * n) - &sum_g - x_hat * &sum_g_x_hat) * &inv_std.mapv(|s| s / n);
+        ctx.insert(self.out, grad);
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, _out_grads: &[Id]) -> Option<Vec<Id>> {
+        // a silent `None` here would make `grad().grad()` return zeros.
+        panic!("second-order gradient not supported for layer_norm")
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp, self.gamma, self.og]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(LayerNormInputGrad::new(
+            self.inp,
+            self.gamma,
+            self.og,
+            self.out,
+            self.axis.clone(),
+            self.eps.as_f64(),
+        ))
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("axis={:?} eps={:?}", self.axis, self.eps))
+    }
+}
+
+impl Tracer {
+    pub fn layer_norm(
+        &self,
+        _gamma: Tracer,
+        _beta: Tracer,
+        _axis: impl Into<Vec<isize>>,
+        _eps: f64,
+    ) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// layer normalization of `a` over `axis`, then `* gamma + beta`.
+    ///
+    /// `eps` is a [`ScalarArg`]: a float, or inside `#[trace]` a literal or
+    /// `f32`/`f64` hyperparameter, which are lifted into graph constants.
+    pub fn layer_norm(
+        &mut self,
+        a: Tracer,
+        gamma: Tracer,
+        beta: Tracer,
+        axis: impl Into<Vec<isize>>,
+        eps: impl ScalarArg,
+    ) -> Tracer {
+        let eps = eps.scalar(self);
+        let out = self.g.fresh();
+        let op = LayerNorm::new(a.id(), gamma.id(), beta.id(), out, axis, eps);
+        self.emit(op, out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{prelude::*, tests::assert_grad_matches_fd};
+    use ndarray::{arr1, arr2};
+
+    const EPS: f64 = 1e-5;
+
+    #[trace]
+    fn f(x: Tensor, gamma: Tensor, beta: Tensor, w: Tensor) -> Tensor {
+        (x.layer_norm(gamma, beta, vec![1], EPS) * w).sum(vec![], false)
+    }
+
+    #[test]
+    fn test_layer_norm_forward() {
+        let traced = trace_fn::<f64>(f);
+        let x = arr2(&[[1., 2., 3.], [2., 2., 5.]]).into_dyn();
+        let ones = arr1(&[1., 1., 1.]).into_dyn();
+        let zeros = arr1(&[0., 0., 0.]).into_dyn();
+        let (out,) = traced.eval()((&x, &ones, &zeros, &ones));
+        // each normalized row sums to zero
+        assert!(out.item().abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_layer_norm_grad_check() {
+        let traced = trace_fn::<f64>(f);
+        let args = [
+            arr2(&[[1., 2., 3.], [2., -2., 5.]]).into_dyn(),
+            arr1(&[0.5, 1.5, -1.]).into_dyn(),
+            arr1(&[0.1, 0.2, 0.3]).into_dyn(),
+            arr2(&[[1., -2., 0.5], [3., 1., -1.]]).into_dyn(),
+        ];
+        assert_grad_matches_fd(&traced, &args, 1e-6, 1e-5);
+    }
+
+    #[test]
+    #[should_panic(expected = "second-order gradient not supported for layer_norm")]
+    fn test_layer_norm_second_order_panics() {
+        let _ = trace_fn::<f64>(f).grad().grad();
+    }
+}
@@ -0,0 +1,171 @@
+use std::cmp::Ordering;
+
+use ndarray::{Array2, ArrayView2, Axis, Zip, s};
+
+use crate::{Floating, tracing::TensorData};
+
+/// Size `n` of the square matrices over the last two axes of `shape`.
+///
+/// The shape check shared by the linear algebra ops; `name` prefixes the
+/// panic message.
+pub(crate) fn check_square(name: &str, shape: &[usize]) -> usize {
+    let rank = shape.len();
+    assert!(
+        rank >= 2 && shape[rank - 1] == shape[rank - 2],
+        "{name}: expected square matrices over the last two axes, got {shape:?}"
+    );
+    shape[rank - 1]
+}
+
+/// `f` applied to each `[n, n]` matrix over the last two axes of `a`, with
+/// the results stacked back into `a`'s shape. `f` also gets the matrix's
+/// index in the flattened batch, for error messages.
+pub(crate) fn map_matrices<D: Floating>(
+    name: &str,
+    a: &TensorData<D>,
+    mut f: impl FnMut(usize, ArrayView2<D>) -> Array2<D>,
+) -> TensorData<D> {
+    let shape = a.shape();
+    let n = check_square(name, shape);
+    let batch: usize = shape[..shape.len() - 2].iter().product();
+
+    let stacked = a
+        .to_shape((batch, n, n))
+        .expect("reshape should succeed because the number of elements is preserved");
+    let mut result = TensorData::zeros(shape);
+    {
+        let mut out = result
+            .view_mut()
+            .into_shape_with_order((batch, n, n))
+            .expect("reshape should succeed because the number of elements is preserved");
+        for (b, m) in stacked.axis_iter(Axis(0)).enumerate() {
+            out.index_axis_mut(Axis(0), b).assign(&f(b, m));
+        }
+    }
+    result
+}
+
+/// LU factorisation `p a = l u` with partial pivoting, `l` unit lower and `u`
+/// upper triangular, both packed into one matrix.
+///
+/// The elimination routine shared by the linear algebra ops. A zero pivot
+/// marks `a` as singular; elimination carries on past it.
+pub(crate) struct Lu<D> {
+    lu: Array2<D>,
+    /// row `i` of `p a` is row `perm[i]` of `a`.
+    perm: Vec<usize>,
+    odd_swaps: bool,
+}
+
+impl<D: Floating> Lu<D> {
+    pub(crate) fn new(a: ArrayView2<D>) -> Self {
+        let n = a.nrows();
+        let mut lu = a.to_owned();
+        let mut perm: Vec<usize> = (0..n).collect();
+        let mut odd_swaps = false;
+        for col in 0..n {
+            // total order, so a NaN entry propagates instead of panicking.
+            let pivot = (col..n)
+                .max_by(|&i, &j| abs_cmp(lu[[i, col]], lu[[j, col]]))
+                .unwrap();
+            if pivot != col {
+                let (mut upper, mut lower) = lu.multi_slice_mut((s![col, ..], s![pivot, ..]));
+                Zip::from(&mut upper)
+                    .and(&mut lower)
+                    .for_each(std::mem::swap);
+                perm.swap(col, pivot);
+                odd_swaps = !odd_swaps;
+            }
+            let p = lu[[col, col]];
+            if p == D::zero() {
+                continue;
+            }
+            for row in col + 1..n {
+                let factor = lu[[row, col]] / p;
+                lu[[row, col]] = factor;
+                for j in col + 1..n {
+                    lu[[row, j]] = lu[[row, j]] - factor * lu[[col, j]];
+                }
+            }
+        }
+        Self {
+            lu,
+            perm,
+            odd_swaps,
+        }
+    }
+
+    /// `det(a)`: the product of the pivots, negated for an odd permutation.
+    /// exactly zero when `a` is singular.
+    pub(crate) fn det(&self) -> D {
+        let det = self.lu.diag().iter().fold(D::one(), |acc, &p| acc * p);
+        if self.odd_swaps { -det } else { det }
+    }
+
+    /// `ln |det(a)|`, summed from the pivots so it does not overflow like `det`.
+    pub(crate) fn log_abs_det(&self) -> D {
+        self.lu
+            .diag()
+            .iter()
+            .fold(D::zero(), |acc, &p| acc + p.abs().ln())
+    }
+
+    fn is_singular(&self) -> bool {
+        self.lu.diag().iter().any(|&p| p == D::zero())
+    }
+
+    /// `x` with `a x = b` for an `[n, k]` `b`; `None` if `a` is singular.
+    pub(crate) fn solve(&self, b: ArrayView2<D>) -> Option<Array2<D>> {
+        if self.is_singular() {
+            return None;
+        }
+        let (n, k) = (self.lu.nrows(), b.ncols());
+        let mut x = b.select(Axis(0), &self.perm);
+        // forward substitution with the unit lower factor, then back
+        // substitution with the upper one.
+        for row in 0..n {
+            for i in 0..row {
+                let factor = self.lu[[row, i]];
+                for j in 0..k {
+                    x[[row, j]] = x[[row, j]] - factor * x[[i, j]];
+                }
+            }
+        }
+        for row in (0..n).rev() {
+            for i in row + 1..n {
+                let factor = self.lu[[row, i]];
+                for j in 0..k {
+                    x[[row, j]] = x[[row, j]] - factor * x[[i, j]];
+                }
+            }
+            let scale = D::one() / self.lu[[row, row]];
+            x.row_mut(row).mapv_inplace(|v| v * scale);
+        }
+        Some(x)
+    }
+}
+
+/// Lower Cholesky factor of `a`, reading only its lower triangle; `None` if
+/// `a` is not positive definite. Not an LU: it needs no pivoting and keeps
+/// the symmetric input's lower triangle as the only source of truth.
+pub(crate) fn cholesky_lower<D: Floating>(a: ArrayView2<D>) -> Option<Array2<D>> {
+    let n = a.nrows();
+    let mut l = Array2::<D>::zeros((n, n));
+    for j in 0..n {
+        let diag = (0..j).fold(a[[j, j]], |acc, k| acc - l[[j, k]] * l[[j, k]]);
+        if diag <= D::zero() {
+            return None;
+        }
+        let ljj = diag.sqrt();
+        l[[j, j]] = ljj;
+        for i in j + 1..n {
+            let off = (0..j).fold(a[[i, j]], |acc, k| acc - l[[i, k]] * l[[j, k]]);
+            l[[i, j]] = off / ljj;
+        }
+    }
+    Some(l)
+}
+
+fn abs_cmp<D: Floating>(a: D, b: D) -> Ordering {
+    a.abs().as_f64().total_cmp(&b.abs().as_f64())
+}
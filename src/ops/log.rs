@@ -1,5 +1,6 @@
 use crate::{
     Floating, Graph, Id, TraceSession, Tracer,
+    identity::IdGenerator,
     ops::{Const, Mul, div::Div},
     simple_unary_op,
     tracing::TensorData,
@@ -29,7 +30,7 @@ impl Tracer {
     }
 }
 
-impl<D: Floating + 'static> TraceSession<'_, D> {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
     pub fn log(&mut self, a: Tracer) -> Tracer {
         let out = self.g.fresh();
         self.emit(Log::new(a.id(), out), out)
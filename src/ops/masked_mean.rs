@@ -0,0 +1,248 @@
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Op, normalize_axes, numel, reduced_shape, sum::sum_over, unique_axes},
+    tracing::TensorData,
+};
+
+/// `mask` broadcast up to the shape of `x`.
+fn broadcast_mask<D: Floating>(op: &str, mask: &TensorData<D>, x: &TensorData<D>) -> TensorData<D> {
+    mask.broadcast(x.shape())
+        .unwrap_or_else(|| {
+            panic!(
+                "{op}: cannot broadcast mask {:?} to {:?}",
+                mask.shape(),
+                x.shape()
+            )
+        })
+        .to_owned()
+}
+
+/// Mean of `x` over `axis`, counting only positions where the constant 0/1
+/// `mask` (broadcast to `x`) is 1: `sum(x * mask) / sum(mask)`.
+///
+/// A slice with no unmasked positions divides by zero and yields `nan`.
+/// `mask` gets no gradient.
+#[derive(Debug, Clone)]
+pub struct MaskedMean {
+    inp: Id,
+    mask: Id,
+    out: Id,
+    axis: Vec<isize>,
+    keep_dims: bool,
+}
+
+impl MaskedMean {
+    pub fn new(inp: Id, mask: Id, out: Id, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Self {
+        Self {
+            inp,
+            mask,
+            out,
+            axis: unique_axes("masked_mean", axis),
+            keep_dims,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for MaskedMean {
+    fn name(&self) -> &'static str {
+        "masked_mean"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let mask = broadcast_mask("masked_mean", ctx.checked_get(&self.mask), x);
+        let axis = normalize_axes("masked_mean", &self.axis, x.ndim());
+
+        let total = sum_over(x * &mask, &axis, self.keep_dims);
+        let count = sum_over(mask, &axis, self.keep_dims);
+        ctx.insert(self.out, total / count);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d/dx = broadcast(og / sum(mask, axis)) * mask; nothing for the mask.
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(MaskedMeanGrad {
+            og,
+            like: self.inp,
+            mask: self.mask,
+            out,
+            axis: self.axis.clone(),
+            keep_dims: self.keep_dims,
+        }));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp, self.mask]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let axis = normalize_axes("masked_mean", &self.axis, input_shapes[0].len());
+        vec![reduced_shape(&input_shapes[0], &axis, self.keep_dims)]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        // a multiply and two adds per element
+        3 * numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("axis={:?} keep_dims={}", self.axis, self.keep_dims))
+    }
+}
+
+// Backward helper: spreads og / count back over the unmasked positions of `like`.
+#[derive(Debug, Clone)]
+pub struct MaskedMeanGrad {
+    og: Id,
+    like: Id,
+    mask: Id,
+    out: Id,
+    axis: Vec<isize>,
+    keep_dims: bool,
+}
+
+impl<D: Floating + 'static> Op<D> for MaskedMeanGrad {
+    fn name(&self) -> &'static str {
+        "masked_mean_grad"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let like = ctx.checked_get(&self.like);
+        let mask = broadcast_mask("masked_mean_grad", ctx.checked_get(&self.mask), like);
+        let axis = normalize_axes("masked_mean_grad", &self.axis, like.ndim());
+
+        let kept = reduced_shape(like.shape(), &axis, true);
+        let og = ctx
+            .checked_get(&self.og)
+            .to_shape(kept)
+            .expect("masked_mean_grad: og should have the reduced shape")
+            .to_owned();
+        let count = sum_over(mask.clone(), &axis, true);
+        ctx.insert(self.out, mask * &(og / count));
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // linear in og, and its adjoint is the masked mean itself.
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(MaskedMean::new(
+            og,
+            self.mask,
+            out,
+            self.axis.clone(),
+            self.keep_dims,
+        )));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.og, self.like, self.mask]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        // [og, like, mask]
+        vec![input_shapes[1].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("axis={:?} keep_dims={}", self.axis, self.keep_dims))
+    }
+}
+
+impl Tracer {
+    pub fn masked_mean(
+        &self,
+        _mask: Tracer,
+        _axis: impl Into<Vec<isize>>,
+        _keep_dims: bool,
+    ) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    pub fn masked_mean(
+        &mut self,
+        a: Tracer,
+        mask: Tracer,
+        axis: impl Into<Vec<isize>>,
+        keep_dims: bool,
+    ) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(
+            MaskedMean::new(a.id(), mask.id(), out, axis, keep_dims),
+            out,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_masked_mean_matches_composed() {
+        #[trace]
+        fn fused(x: Tensor, mask: Tensor) -> Tensor {
+            x.masked_mean(mask, vec![1], false)
+        }
+        #[trace]
+        fn composed(x: Tensor, mask: Tensor) -> Tensor {
+            (x * mask).sum(vec![1], false) / mask.sum(vec![1], false)
+        }
+
+        // two sequences of length 3 and 2, padded to 4.
+        let x = arr2(&[[1., 2., 3., 9.], [4., -2., 7., 7.]]).into_dyn();
+        let mask = arr2(&[[1., 1., 1., 0.], [1., 1., 0., 0.]]).into_dyn();
+
+        let fused = trace_fn::<f32>(fused);
+        let composed = trace_fn::<f32>(composed);
+        let (out,) = fused.eval()((&x, &mask));
+        assert_eq!(out, arr1(&[2., 1.]).into_dyn());
+        let (expected,) = composed.eval()((&x, &mask));
+        assert_eq!(out, expected);
+
+        let (gx, _) = fused.grad().eval()((&x, &mask));
+        let (ex, _) = composed.grad().eval()((&x, &mask));
+        assert_eq!(gx, ex);
+        assert_eq!(
+            gx,
+            arr2(&[[1. / 3., 1. / 3., 1. / 3., 0.], [0.5, 0.5, 0., 0.]]).into_dyn()
+        );
+    }
+
+    #[test]
+    fn test_masked_mean_all_masked_is_nan() {
+        #[trace]
+        fn f(x: Tensor, mask: Tensor) -> Tensor {
+            x.masked_mean(mask, vec![], false)
+        }
+
+        let x = arr1(&[1., 2.]).into_dyn();
+        let mask = arr1(&[0., 0.]).into_dyn();
+        let (out,): (ndarray::ArrayD<f32>,) = trace_fn::<f32>(f).eval()((&x, &mask));
+        assert!(out.iter().all(|v| v.is_nan()));
+    }
+}
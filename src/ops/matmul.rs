@@ -1,8 +1,8 @@
 use crate::{
     Graph, Tracer,
     context::Context,
-    identity::Id,
-    ops::{Op, transpose::TransposeDefault},
+    identity::{Id, IdGenerator},
+    ops::{Op, sum::ReduceToLike, transpose::TransposeDefault},
 };
 use ndarray::{
     Array, ArrayD, ArrayView1, Ix1, Ix2, IxDyn,
@@ -29,32 +29,52 @@ fn batched_matmul<D: Floating + 'static>(a: &ArrayD<D>, b: &ArrayD<D>) -> ArrayD
 
     let batch_a = &shape_a[..shape_a.len() - 2];
     let batch_b = &shape_b[..shape_b.len() - 2];
-    let batch_shape = super::broadcast_shapes(batch_a, batch_b)
-        .expect("batch dimensions should be broadcast-compatible");
-
-    let bc_shape_a: Vec<usize> = batch_shape.iter().copied().chain([m, k1]).collect();
-    let bc_shape_b: Vec<usize> = batch_shape.iter().copied().chain([k2, n]).collect();
-
-    let a_bc = a
-        .broadcast(IxDyn(&bc_shape_a))
-        .expect("broadcasting to a derived valid shape should be infallible ");
-    let b_bc = b
-        .broadcast(IxDyn(&bc_shape_b))
-        .expect("broadcasting to a derived valid shape should be infallible ");
+    let batch_shape = super::broadcast_shapes(batch_a, batch_b).unwrap_or_else(|| {
+        panic!("batched matmul: batch dims of {shape_a:?} and {shape_b:?} do not broadcast")
+    });
 
     let result_shape: Vec<usize> = batch_shape.iter().copied().chain([m, n]).collect();
     let mut result = ArrayD::zeros(IxDyn(&result_shape));
 
     let batch_elems: usize = batch_shape.iter().product();
-    let a_reshaped = a_bc
-        .to_shape((batch_elems, m, k1))
-        .expect("reshape should succeed because the number of elements is preserved");
-    let b_reshaped = b_bc
-        .to_shape((batch_elems, k2, n))
-        .expect("reshape should succeed because the number of elements is preserved");
-    let binding = result.view_mut();
-    let mut r_reshaped = binding
-        .to_shape((batch_elems, m, n))
+    let (a_reshaped, b_reshaped) = if batch_a == batch_b {
+        // fast path: equal batch shapes need no broadcasting. reshaping the operands
+        // directly is a free view for standard-layout inputs, whereas flattening a
+        // broadcast view always allocates a copy.
+        let a_reshaped = a
+            .to_shape((batch_elems, m, k1))
+            .expect("reshape should succeed because the number of elements is preserved");
+        let b_reshaped = b
+            .to_shape((batch_elems, k2, n))
+            .expect("reshape should succeed because the number of elements is preserved");
+        (a_reshaped, b_reshaped)
+    } else {
+        let bc_shape_a: Vec<usize> = batch_shape.iter().copied().chain([m, k1]).collect();
+        let bc_shape_b: Vec<usize> = batch_shape.iter().copied().chain([k2, n]).collect();
+
+        let a_bc = a
+            .broadcast(IxDyn(&bc_shape_a))
+            .expect("broadcasting to a derived valid shape should be infallible ");
+        let b_bc = b
+            .broadcast(IxDyn(&bc_shape_b))
+            .expect("broadcasting to a derived valid shape should be infallible ");
+
+        let a_reshaped = a_bc
+            .to_shape((batch_elems, m, k1))
+            .expect("reshape should succeed because the number of elements is preserved")
+            .into_owned();
+        let b_reshaped = b_bc
+            .to_shape((batch_elems, k2, n))
+            .expect("reshape should succeed because the number of elements is preserved")
+            .into_owned();
+        (a_reshaped.into(), b_reshaped.into())
+    };
+
+    // `to_shape` would hand back a copy-on-write array and the writes would be lost,
+    // so reshape the freshly allocated (standard layout) result as a mutable view.
+    let mut r_reshaped = result
+        .view_mut()
+        .into_shape_with_order((batch_elems, m, n))
         .expect("reshape should succeed because the number of elements is preserved");
 
     ndarray::Zip::from(a_reshaped.outer_iter())
@@ -176,6 +196,42 @@ impl MatMul {
     pub fn new(lhs: Id, rhs: Id, out: Id) -> Self {
         Self { lhs, rhs, out }
     }
+
+    /// panics naming both full shapes and the tensors they belong to,
+    /// rather than failing deep inside the kernel.
+    fn check_shapes(&self, lhs: &[usize], rhs: &[usize]) {
+        if let Some(reason) = matmul_mismatch(lhs, rhs) {
+            panic!(
+                "matmul: cannot multiply {lhs:?} (tensor {:?}) by {rhs:?} (tensor {:?}): {reason}",
+                self.lhs, self.rhs
+            );
+        }
+    }
+}
+
+/// why `lhs @ rhs` is undefined, if it is.
+fn matmul_mismatch(lhs: &[usize], rhs: &[usize]) -> Option<String> {
+    let (k1, k2) = match (lhs.len(), rhs.len()) {
+        (0, _) | (_, 0) => return None,
+        (1, 1) | (1, 2) => (lhs[0], rhs[0]),
+        (2, 1) => (lhs[1], rhs[0]),
+        (1, _) | (_, 1) => {
+            return Some("a vector operand needs a vector or matrix on the other side".into());
+        }
+        (r1, r2) => (lhs[r1 - 1], rhs[r2 - 2]),
+    };
+    if k1 != k2 {
+        return Some(format!("contracted dims differ ({k1} vs {k2})"));
+    }
+    if lhs.len() > 2 || rhs.len() > 2 {
+        let (batch_a, batch_b) = (&lhs[..lhs.len() - 2], &rhs[..rhs.len() - 2]);
+        if super::broadcast_shapes(batch_a, batch_b).is_none() {
+            return Some(format!(
+                "batch dims {batch_a:?} and {batch_b:?} do not broadcast"
+            ));
+        }
+    }
+    None
 }
 
 impl<D: Floating + 'static> Op<D> for MatMul {
@@ -191,15 +247,45 @@ impl<D: Floating + 'static> Op<D> for MatMul {
         vec![self.out]
     }
 
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        self.check_shapes(&input_shapes[0], &input_shapes[1]);
+        vec![infer_matmul_shape(&input_shapes[0], &input_shapes[1])]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], output_shapes: &[Vec<usize>]) -> u64 {
+        // one multiply and one add per contracted element of every output
+        let out = super::numel(&output_shapes[0]);
+        match (input_shapes[0].last(), input_shapes[1].is_empty()) {
+            (Some(&k), false) => 2 * out * k as u64,
+            _ => out,
+        }
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
     fn eval(&self, ctx: &mut Context<D>) {
         let lhs = ctx.checked_get(&self.lhs);
         let rhs = ctx.checked_get(&self.rhs);
+        self.check_shapes(lhs.shape(), rhs.shape());
         ctx.insert(self.out, matmul(lhs, rhs));
     }
 
     fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
         let og = *out_grads.first()?;
 
+        // an operand broadcast across batch dims, e.g. the shared (k, n) rhs
+        // of a (batch, m, k) @ (k, n), gets its gradient summed back to shape.
+        // the vjp sees no shapes, and even equal ranks can broadcast, e.g.
+        // (1, m, k) @ (b, k, n), so both sides always get the node; it passes
+        // the gradient through untouched when the shapes already agree.
+        let reduce_to = |g: &mut Graph<D>, grad: Id, like: Id| {
+            let out = g.fresh();
+            g.push(Box::new(ReduceToLike::new(grad, like, out)));
+            out
+        };
+
         let rhs_t = {
             let out = g.fresh();
             let transpose = TransposeDefault::new(self.rhs, out);
@@ -211,7 +297,7 @@ impl<D: Floating + 'static> Op<D> for MatMul {
             let out = g.fresh();
             let matmul = MatMul::new(og, rhs_t, out);
             g.push(Box::new(matmul));
-            out
+            reduce_to(g, out, self.lhs)
         };
 
         let lhs_t = {
@@ -225,23 +311,42 @@ impl<D: Floating + 'static> Op<D> for MatMul {
             let out = g.fresh();
             let matmul = MatMul::new(lhs_t, og, out);
             g.push(Box::new(matmul));
-            out
+            reduce_to(g, out, self.rhs)
         };
 
         Some(vec![grad_lhs, grad_rhs])
     }
+    fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        Some(Box::new(Self::new(inputs[0], inputs[1], self.out)))
+    }
 }
 
-impl<D: Floating + 'static> crate::tracing::session::TraceSession<'_, D> {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>>
+    crate::tracing::session::TraceSession<'_, D, G>
+{
     #[must_use]
     pub fn matmul(&mut self, a: Tracer, b: Tracer) -> Tracer {
         let out = self.g.fresh();
         self.emit(MatMul::new(a.id(), b.id(), out), out)
     }
+
+    /// a dense layer, `x.matmul(w) + b`, with `b` broadcast over the batch
+    /// axis as in [`add_bias`](Self::add_bias).
+    #[must_use]
+    pub fn linear(&mut self, x: Tracer, w: Tracer, b: Tracer) -> Tracer {
+        let xw = self.matmul(x, w);
+        self.add_bias(xw, b)
+    }
 }
 
 impl Tracer {
-    pub fn matmul(&self, _: Tracer) -> Tracer {
+    /// `x.matmul(w)` lowers to `sess.matmul(x, w)`. There is no operator for
+    /// it: `*` on tracers is elementwise.
+    pub fn matmul(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// `x.linear(w, b)` is `x.matmul(w) + b`.
+    pub fn linear(&self, _w: Tracer, _b: Tracer) -> Tracer {
         panic!("dummy operation - only allowed inside #[trace] function")
     }
 }
@@ -268,8 +373,9 @@ pub fn infer_matmul_shape(lhs: &[usize], rhs: &[usize]) -> Vec<usize> {
         _ => {
             let batch_a = &lhs[..lhs.len() - 2];
             let batch_b = &rhs[..rhs.len() - 2];
-            let batch_shape = super::broadcast_shapes(batch_a, batch_b)
-                .expect("batch dims broadcastable for matmul");
+            let batch_shape = super::broadcast_shapes(batch_a, batch_b).unwrap_or_else(|| {
+                panic!("matmul: batch dims of {lhs:?} and {rhs:?} do not broadcast")
+            });
 
             let m = lhs[lhs.len() - 2];
             let n = rhs[rhs.len() - 1];
@@ -285,7 +391,7 @@ pub fn infer_matmul_shape(lhs: &[usize], rhs: &[usize]) -> Vec<usize> {
 mod tests {
     use ndarray::arr2;
 
-    use crate::prelude::*;
+    use crate::{Graph, TraceSession, prelude::*, tests::assert_grad_matches_fd};
 
     #[test]
     fn test_matmul() {
@@ -304,4 +410,120 @@ mod tests {
         let expected = x.dot(&w);
         assert_eq!(out, expected.into_dyn());
     }
+
+    #[test]
+    fn test_matmul_method_matches_session_call() {
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            x.matmul(w)
+        }
+
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let x = sess.input();
+        let w = sess.input();
+        let _ = sess.matmul(x, w);
+
+        assert_eq!(trace_fn::<f32>(f).graph.to_ssa(), g.to_ssa());
+    }
+
+    #[test]
+    fn test_batched_matmul_equal_and_broadcast_batches() {
+        use ndarray::{Array, Array3, Axis};
+
+        let a: Array3<f32> =
+            Array::from_shape_fn((2, 2, 3), |(b, i, j)| (b * 6 + i * 3 + j) as f32);
+        let b: Array3<f32> = Array::from_shape_fn((2, 3, 2), |(b, i, j)| (b + i * 2 + j) as f32);
+
+        // equal batch shapes take the no-broadcast fast path
+        let out = super::matmul(&a.clone().into_dyn(), &b.clone().into_dyn());
+        assert_eq!(out.shape(), &[2, 2, 2]);
+        for (i, batch) in out.outer_iter().enumerate() {
+            let expected = a.index_axis(Axis(0), i).dot(&b.index_axis(Axis(0), i));
+            assert_eq!(batch, expected.into_dyn());
+        }
+
+        // a (1, 2, 3) lhs broadcasts against the (2, 3, 2) rhs
+        let a0 = a.index_axis(Axis(0), 0).insert_axis(Axis(0)).to_owned();
+        let out = super::matmul(&a0.into_dyn(), &b.clone().into_dyn());
+        assert_eq!(out.shape(), &[2, 2, 2]);
+        for (i, batch) in out.outer_iter().enumerate() {
+            let expected = a.index_axis(Axis(0), 0).dot(&b.index_axis(Axis(0), i));
+            assert_eq!(batch, expected.into_dyn());
+        }
+    }
+
+    #[trace]
+    fn batched(x: Tensor, w: Tensor) -> Tensor {
+        x.matmul(w)
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "matmul: cannot multiply [2, 4, 3] (tensor Id(1)) by [3, 3, 5] (tensor Id(2)): batch dims [2] and [3] do not broadcast"
+    )]
+    fn test_batched_matmul_mismatch_names_shapes() {
+        let x = ndarray::Array::zeros((2, 4, 3)).into_dyn();
+        let w = ndarray::Array::zeros((3, 3, 5)).into_dyn();
+        let _: (ndarray::ArrayD<f32>,) = trace_fn::<f32>(batched).eval()((&x, &w));
+    }
+
+    #[test]
+    #[should_panic(expected = "contracted dims differ (3 vs 4)")]
+    fn test_matmul_inner_mismatch_in_shape_inference() {
+        let _ = trace_fn::<f32>(batched).output_shapes(&[vec![8, 2, 3], vec![4, 5]]);
+    }
+
+    #[test]
+    fn test_batched_matmul_shared_rhs_grad_matches_finite_differences() {
+        use ndarray::Array;
+
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            let y = x.matmul(w);
+            (y * y).sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f64>(f);
+        let x = Array::from_shape_fn((4, 5, 3), |(b, i, j)| {
+            ((b * 15 + i * 3 + j) as f64 * 0.37).sin()
+        })
+        .into_dyn();
+        let w = Array::from_shape_fn((3, 2), |(i, j)| (i as f64 - j as f64 * 0.5) * 0.3).into_dyn();
+        let (gx, gw) = traced.grad().eval()((&x, &w));
+        assert_eq!(gx.shape(), &[4, 5, 3]);
+        assert_eq!(gw.shape(), &[3, 2]);
+
+        assert_grad_matches_fd(&traced, &[x, w], 1e-6, 1e-6);
+    }
+
+    #[test]
+    fn test_linear_matches_dense() {
+        #[trace]
+        fn dense(w: Tensor, x: Tensor, b: Tensor) -> Tensor {
+            x.matmul(w) + b
+        }
+        #[trace]
+        fn linear(w: Tensor, x: Tensor, b: Tensor) -> Tensor {
+            x.linear(w, b)
+        }
+
+        let w = arr2(&[[1., 2.], [3., 4.]]).into_dyn();
+        let x = arr2(&[[1., 1.], [2., 0.], [0.5, -1.]]).into_dyn();
+        let b = ndarray::arr1(&[0.5, -0.5]).into_dyn();
+
+        let dense = trace_fn::<f32>(dense);
+        let linear = trace_fn::<f32>(linear);
+        let (expected,) = dense.eval()((&w, &x, &b));
+        let (out,) = linear.eval()((&w, &x, &b));
+        assert_eq!(out, expected);
+
+        let (gw, gx, gb) = linear.grad().eval()((&w, &x, &b));
+        assert_eq!(gw, arr2(&[[3.5, 3.5], [0., 0.]]).into_dyn());
+        assert_eq!(gx, arr2(&[[3., 7.], [3., 7.], [3., 7.]]).into_dyn());
+        // the bias gradient sums over the batch of 3
+        assert_eq!(gb, ndarray::arr1(&[3., 3.]).into_dyn());
+        let (dw, dx, db) = dense.grad().eval()((&w, &x, &b));
+        assert_eq!((gw, gx, gb), (dw, dx, db));
+    }
 }
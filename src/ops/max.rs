@@ -1,29 +1,59 @@
-use ndarray::Axis;
-
 use crate::{
     Floating, Graph, Id, TraceSession, Tracer,
     context::Context,
-    ops::{Op, broadcast::BroadcastLike, div::Div, mul::Mul, sum::Sum},
+    identity::IdGenerator,
+    ops::{
+        Op,
+        broadcast::BroadcastLike,
+        div::Div,
+        mul::Mul,
+        normalize_axes, numel,
+        reduce::reduce_axes,
+        reduced_shape,
+        sum::{ReshapeForBroadcast, Sum},
+        unique_axes,
+    },
+    tracing::TensorData,
 };
 
+/// How the gradient of `max` is routed when several elements tie for the maximum.
+///
+/// `Distribute` splits it evenly across the ties; `First` sends all of it to the
+/// first tie in row-major order, as TensorFlow does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TieBreak {
+    #[default]
+    Distribute,
+    First,
+}
+
 #[derive(Debug, Clone)]
 pub struct Max {
     inp: Id,
     out: Id,
-    axis: Vec<usize>,
+    axis: Vec<isize>,
     keep_dims: bool,
+    tie_break: TieBreak,
 }
 
 impl Max {
-    pub fn new(inp: Id, out: Id, axis: impl Into<Vec<usize>>, keep_dims: bool) -> Self {
-        let mut axis = axis.into();
-        // Reduce higher axes first to keep indexing valid as dims shrink
-        axis.sort_unstable_by(|a, b| b.cmp(a));
+    pub fn new(inp: Id, out: Id, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Self {
+        Self::with_tie_break(inp, out, axis, keep_dims, TieBreak::default())
+    }
+
+    pub fn with_tie_break(
+        inp: Id,
+        out: Id,
+        axis: impl Into<Vec<isize>>,
+        keep_dims: bool,
+        tie_break: TieBreak,
+    ) -> Self {
         Self {
             inp,
             out,
-            axis,
+            axis: unique_axes("max", axis),
             keep_dims,
+            tie_break,
         }
     }
 }
@@ -34,21 +64,11 @@ impl<D: Floating + 'static> Op<D> for Max {
     }
 
     fn eval(&self, ctx: &mut Context<D>) {
-        let mut t = ctx.checked_get(&self.inp).clone();
-        for ax in &self.axis {
-            let a = Axis(*ax);
-            let reduced = t.fold_axis(
-                a,
-                D::neg_infinity(),
-                |acc, x| if acc > x { *acc } else { *x },
-            );
-
-            t = if self.keep_dims {
-                reduced.insert_axis(a)
-            } else {
-                reduced
-            };
-        }
+        let t = ctx.checked_get(&self.inp).clone();
+        let axis = normalize_axes("max", &self.axis, t.ndim());
+        let t = reduce_axes(t, &axis, self.keep_dims, D::neg_infinity(), |acc, x| {
+            if acc > x { *acc } else { *x }
+        });
         ctx.insert(self.out, t);
     }
 
@@ -59,40 +79,57 @@ impl<D: Floating + 'static> Op<D> for Max {
         // - mask = 1[x == y_broadcast]
         // - count = sum(mask, axis)
         // - grad = (og_broadcast * mask) / broadcast_like(count, like=x)
+        // with TieBreak::First the mask keeps one tie per reduced slice, so
+        // count is always 1 and the division is skipped.
         let og = *out_grads.first()?;
 
-        let og_bc = {
-            let out = g.fresh();
-            g.push(Box::new(BroadcastLike::new(og, self.inp, out)));
-            out
-        };
-
-        let y_bc = {
+        // reduced axes come back as size-1 dims first, so that e.g. a row max
+        // of a (2, 3) input broadcasts as (2, 1) rather than against the columns.
+        let mut broadcast_to_input = |t: Id| {
+            let reshaped = g.fresh();
+            g.push(Box::new(ReshapeForBroadcast::new(
+                t,
+                reshaped,
+                self.axis.clone(),
+                self.keep_dims,
+            )));
             let out = g.fresh();
-            g.push(Box::new(BroadcastLike::new(self.out, self.inp, out)));
+            g.push(Box::new(BroadcastLike::new(reshaped, self.inp, out)));
             out
         };
+        let og_bc = broadcast_to_input(og);
+        let y_bc = broadcast_to_input(self.out);
 
         let mask = {
             let out = g.fresh();
-            g.push(Box::new(MaxGradMask::new(self.inp, y_bc, out)));
+            match self.tie_break {
+                TieBreak::Distribute => g.push(Box::new(MaxGradMask::new(self.inp, y_bc, out))),
+                TieBreak::First => g.push(Box::new(MaxFirstMask::new(
+                    self.inp,
+                    y_bc,
+                    out,
+                    self.axis.clone(),
+                ))),
+            }
             out
         };
 
-        let count_y_shape = {
+        if self.tie_break == TieBreak::First {
+            let grad_x = g.fresh();
+            g.push(Box::new(Mul::new(og_bc, mask, grad_x)));
+            return Some(vec![grad_x]);
+        }
+
+        // counted with keep_dims so it broadcasts straight back onto x.
+        let count = {
             let out = g.fresh();
-            g.push(Box::new(Sum::new(
-                mask,
-                out,
-                self.axis.clone(),
-                self.keep_dims,
-            )));
+            g.push(Box::new(Sum::new(mask, out, self.axis.clone(), true)));
             out
         };
 
         let count_bc = {
             let out = g.fresh();
-            g.push(Box::new(BroadcastLike::new(count_y_shape, self.inp, out)));
+            g.push(Box::new(BroadcastLike::new(count, self.inp, out)));
             out
         };
 
@@ -118,6 +155,26 @@ impl<D: Floating + 'static> Op<D> for Max {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let axis = normalize_axes("max", &self.axis, input_shapes[0].len());
+        vec![reduced_shape(&input_shapes[0], &axis, self.keep_dims)]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!(
+            "axis={:?} keep_dims={} tie_break={:?}",
+            self.axis, self.keep_dims, self.tie_break
+        ))
+    }
 }
 
 // Backward helper: produce a mask 1.0 where x == y, else 0.0
@@ -167,19 +224,139 @@ impl<D: Floating + 'static> Op<D> for MaxGradMask {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+// Backward helper for TieBreak::First: like `MaxGradMask`, but within each
+// slice reduced over `axis` only the first x == y in row-major order is kept.
+#[derive(Debug, Clone)]
+pub struct MaxFirstMask {
+    x: Id,
+    y: Id, // same shape as x
+    out: Id,
+    axis: Vec<isize>,
+}
+
+impl MaxFirstMask {
+    pub fn new(x: Id, y: Id, out: Id, axis: Vec<isize>) -> Self {
+        Self { x, y, out, axis }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for MaxFirstMask {
+    fn name(&self) -> &'static str {
+        "max_first_mask"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.x);
+        let y = ctx.checked_get(&self.y);
+        assert_eq!(
+            x.shape(),
+            y.shape(),
+            "max first mask: x and y must have the same shape"
+        );
+        // no axes means the max was over everything: one winner overall.
+        let axis = if self.axis.is_empty() {
+            (0..x.ndim()).collect()
+        } else {
+            normalize_axes("max_first_mask", &self.axis, x.ndim())
+        };
+
+        // elements of one reduced slice share an index once the reduced axes
+        // are zeroed; the first tie seen for that index wins.
+        let mut taken = std::collections::HashSet::new();
+        let mut mask = TensorData::zeros(x.shape());
+        for ((idx, &a), &b) in x.indexed_iter().zip(y.iter()) {
+            if a != b {
+                continue;
+            }
+            let mut slice = idx.clone();
+            for &ax in &axis {
+                slice[ax] = 0;
+            }
+            if taken.insert(slice) {
+                mask[idx] = D::one();
+            }
+        }
+
+        ctx.insert(self.out, mask);
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, _out_grads: &[Id]) -> Option<Vec<Id>> {
+        None
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.x, self.y]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("axis={:?}", self.axis))
+    }
 }
 
 impl Tracer {
-    pub fn max(&self, _axis: impl Into<Vec<usize>>, _keep_dims: bool) -> Tracer {
+    pub fn max(&self, _axis: impl Into<Vec<isize>>, _keep_dims: bool) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// `x.max_all()` is `x.max(vec![], false)`.
+    pub fn max_all(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn max_with_tie_break(
+        &self,
+        _axis: impl Into<Vec<isize>>,
+        _keep_dims: bool,
+        _tie_break: TieBreak,
+    ) -> Tracer {
         panic!("dummy operation - only allowed inside #[trace] function")
     }
 }
 
-impl<D: Floating + 'static> TraceSession<'_, D> {
-    pub fn max(&mut self, a: Tracer, axis: impl Into<Vec<usize>>, keep_dims: bool) -> Tracer {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    pub fn max(&mut self, a: Tracer, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Tracer {
         let out = self.g.fresh();
         self.emit(Max::new(a.id(), out, axis, keep_dims), out)
     }
+
+    /// largest element, as a scalar.
+    pub fn max_all(&mut self, a: Tracer) -> Tracer {
+        self.max(a, Vec::new(), false)
+    }
+
+    pub fn max_with_tie_break(
+        &mut self,
+        a: Tracer,
+        axis: impl Into<Vec<isize>>,
+        keep_dims: bool,
+        tie_break: TieBreak,
+    ) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(
+            Max::with_tie_break(a.id(), out, axis, keep_dims, tie_break),
+            out,
+        )
+    }
 }
 
 #[cfg(test)]
@@ -207,4 +384,102 @@ mod test {
             .into_dyn();
         assert_eq!(out, expected);
     }
+
+    #[test]
+    fn test_max_tie_break() {
+        use super::TieBreak;
+        use crate::prelude::*;
+        use ndarray::{arr1, arr2};
+
+        #[trace]
+        fn distribute(x: Tensor) -> Tensor {
+            x.max([0], false)
+        }
+
+        #[trace]
+        fn first(x: Tensor) -> Tensor {
+            x.max_with_tie_break([0], false, TieBreak::First)
+        }
+
+        let x = arr1(&[3.0, 3.0]).into_dyn();
+        let (g,) = trace_fn::<f32>(distribute).grad().eval()(&x);
+        assert_eq!(g, arr1(&[0.5, 0.5]).into_dyn());
+        let (g,) = trace_fn::<f32>(first).grad().eval()(&x);
+        assert_eq!(g, arr1(&[1.0, 0.0]).into_dyn());
+
+        // one winner per reduced row.
+        #[trace]
+        fn rows(x: Tensor) -> Tensor {
+            x.max_with_tie_break([1], false, TieBreak::First)
+        }
+        #[trace]
+        fn rows_distribute(x: Tensor) -> Tensor {
+            x.max([1], false)
+        }
+        let x = arr2(&[[1., 4., 4.], [2., 2., 0.]]).into_dyn();
+        let (g,) = trace_fn::<f32>(rows).grad().eval()(&x);
+        assert_eq!(g, arr2(&[[0., 1., 0.], [1., 0., 0.]]).into_dyn());
+        let (g,) = trace_fn::<f32>(rows_distribute).grad().eval()(&x);
+        assert_eq!(g, arr2(&[[0., 0.5, 0.5], [0.5, 0.5, 0.]]).into_dyn());
+    }
+
+    #[test]
+    fn test_reductions_agree_on_empty_axes() {
+        use crate::prelude::*;
+        use ndarray::{arr0, arr2};
+
+        #[trace]
+        fn max_all(x: Tensor) -> Tensor {
+            x.max(vec![], false)
+        }
+        #[trace]
+        fn max_all_keep(x: Tensor) -> Tensor {
+            x.max(vec![], true)
+        }
+        #[trace]
+        fn sum_all_keep(x: Tensor) -> Tensor {
+            x.sum(vec![], true)
+        }
+
+        let x = arr2(&[[1., 7., 2.], [-4., 0., 5.]]).into_dyn();
+        let (out,) = trace_fn::<f32>(max_all).eval()(&x);
+        assert_eq!(out, arr0(7.).into_dyn());
+        assert_eq!(
+            trace_fn::<f32>(max_all).output_shapes(&[vec![2, 3]]),
+            vec![Vec::<usize>::new()]
+        );
+
+        // with keep_dims every reduction leaves an all-ones shape.
+        let (max_keep,) = trace_fn::<f32>(max_all_keep).eval()(&x);
+        let (sum_keep,) = trace_fn::<f32>(sum_all_keep).eval()(&x);
+        assert_eq!(max_keep, arr2(&[[7.]]).into_dyn());
+        assert_eq!(max_keep.shape(), sum_keep.shape());
+    }
+
+    #[test]
+    fn test_max_empty_axes_grad_goes_to_global_argmax() {
+        use super::TieBreak;
+        use crate::prelude::*;
+        use ndarray::arr2;
+
+        #[trace]
+        fn distribute(x: Tensor) -> Tensor {
+            x.max(vec![], false)
+        }
+        #[trace]
+        fn first(x: Tensor) -> Tensor {
+            x.max_with_tie_break(vec![], false, TieBreak::First)
+        }
+
+        let x = arr2(&[[1., 7., 2.], [-4., 0., 5.]]).into_dyn();
+        let (g,) = trace_fn::<f32>(distribute).grad().eval()(&x);
+        assert_eq!(g, arr2(&[[0., 1., 0.], [0., 0., 0.]]).into_dyn());
+
+        // ties in different rows still share one global maximum.
+        let x = arr2(&[[1., 7., 2.], [7., 0., 5.]]).into_dyn();
+        let (g,) = trace_fn::<f32>(distribute).grad().eval()(&x);
+        assert_eq!(g, arr2(&[[0., 0.5, 0.], [0.5, 0., 0.]]).into_dyn());
+        let (g,) = trace_fn::<f32>(first).grad().eval()(&x);
+        assert_eq!(g, arr2(&[[0., 1., 0.], [0., 0., 0.]]).into_dyn());
+    }
 }
@@ -0,0 +1,185 @@
+use ndarray::Zip;
+
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    identity::IdGenerator,
+    ops::{
+        Const, Mul, Sub, broadcast_shapes,
+        compare::{Greater, Less},
+        sum::ReduceToLike,
+    },
+    primitive_binary_op,
+    tracing::TensorData,
+};
+
+/// elementwise `pick(x, y)`, broadcasting like the arithmetic ops.
+fn select<D: Floating>(
+    name: &str,
+    x: &TensorData<D>,
+    y: &TensorData<D>,
+    pick: impl Fn(D, D) -> D,
+) -> TensorData<D> {
+    let shape = broadcast_shapes(x.shape(), y.shape()).unwrap_or_else(|| {
+        panic!(
+            "{name}: cannot broadcast {:?} with {:?}",
+            x.shape(),
+            y.shape()
+        )
+    });
+    let (x, y) = (x.broadcast(&*shape).unwrap(), y.broadcast(&*shape).unwrap());
+    Zip::from(&x).and(&y).map_collect(|&a, &b| pick(a, b))
+}
+
+/// routes `og` to whichever operand won, given `lhs_won` (1 where lhs strictly
+/// won); ties go to rhs, so `maximum(x, 0)` has relu's zero gradient at 0.
+/// each gradient is reduced back to its operand's shape, since either side may
+/// have been broadcast.
+fn route<D: Floating + 'static>(
+    g: &mut Graph<D>,
+    og: Id,
+    lhs: Id,
+    rhs: Id,
+    lhs_won: Id,
+) -> Vec<Id> {
+    let one = g.fresh();
+    g.push(Box::new(Const::new(D::one(), one)));
+    let lhs_lost = g.fresh();
+    g.push(Box::new(Sub::new(one, lhs_won, lhs_lost)));
+
+    [(lhs, lhs_won), (rhs, lhs_lost)]
+        .into_iter()
+        .map(|(operand, mask)| {
+            let prod = g.fresh();
+            g.push(Box::new(Mul::new(og, mask, prod)));
+            let out = g.fresh();
+            g.push(Box::new(ReduceToLike::new(prod, operand, out)));
+            out
+        })
+        .collect()
+}
+
+primitive_binary_op!(
+    Maximum,
+    disp: "maximum",
+    fwd: |x: &TensorData<D>, y: &TensorData<D>| {
+        select("maximum", x, y, |a, b| if a >= b { a } else { b })
+    },
+    vjp: |this: &Maximum, g: &mut Graph<D>, og: Id| {
+        let lhs_won = g.fresh();
+        g.push(Box::new(Greater::new(this.lhs, this.rhs, lhs_won)));
+        route(g, og, this.lhs, this.rhs, lhs_won)
+    }
+);
+
+primitive_binary_op!(
+    Minimum,
+    disp: "minimum",
+    fwd: |x: &TensorData<D>, y: &TensorData<D>| {
+        select("minimum", x, y, |a, b| if a <= b { a } else { b })
+    },
+    vjp: |this: &Minimum, g: &mut Graph<D>, og: Id| {
+        let lhs_won = g.fresh();
+        g.push(Box::new(Less::new(this.lhs, this.rhs, lhs_won)));
+        route(g, og, this.lhs, this.rhs, lhs_won)
+    }
+);
+
+impl Tracer {
+    pub fn maximum(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn minimum(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// elementwise max of two tensors; on ties the gradient goes to `b`.
+    #[must_use]
+    pub fn maximum(&mut self, a: Tracer, b: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Maximum::new(a.id(), b.id(), out), out)
+    }
+
+    /// elementwise min of two tensors; on ties the gradient goes to `b`.
+    #[must_use]
+    pub fn minimum(&mut self, a: Tracer, b: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Minimum::new(a.id(), b.id(), out), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array, arr1};
+
+    use crate::prelude::*;
+
+    #[trace]
+    fn weighted_max(x: Tensor, y: Tensor, w: Tensor) -> Tensor {
+        (x.maximum(y) * w).sum(vec![], false)
+    }
+
+    #[trace]
+    fn weighted_min(x: Tensor, y: Tensor, w: Tensor) -> Tensor {
+        (x.minimum(y) * w).sum(vec![], false)
+    }
+
+    #[test]
+    fn test_maximum_minimum_broadcast_grad() {
+        let x = Array::from_shape_fn((4, 3), |(i, j)| (i * 3 + j) as f32 - 5.).into_dyn();
+        let y = arr1(&[0., -2., 3.]).into_dyn();
+        let w = Array::from_shape_fn((4, 3), |(i, j)| 1. + (i + 2 * j) as f32).into_dyn();
+
+        let (out,) = trace_fn::<f32>(weighted_max).eval()((&x, &y, &w));
+        let expected = (ndarray::Zip::from(&x)
+            .and_broadcast(&y)
+            .map_collect(|&a, &b| a.max(b))
+            * &w)
+            .sum();
+        assert_eq!(out.item(), expected);
+
+        // each operand collects the weights of the positions it won.
+        let won = |pick: fn(f32, f32) -> bool| {
+            let mut gx = Array::zeros((4, 3)).into_dyn();
+            let mut gy = Array::zeros(3).into_dyn();
+            for ((idx, &a), &wi) in x.indexed_iter().zip(w.iter()) {
+                let b = y[idx[1]];
+                if pick(a, b) {
+                    gx[&idx] += wi;
+                } else {
+                    gy[idx[1]] += wi;
+                }
+            }
+            (gx, gy)
+        };
+
+        let (gx, gy, _) = trace_fn::<f32>(weighted_max).grad().eval()((&x, &y, &w));
+        let (ex, ey) = won(|a, b| a > b);
+        assert_eq!(gy.shape(), &[3]);
+        assert_eq!((gx, gy), (ex, ey));
+
+        let (gx, gy, _) = trace_fn::<f32>(weighted_min).grad().eval()((&x, &y, &w));
+        let (ex, ey) = won(|a, b| a < b);
+        assert_eq!((gx, gy), (ex, ey));
+    }
+
+    #[test]
+    fn test_maximum_with_zero_matches_relu_at_the_tie() {
+        #[trace]
+        fn via_maximum(x: Tensor) -> Tensor {
+            x.maximum(x.zeros_like())
+        }
+
+        #[trace]
+        fn via_relu(x: Tensor) -> Tensor {
+            x.relu()
+        }
+
+        let x = arr1(&[-1., 0., 2.]).into_dyn();
+        let (gm,) = trace_fn::<f32>(via_maximum).grad().eval()(&x);
+        let (gr,) = trace_fn::<f32>(via_relu).grad().eval()(&x);
+        assert_eq!(gm, gr);
+        assert_eq!(gm, arr1(&[0., 0., 1.]).into_dyn());
+    }
+}
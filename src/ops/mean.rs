@@ -1,14 +1,15 @@
-use ndarray::Axis;
-
 use crate::{
     Floating, Graph, Id, TraceSession, Tracer,
     context::Context,
+    identity::IdGenerator,
     ops::{
         Op,
         broadcast::BroadcastLike,
         constant::Const,
         div::Div,
-        sum::{ReshapeForBroadcast, Sum},
+        normalize_axes, numel, reduced_shape,
+        sum::{ReshapeForBroadcast, Sum, reduce_attrs, sum_over, sum_over_stable},
+        unique_axes,
     },
 };
 
@@ -16,20 +17,27 @@ use crate::{
 pub struct Mean {
     inp: Id,
     out: Id,
-    axis: Vec<usize>,
+    axis: Vec<isize>,
     keep_dims: bool,
+    stable: bool,
 }
 
 impl Mean {
-    pub fn new(inp: Id, out: Id, axis: impl Into<Vec<usize>>, keep_dims: bool) -> Self {
-        let mut axis = axis.into();
-        // Reduce higher axes first to keep indexing valid as dims shrink
-        axis.sort_unstable_by(|a, b| b.cmp(a));
+    pub fn new(inp: Id, out: Id, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Self {
         Self {
             inp,
             out,
-            axis,
+            axis: unique_axes("mean", axis),
             keep_dims,
+            stable: false,
+        }
+    }
+
+    /// like [`Mean::new`], but accumulates in `f64` and rounds back once.
+    pub fn new_stable(inp: Id, out: Id, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Self {
+        Self {
+            stable: true,
+            ..Self::new(inp, out, axis, keep_dims)
         }
     }
 }
@@ -41,39 +49,24 @@ impl<D: Floating + 'static> Op<D> for Mean {
 
     fn eval(&self, ctx: &mut Context<D>) {
         let x = ctx.checked_get(&self.inp);
-        let mut t = x.clone();
-        // sum along axes
-        if self.axis.is_empty() {
-            // sum to scalar.
-            let n = D::from_f64(x.len() as f64);
-            if n == D::zero() {
-                // div by zero -> 0
-                ctx.insert(self.out, ndarray::arr0(D::zero()).into_dyn());
-                return;
-            }
-            let sum = x.sum();
-            let result = ndarray::arr0(sum / n).into_dyn();
-            ctx.insert(self.out, result);
-            return;
-        }
-
-        for ax in &self.axis {
-            let a = Axis(*ax);
-            t = if self.keep_dims {
-                t.sum_axis(a).insert_axis(a)
-            } else {
-                t.sum_axis(a)
-            };
-        }
+        let axis = normalize_axes("mean", &self.axis, x.ndim());
+        // count of reduced elements; every element when no axes are given
+        let count: usize = if axis.is_empty() {
+            x.len()
+        } else {
+            axis.iter().map(|&ax| x.shape()[ax]).product()
+        };
 
-        // divide by the count of reduced elements
-        let shape = x.shape().to_vec();
-        let mut denom = D::one();
-        for &ax in &self.axis {
-            denom = denom * D::from_f64(shape[ax] as f64);
-        }
-        // If no axes provided, denom=1 and t==x; that's fine.
-        let t = t.mapv(|v| v / denom);
+        // div by zero -> 0
+        let t = if self.stable {
+            let n = count as f64;
+            sum_over_stable(x, &axis, self.keep_dims)
+                .mapv(|v| D::from_f64(if count == 0 { 0.0 } else { v / n }))
+        } else {
+            let n = D::from_f64(count as f64);
+            sum_over(x.clone(), &axis, self.keep_dims)
+                .mapv(|v| if count == 0 { D::zero() } else { v / n })
+        };
         ctx.insert(self.out, t);
     }
 
@@ -140,19 +133,69 @@ impl<D: Floating + 'static> Op<D> for Mean {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let axis = normalize_axes("mean", &self.axis, input_shapes[0].len());
+        vec![reduced_shape(&input_shapes[0], &axis, self.keep_dims)]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(reduce_attrs(&self.axis, self.keep_dims, self.stable))
+    }
+    fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        Some(Box::new(Self {
+            inp: inputs[0],
+            ..self.clone()
+        }))
+    }
 }
 
 impl Tracer {
-    pub fn mean(&self, _axis: impl Into<Vec<usize>>, _keep_dims: bool) -> Tracer {
+    pub fn mean(&self, _axis: impl Into<Vec<isize>>, _keep_dims: bool) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn mean_stable(&self, _axis: impl Into<Vec<isize>>, _keep_dims: bool) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// `x.mean_all()` is `x.mean(vec![], false)`.
+    pub fn mean_all(&self) -> Tracer {
         panic!("dummy operation - only allowed inside #[trace] function")
     }
 }
 
-impl<D: Floating + 'static> TraceSession<'_, D> {
-    pub fn mean(&mut self, a: Tracer, axis: impl Into<Vec<usize>>, keep_dims: bool) -> Tracer {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    pub fn mean(&mut self, a: Tracer, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Tracer {
         let out = self.g.fresh();
         self.emit(Mean::new(a.id(), out, axis, keep_dims), out)
     }
+
+    /// mean of every element, as a scalar.
+    pub fn mean_all(&mut self, a: Tracer) -> Tracer {
+        self.mean(a, Vec::new(), false)
+    }
+
+    /// `mean` accumulated in `f64`, for long reductions over `f32` data.
+    pub fn mean_stable(
+        &mut self,
+        a: Tracer,
+        axis: impl Into<Vec<isize>>,
+        keep_dims: bool,
+    ) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Mean::new_stable(a.id(), out, axis, keep_dims), out)
+    }
 }
 
 #[cfg(test)]
@@ -180,4 +223,36 @@ mod test {
             .into_dyn();
         assert_eq!(out, expected);
     }
+
+    #[test]
+    fn test_mean_full_reduction_keep_dims() {
+        use crate::prelude::*;
+        use ndarray::arr2;
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.mean(vec![], true)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let x = arr2(&[[1., 3., 2.], [4., 0., 4.]]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, arr2(&[[14. / 6.]]).into_dyn());
+    }
+
+    #[test]
+    #[should_panic(expected = "mean: axis 2 is out of range for a tensor of rank 2")]
+    fn test_mean_rejects_out_of_range_axis() {
+        use crate::prelude::*;
+        use ndarray::arr2;
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.mean(vec![2], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let x = arr2(&[[1., 3., 2.], [4., 0., 4.]]).into_dyn();
+        let _: (ndarray::ArrayD<f32>,) = traced.eval()(&x);
+    }
 }
@@ -1,25 +1,59 @@
 pub mod add;
+pub mod assert_scalar;
+pub mod assert_shape;
+pub mod batch_norm;
 pub mod broadcast;
+#[cfg(feature = "linalg")]
+pub mod cholesky;
+pub mod compare;
 pub mod constant;
+pub mod cross_entropy;
+#[cfg(feature = "linalg")]
+pub mod det;
 pub mod div;
+pub mod embedding;
+#[cfg(feature = "libm")]
+pub mod erf;
 pub mod exp;
 pub mod input;
+#[cfg(feature = "linalg")]
+pub mod inverse;
+pub mod layer_norm;
+#[cfg(feature = "linalg")]
+pub(crate) mod linalg;
 pub mod log;
+pub mod masked_mean;
 pub mod matmul;
 pub mod max;
+pub mod maximum;
 pub mod mean;
 pub mod mul;
+pub mod nan_to_num;
 pub mod neg;
+pub mod norm;
+pub mod powf;
+pub mod reduce;
 pub mod relu;
+pub mod remat;
+pub mod repeat_interleave;
 pub mod reshape;
+pub mod round;
+pub mod rsqrt;
+pub mod scalar_minmax;
+pub mod slice;
+#[cfg(feature = "linalg")]
+pub mod solve;
+pub mod split;
+pub mod straight_through;
 pub mod sub;
 pub mod sum;
 pub mod transpose;
+pub mod where_positive;
 
 use core::fmt::Debug;
 
 pub use add::Add;
-pub use constant::Const;
+pub use constant::{Const, ConstTensor, ZerosLike};
 pub use input::Input;
 pub use matmul::MatMul;
 pub use mul::Mul;
@@ -29,7 +63,7 @@ pub use sub::Sub;
 pub use sum::Sum;
 pub use transpose::{Transpose, TransposeDefault};
 
-use crate::{context::Context, graph::Graph, identity::Id};
+use crate::{context::Context, graph::Graph, identity::Id, tracing::TensorData};
 
 pub trait OpClone<D> {
     fn boxed_clone(&self) -> Box<dyn Op<D>>;
@@ -60,6 +94,60 @@ pub trait Op<D>: Debug + OpClone<D> {
     fn inputs(&self) -> Vec<Id>;
     /// returns the output(s) to the operation.
     fn outputs(&self) -> Vec<Id>;
+
+    /// output shape(s) given the shapes of `inputs()`, without touching any data.
+    /// ops that do not override it cannot take part in shape inference
+    /// (`output_shapes`, `flops`, `peak_memory`).
+    fn shape_rule(&self, _input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        panic!("{}: shape_rule is not implemented for this op", self.name())
+    }
+
+    /// rough floating point operation count, given input and output shapes.
+    /// defaults to one operation per output element.
+    fn flops(&self, _input_shapes: &[Vec<usize>], output_shapes: &[Vec<usize>]) -> u64 {
+        output_shapes.iter().map(|s| numel(s)).sum()
+    }
+
+    /// rebuilds the operation for an `f64` graph, converting any stored scalars.
+    /// ops that do not override it cannot be part of a graph passed to
+    /// `to_f64`.
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        panic!("{}: retype_f64 is not implemented for this op", self.name())
+    }
+
+    /// whether the op is linear in its inputs, so its vjp reads input tensors at
+    /// most for their shapes and never their values. lets rematerialization and
+    /// memory planning drop forward values that only linear ops consume.
+    fn is_linear(&self) -> bool {
+        false
+    }
+
+    /// whether the node is a graph input, whose value the caller supplies.
+    /// graph passes use this rather than the op's name.
+    fn is_input(&self) -> bool {
+        false
+    }
+
+    /// the value of a node that holds a fixed constant, independent of any
+    /// input, for passes that fold, merge or lift constants.
+    fn const_value(&self) -> Option<TensorData<D>> {
+        None
+    }
+
+    /// static attributes worth printing next to the op, e.g. a constant's value.
+    ///
+    /// ops with parameters must report every one of them here: it is all
+    /// [`TraceableFn::structural_hash`](crate::TraceableFn::structural_hash)
+    /// sees of a node besides its name and wiring.
+    fn attrs(&self) -> Option<String> {
+        None
+    }
+
+    /// a copy of the op reading `inputs` (in `inputs()` order) instead, for
+    /// graph passes that merge tensors. `None` if the op cannot be rewired.
+    fn with_inputs(&self, _inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        None
+    }
 }
 
 impl<D> Clone for Box<dyn Op<D>> {
@@ -89,14 +177,66 @@ pub(crate) fn broadcast_shapes(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
     Some(result)
 }
 
+pub(crate) fn numel(shape: &[usize]) -> u64 {
+    shape.iter().product::<usize>() as u64
+}
+
+/// shape left after reducing `axis`; an empty `axis` reduces every dimension.
+pub(crate) fn reduced_shape(shape: &[usize], axis: &[usize], keep_dims: bool) -> Vec<usize> {
+    shape
+        .iter()
+        .enumerate()
+        .filter_map(
+            |(i, &dim)| match (axis.is_empty() || axis.contains(&i), keep_dims) {
+                (false, _) => Some(dim),
+                (true, true) => Some(1),
+                (true, false) => None,
+            },
+        )
+        .collect()
+}
+
+/// panics if a reduction axis is listed more than once.
+/// catches literal duplicates at trace time, before the rank is known.
+pub(crate) fn unique_axes(op: &str, axis: impl Into<Vec<isize>>) -> Vec<isize> {
+    let axis = axis.into();
+    for (i, ax) in axis.iter().enumerate() {
+        if axis[..i].contains(ax) {
+            panic!("{op}: duplicate axis {ax} in {axis:?}");
+        }
+    }
+    axis
+}
+
+/// resolves numpy-style negative axes against `rank` and sorts them highest-first,
+/// so indexing stays valid as dims shrink. panics on out-of-range or duplicate axes.
+pub(crate) fn normalize_axes(op: &str, axis: &[isize], rank: usize) -> Vec<usize> {
+    let mut resolved: Vec<usize> = axis
+        .iter()
+        .map(|&ax| {
+            let r = if ax < 0 { ax + rank as isize } else { ax };
+            if r < 0 || r >= rank as isize {
+                panic!("{op}: axis {ax} is out of range for a tensor of rank {rank}");
+            }
+            r as usize
+        })
+        .collect();
+    resolved.sort_unstable_by(|a, b| b.cmp(a));
+    if let Some(w) = resolved.windows(2).find(|w| w[0] == w[1]) {
+        panic!("{op}: duplicate axis {} in {axis:?}", w[0]);
+    }
+    resolved
+}
+
 pub mod macros {
     #[macro_export]
     /// binary operation implementer
     ///
+    /// linear: true; (optional, see `Op::is_linear`)
     /// fwd: |x: Tensor, y: Tensor| -> Tensor;
     /// vjp: |self, g: Graph, og: &[Id]| -> Option<Vec<ID>>;
     macro_rules! primitive_binary_op {
-        ($name:ident, disp: $strname:expr, fwd: $forward:expr, vjp: $vjp_rule:expr) => {
+        ($name:ident, disp: $strname:expr, $(linear: $linear:literal,)? fwd: $forward:expr, vjp: $vjp_rule:expr) => {
             #[derive(Debug, Clone)]
             #[non_exhaustive]
             pub struct $name {
@@ -142,6 +282,29 @@ pub mod macros {
                 fn outputs(&self) -> Vec<$crate::identity::Id> {
                     vec![self.out]
                 }
+
+                fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+                    let (lhs, rhs) = (&input_shapes[0], &input_shapes[1]);
+                    let out = $crate::ops::broadcast_shapes(lhs, rhs).unwrap_or_else(|| {
+                        panic!("{}: cannot broadcast {lhs:?} with {rhs:?}", $strname)
+                    });
+                    vec![out]
+                }
+
+                fn retype_f64(&self) -> Box<dyn $crate::ops::Op<f64>> {
+                    Box::new(self.clone())
+                }
+
+                fn is_linear(&self) -> bool {
+                    false $(|| $linear)?
+                }
+
+                fn with_inputs(
+                    &self,
+                    inputs: &[$crate::identity::Id],
+                ) -> Option<Box<dyn $crate::ops::Op<D>>> {
+                    Some(Box::new(Self::new(inputs[0], inputs[1], self.out)))
+                }
             }
         };
     }
@@ -184,6 +347,15 @@ pub mod macros {
                 fn outputs(&self) -> Vec<Id> {
                     vec![self.out]
                 }
+                fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+                    vec![input_shapes[0].clone()]
+                }
+                fn retype_f64(&self) -> Box<dyn $crate::ops::Op<f64>> {
+                    Box::new(self.clone())
+                }
+                fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn $crate::ops::Op<D>>> {
+                    Some(Box::new(Self::new(inputs[0], self.out)))
+                }
             }
         };
     }
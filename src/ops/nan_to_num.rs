@@ -0,0 +1,180 @@
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Mul, Op},
+};
+
+/// replaces `nan`, `+inf` and `-inf` with fixed finite values.
+///
+/// the replaced positions get a zero gradient; everywhere else `og` passes
+/// straight through, so a rare `nan` does not poison the whole backward pass.
+#[derive(Debug, Clone)]
+pub struct NanToNum<D: Floating> {
+    inp: Id,
+    out: Id,
+    nan: D,
+    posinf: D,
+    neginf: D,
+}
+
+impl<D: Floating> NanToNum<D> {
+    pub fn new(inp: Id, out: Id, nan: D, posinf: D, neginf: D) -> Self {
+        Self {
+            inp,
+            out,
+            nan,
+            posinf,
+            neginf,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for NanToNum<D> {
+    fn name(&self) -> &'static str {
+        "nan_to_num"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let out = x.mapv(|a| {
+            if a.is_nan() {
+                self.nan
+            } else if a.is_infinite() {
+                if a > D::zero() {
+                    self.posinf
+                } else {
+                    self.neginf
+                }
+            } else {
+                a
+            }
+        });
+        ctx.insert(self.out, out);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // grad = og * 1[x is finite]
+        let og = *out_grads.first()?;
+        let mask = g.fresh();
+        g.push(Box::new(FiniteMask::new(self.inp, mask)));
+        let prod = g.fresh();
+        g.push(Box::new(Mul::new(og, mask, prod)));
+        Some(vec![prod])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(NanToNum::new(
+            self.inp,
+            self.out,
+            self.nan.as_f64(),
+            self.posinf.as_f64(),
+            self.neginf.as_f64(),
+        ))
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!(
+            "nan={:?}, posinf={:?}, neginf={:?}",
+            self.nan, self.posinf, self.neginf
+        ))
+    }
+}
+
+/// 1 where the input is finite, 0 where it is `nan` or infinite.
+#[derive(Debug, Clone)]
+pub struct FiniteMask {
+    inp: Id,
+    out: Id,
+}
+
+impl FiniteMask {
+    pub fn new(inp: Id, out: Id) -> Self {
+        Self { inp, out }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for FiniteMask {
+    fn name(&self) -> &'static str {
+        "finite_mask"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let mask = x.mapv(|a| if a.is_finite() { D::one() } else { D::zero() });
+        ctx.insert(self.out, mask);
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, _out_grads: &[Id]) -> Option<Vec<Id>> {
+        // the indicator is piecewise constant, so no backward pass
+        None
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+impl Tracer {
+    pub fn nan_to_num(&self, _nan: f64, _posinf: f64, _neginf: f64) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// `a` with `nan` replaced by `nan`, `+inf` by `posinf` and `-inf` by
+    /// `neginf`; replaced positions get no gradient.
+    #[must_use]
+    pub fn nan_to_num(&mut self, a: Tracer, nan: D, posinf: D, neginf: D) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(NanToNum::new(a.id(), out, nan, posinf, neginf), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr1;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_nan_to_num_sanitizes_and_zeroes_grad() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            (x.nan_to_num(0.0, 1e6, -1e6) * x.nan_to_num(0.0, 1e6, -1e6)).sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let x = arr1(&[1., f32::NAN, f32::INFINITY, -2., f32::NEG_INFINITY]).into_dyn();
+
+        let (out,) = traced.eval()(&x);
+        assert!(out.iter().all(|v| v.is_finite()));
+
+        let (gx,) = traced.grad().eval()(&x);
+        assert_eq!(gx, arr1(&[2., 0., 0., -4., 0.]).into_dyn());
+    }
+}
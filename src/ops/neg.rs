@@ -1,4 +1,4 @@
-use crate::{Floating, context::Context, graph::Graph, identity::Id, ops::Op};
+use crate::{Floating, Tracer, context::Context, graph::Graph, identity::Id, ops::Op};
 
 #[derive(Debug, Clone)]
 pub struct Neg {
@@ -39,6 +39,31 @@ impl<D: Floating> Op<D> for Neg {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+    fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        Some(Box::new(Self {
+            inp: inputs[0],
+            ..self.clone()
+        }))
+    }
+}
+
+impl Tracer {
+    /// method form of `-x`; both lower to `sess.neg(x)`.
+    pub fn neg(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
 }
 
 #[cfg(test)]
@@ -61,4 +86,28 @@ mod tests {
         let expected = -&x;
         assert_eq!(out, expected);
     }
+
+    #[test]
+    fn test_neg_method() {
+        #[trace]
+        fn method(x: Tensor) -> Tensor {
+            x.neg() * 2.0
+        }
+        #[trace]
+        fn operator(x: Tensor) -> Tensor {
+            -x * 2.0
+        }
+
+        let method = trace_fn::<f32>(method);
+        assert_eq!(
+            method.graph.to_ssa(),
+            trace_fn::<f32>(operator).graph.to_ssa()
+        );
+
+        let x = arr1(&[2., -3., 4.]).into_dyn();
+        let (out,) = method.eval()(&x);
+        assert_eq!(out, arr1(&[-4., 6., -8.]).into_dyn());
+        let (grad_x,) = method.grad().eval()(&x);
+        assert_eq!(grad_x, arr1(&[-2., -2., -2.]).into_dyn());
+    }
 }
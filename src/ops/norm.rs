@@ -0,0 +1,332 @@
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{
+        Const, Op,
+        broadcast::BroadcastLike,
+        mul::Mul,
+        normalize_axes, numel, reduced_shape,
+        sum::{ReshapeForBroadcast, reduce_attrs, sum_over},
+        unique_axes,
+    },
+};
+
+/// `sum(abs(x), axis)`, differentiated with the `sign(x)` subgradient.
+#[derive(Debug, Clone)]
+pub struct L1Norm {
+    inp: Id,
+    out: Id,
+    axis: Vec<isize>,
+    keep_dims: bool,
+}
+
+impl L1Norm {
+    pub fn new(inp: Id, out: Id, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Self {
+        Self {
+            inp,
+            out,
+            axis: unique_axes("l1_norm", axis),
+            keep_dims,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for L1Norm {
+    fn name(&self) -> &'static str {
+        "l1_norm"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let axis = normalize_axes("l1_norm", &self.axis, x.ndim());
+        let result = sum_over(x.mapv(|a| a.abs()), &axis, self.keep_dims);
+        ctx.insert(self.out, result);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d/dx sum(|x|, axis) = broadcast_like(og, like=x) * sign(x)
+        let og = *out_grads.first()?;
+
+        let og_bc = {
+            let reshaped = g.fresh();
+            g.push(Box::new(ReshapeForBroadcast::new(
+                og,
+                reshaped,
+                self.axis.clone(),
+                self.keep_dims,
+            )));
+            let out = g.fresh();
+            g.push(Box::new(BroadcastLike::new(reshaped, self.inp, out)));
+            out
+        };
+
+        let sign = {
+            let out = g.fresh();
+            g.push(Box::new(Sign::new(self.inp, out)));
+            out
+        };
+
+        let grad_x = g.fresh();
+        g.push(Box::new(Mul::new(og_bc, sign, grad_x)));
+        Some(vec![grad_x])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let axis = normalize_axes("l1_norm", &self.axis, input_shapes[0].len());
+        vec![reduced_shape(&input_shapes[0], &axis, self.keep_dims)]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        // one abs and one add per element
+        2 * numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(reduce_attrs(&self.axis, self.keep_dims, false))
+    }
+}
+
+/// `sum(x * x, axis)` as a single node, with a `2 * x * og` gradient.
+#[derive(Debug, Clone)]
+pub struct SumSquares {
+    inp: Id,
+    out: Id,
+    axis: Vec<isize>,
+    keep_dims: bool,
+}
+
+impl SumSquares {
+    pub fn new(inp: Id, out: Id, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Self {
+        Self {
+            inp,
+            out,
+            axis: unique_axes("sum_squares", axis),
+            keep_dims,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for SumSquares {
+    fn name(&self) -> &'static str {
+        "sum_squares"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let axis = normalize_axes("sum_squares", &self.axis, x.ndim());
+
+        let result = sum_over(x.mapv(|a| a * a), &axis, self.keep_dims);
+        ctx.insert(self.out, result);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d/dx sum(x * x, axis) = broadcast_like(og, like=x) * 2 * x
+        let og = *out_grads.first()?;
+
+        let og_bc = {
+            let reshaped = g.fresh();
+            g.push(Box::new(ReshapeForBroadcast::new(
+                og,
+                reshaped,
+                self.axis.clone(),
+                self.keep_dims,
+            )));
+            let out = g.fresh();
+            g.push(Box::new(BroadcastLike::new(reshaped, self.inp, out)));
+            out
+        };
+
+        let og_x = g.fresh();
+        g.push(Box::new(Mul::new(og_bc, self.inp, og_x)));
+
+        let two = g.fresh();
+        g.push(Const::boxed(D::from_f64(2.0), two));
+
+        let grad_x = g.fresh();
+        g.push(Box::new(Mul::new(two, og_x, grad_x)));
+        Some(vec![grad_x])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let axis = normalize_axes("sum_squares", &self.axis, input_shapes[0].len());
+        vec![reduced_shape(&input_shapes[0], &axis, self.keep_dims)]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        // one multiply and one add per element
+        2 * numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(reduce_attrs(&self.axis, self.keep_dims, false))
+    }
+}
+
+// Backward helper: -1, 0 or 1 following the sign of x, with sign(0) = 0
+#[derive(Debug, Clone)]
+pub struct Sign {
+    inp: Id,
+    out: Id,
+}
+
+impl Sign {
+    pub fn new(inp: Id, out: Id) -> Self {
+        Self { inp, out }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for Sign {
+    fn name(&self) -> &'static str {
+        "sign"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let sign = x.mapv(|a| {
+            if a > D::zero() {
+                D::one()
+            } else if a < D::zero() {
+                -D::one()
+            } else {
+                D::zero()
+            }
+        });
+        ctx.insert(self.out, sign);
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, _out_grads: &[Id]) -> Option<Vec<Id>> {
+        // sign is piecewise constant, so no backward pass
+        None
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+impl Tracer {
+    pub fn l1_norm(&self, _axis: impl Into<Vec<isize>>, _keep_dims: bool) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn sum_squares(&self, _axis: impl Into<Vec<isize>>, _keep_dims: bool) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    pub fn l1_norm(&mut self, a: Tracer, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(L1Norm::new(a.id(), out, axis, keep_dims), out)
+    }
+
+    pub fn sum_squares(
+        &mut self,
+        a: Tracer,
+        axis: impl Into<Vec<isize>>,
+        keep_dims: bool,
+    ) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(SumSquares::new(a.id(), out, axis, keep_dims), out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[test]
+    fn test_l1_norm_forward_and_grad() {
+        use crate::prelude::*;
+        use ndarray::arr2;
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.l1_norm(vec![1], true)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let x = arr2(&[[1., -3., 0.], [-4., 2., 0.5]]).into_dyn();
+
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, arr2(&[[4.], [6.5]]).into_dyn());
+
+        let (grad_x,) = traced.grad().eval()(&x);
+        assert_eq!(grad_x, arr2(&[[1., -1., 0.], [-1., 1., 1.]]).into_dyn());
+    }
+
+    #[test]
+    fn test_sum_squares_matches_mul_sum() {
+        use crate::prelude::*;
+        use ndarray::Array;
+
+        #[trace]
+        fn fused_rows(x: Tensor) -> Tensor {
+            x.sum_squares([1], false)
+        }
+        #[trace]
+        fn unfused_rows(x: Tensor) -> Tensor {
+            (x * x).sum([1], false)
+        }
+        #[trace]
+        fn fused_all(x: Tensor) -> Tensor {
+            x.sum_squares([0, 2], true)
+        }
+        #[trace]
+        fn unfused_all(x: Tensor) -> Tensor {
+            (x * x).sum([0, 2], true)
+        }
+
+        let x = Array::from_shape_fn((2, 3, 4), |(i, j, k)| {
+            (i as f32 - 0.5) * (j as f32 + 1.) - 0.25 * k as f32
+        })
+        .into_dyn();
+        let pairs = [
+            (trace_fn::<f32>(fused_rows), trace_fn::<f32>(unfused_rows)),
+            (trace_fn::<f32>(fused_all), trace_fn::<f32>(unfused_all)),
+        ];
+        for (fused, unfused) in pairs {
+            let (a,) = fused.eval()(&x);
+            let (b,) = unfused.eval()(&x);
+            assert_eq!(a.shape(), b.shape());
+            assert!(a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-5));
+
+            let (ga,) = fused.grad().eval()(&x);
+            assert_eq!(ga, x.mapv(|v| 2. * v));
+        }
+    }
+}
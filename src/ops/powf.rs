@@ -0,0 +1,127 @@
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Const, Mul, Op},
+};
+
+/// `x^exp` elementwise for a fixed scalar exponent.
+///
+/// follows `f64::powf`: a negative base with a fractional exponent gives
+/// `nan`, in both the forward value and the gradient.
+#[derive(Debug, Clone)]
+pub struct PowfScalar<D: Floating> {
+    pub inp: Id,
+    pub out: Id,
+    pub exp: D,
+}
+
+impl<D: Floating> PowfScalar<D> {
+    pub fn new(inp: Id, out: Id, exp: D) -> Self {
+        Self { inp, out, exp }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for PowfScalar<D> {
+    fn name(&self) -> &'static str {
+        "powf"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let exp = self.exp;
+        ctx.insert(self.out, x.mapv(|a| a.powf(exp)));
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d/dx x^p = p * x^(p-1)
+        let og = *out_grads.first()?;
+
+        let pow = {
+            let out = g.fresh();
+            g.push(Box::new(PowfScalar::new(
+                self.inp,
+                out,
+                self.exp - D::one(),
+            )));
+            out
+        };
+
+        let scaled = {
+            let exp = g.fresh();
+            g.push(Const::boxed(self.exp, exp));
+            let out = g.fresh();
+            g.push(Box::new(Mul::new(pow, exp, out)));
+            out
+        };
+
+        let grad_x = g.fresh();
+        g.push(Box::new(Mul::new(og, scaled, grad_x)));
+        Some(vec![grad_x])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(PowfScalar::new(self.inp, self.out, self.exp.as_f64()))
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("{:?}", self.exp))
+    }
+}
+
+impl Tracer {
+    pub fn powf(&self, _exp: f64) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    #[must_use]
+    pub fn powf(&mut self, a: Tracer, exp: D) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(PowfScalar::new(a.id(), out, exp), out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{prelude::*, tests::assert_grad_matches_fd};
+    use ndarray::arr1;
+
+    #[trace]
+    fn f(x: Tensor) -> Tensor {
+        x.powf(1.5).sum(vec![], false)
+    }
+
+    #[test]
+    fn test_powf_grad_check() {
+        let traced = trace_fn::<f64>(f);
+        let x = arr1(&[0.5, 1.0, 2.0, 4.0]).into_dyn();
+
+        let (out,) = traced.eval()(&x);
+        let expected: f64 = x.iter().map(|a| a.powf(1.5)).sum();
+        assert!((out.item() - expected).abs() < 1e-12);
+
+        assert_grad_matches_fd(&traced, &[x], 1e-6, 1e-6);
+    }
+
+    #[test]
+    fn test_powf_negative_base_is_nan() {
+        let traced = trace_fn::<f64>(f);
+        let x = arr1(&[-1.0]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert!(out.item().is_nan());
+    }
+}
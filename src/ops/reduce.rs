@@ -0,0 +1,86 @@
+use ndarray::Axis;
+
+use crate::tracing::TensorData;
+
+/// Folds `t` over `axis` (sorted descending, as from `normalize_axes`),
+/// starting every slice from `init`.
+///
+/// The shared reduction of `sum`, `mean` and `max`: an empty axis list
+/// reduces over everything, to a scalar or to an all-ones shape with
+/// `keep_dims`, so every reduction agrees on what `axis = []` means.
+pub(crate) fn reduce_axes<T, F>(
+    t: TensorData<T>,
+    axis: &[usize],
+    keep_dims: bool,
+    init: T,
+    fold: F,
+) -> TensorData<T>
+where
+    T: Clone,
+    F: Fn(&T, &T) -> T,
+{
+    reduce_axes_with(
+        t,
+        axis,
+        keep_dims,
+        |t| t.iter().fold(init.clone(), |acc, x| fold(&acc, x)),
+        |t, a| t.fold_axis(a, init.clone(), |acc, x| fold(acc, x)),
+    )
+}
+
+/// [`reduce_axes`] with the whole-tensor and single-axis kernels supplied
+/// directly, for reductions ndarray already has fast paths for (`sum`).
+pub(crate) fn reduce_axes_with<T, A, S>(
+    t: TensorData<T>,
+    axis: &[usize],
+    keep_dims: bool,
+    all: A,
+    along: S,
+) -> TensorData<T>
+where
+    T: Clone,
+    A: Fn(&TensorData<T>) -> T,
+    S: Fn(&TensorData<T>, Axis) -> TensorData<T>,
+{
+    if axis.is_empty() {
+        let shape = if keep_dims { vec![1; t.ndim()] } else { vec![] };
+        return TensorData::from_elem(shape, all(&t));
+    }
+    let mut t = t;
+    for &ax in axis {
+        let a = Axis(ax);
+        let reduced = along(&t, a);
+        t = if keep_dims {
+            reduced.insert_axis(a)
+        } else {
+            reduced
+        };
+    }
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr0, arr1, arr2};
+
+    use super::reduce_axes;
+
+    #[test]
+    fn test_reduce_axes_empty_reduces_everything() {
+        let x = arr2(&[[1., 5.], [-2., 3.]]).into_dyn();
+        let max = |a: &f32, b: &f32| a.max(*b);
+
+        assert_eq!(
+            reduce_axes(x.clone(), &[], false, f32::NEG_INFINITY, max),
+            arr0(5.).into_dyn()
+        );
+        assert_eq!(
+            reduce_axes(x.clone(), &[], true, f32::NEG_INFINITY, max),
+            arr2(&[[5.]]).into_dyn()
+        );
+        assert_eq!(
+            reduce_axes(x, &[1], false, f32::NEG_INFINITY, max),
+            arr1(&[5., 3.]).into_dyn()
+        );
+    }
+}
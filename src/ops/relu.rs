@@ -1,33 +1,97 @@
 use crate::{
     Floating, Graph, Id, TraceSession, Tracer,
     context::Context,
+    identity::IdGenerator,
     ops::{Mul, Op},
-    simple_unary_op,
-    tracing::TensorData,
 };
 
-simple_unary_op!(
-    ReLU,
-    disp: "relu",
-    fwd: |x: &TensorData<D>| x.mapv(|a| if a > D::zero() { a } else { D::zero() }),
-    vjp: |this: &ReLU, g: &mut Graph<D>, og: Id| {
-        // grad = og * 1[x>0]
+/// Which subgradient `relu` reports at exactly `x == 0`.
+///
+/// Frameworks disagree here; pick the one you need to match numerically.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReluBoundary {
+    #[default]
+    Zero,
+    Half,
+    One,
+}
+
+impl ReluBoundary {
+    fn value<D: Floating>(self) -> D {
+        match self {
+            ReluBoundary::Zero => D::zero(),
+            ReluBoundary::Half => D::from_f64(0.5),
+            ReluBoundary::One => D::one(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReLU {
+    pub inp: Id,
+    pub out: Id,
+    pub boundary: ReluBoundary,
+}
+
+impl ReLU {
+    pub fn new(inp: Id, out: Id) -> Self {
+        Self::with_boundary(inp, out, ReluBoundary::default())
+    }
+
+    pub fn with_boundary(inp: Id, out: Id, boundary: ReluBoundary) -> Self {
+        Self { inp, out, boundary }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for ReLU {
+    fn name(&self) -> &str {
+        "relu"
+    }
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let y = x.mapv(|a| if a > D::zero() { a } else { D::zero() });
+        ctx.insert(self.out, y);
+    }
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // grad = og * 1[x>0], with the boundary value at x == 0
+        let og = *out_grads.first()?;
         let mask_out = g.fresh();
-        g.push(Box::new(ReLUGradMask::new(this.inp, mask_out)));
+        g.push(Box::new(ReLUGradMask::new(
+            self.inp,
+            mask_out,
+            self.boundary,
+        )));
         let prod = g.fresh();
         g.push(Box::new(Mul::new(og, mask_out, prod)));
-        prod
+        Some(vec![prod])
+    }
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
     }
-);
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("boundary={:?}", self.boundary))
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct ReLUGradMask {
     inp: Id,
     out: Id,
+    boundary: ReluBoundary,
 }
 impl ReLUGradMask {
-    pub fn new(inp: Id, out: Id) -> Self {
-        Self { inp, out }
+    pub fn new(inp: Id, out: Id, boundary: ReluBoundary) -> Self {
+        Self { inp, out, boundary }
     }
 }
 impl<D: Floating + 'static> Op<D> for ReLUGradMask {
@@ -36,7 +100,16 @@ impl<D: Floating + 'static> Op<D> for ReLUGradMask {
     }
     fn eval(&self, ctx: &mut Context<D>) {
         let x = ctx.checked_get(&self.inp);
-        let mask = x.mapv(|a| if a > D::zero() { D::one() } else { D::zero() });
+        let at_zero = self.boundary.value::<D>();
+        let mask = x.mapv(|a| {
+            if a > D::zero() {
+                D::one()
+            } else if a == D::zero() {
+                at_zero
+            } else {
+                D::zero()
+            }
+        });
         ctx.insert(self.out, mask);
     }
     fn vjp(&self, _g: &mut Graph<D>, _og: &[Id]) -> Option<Vec<Id>> {
@@ -49,17 +122,72 @@ impl<D: Floating + 'static> Op<D> for ReLUGradMask {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("boundary={:?}", self.boundary))
+    }
 }
 
 impl Tracer {
     pub fn relu(&self) -> Tracer {
         panic!("dummy operation - only allowed inside #[trace] function")
     }
+    pub fn relu_with_boundary(&self, _boundary: ReluBoundary) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
 }
 
-impl<D: Floating + 'static> TraceSession<'_, D> {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
     pub fn relu(&mut self, a: Tracer) -> Tracer {
         let out = self.g.fresh();
         self.emit(ReLU::new(a.id(), out), out)
     }
+
+    pub fn relu_with_boundary(&mut self, a: Tracer, boundary: ReluBoundary) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(ReLU::with_boundary(a.id(), out, boundary), out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReluBoundary;
+
+    #[test]
+    fn test_relu_boundary_subgradient() {
+        use crate::prelude::*;
+        use ndarray::arr1;
+
+        #[trace]
+        fn zero(x: Tensor) -> Tensor {
+            x.relu()
+        }
+
+        #[trace]
+        fn half(x: Tensor) -> Tensor {
+            x.relu_with_boundary(ReluBoundary::Half)
+        }
+
+        #[trace]
+        fn one(x: Tensor) -> Tensor {
+            x.relu_with_boundary(ReluBoundary::One)
+        }
+
+        let x = arr1(&[-1., 0., 2.]).into_dyn();
+        let cases = [
+            (trace_fn::<f32>(zero), 0.),
+            (trace_fn::<f32>(half), 0.5),
+            (trace_fn::<f32>(one), 1.),
+        ];
+        for (traced, at_zero) in cases {
+            let (grad_x,) = traced.grad().eval()(&x);
+            assert_eq!(grad_x, arr1(&[0., at_zero, 1.]).into_dyn());
+        }
+    }
 }
@@ -0,0 +1,123 @@
+use std::rc::Rc;
+
+use crate::{Floating, Graph, Id, context::Context, ops::Op};
+
+/// Runs `op` reading `inputs` and writing `outputs` in place of the ids it
+/// was built with, pairwise in `inputs()`/`outputs()` order.
+///
+/// The backward pass uses it for checkpointed regions: forward nodes are
+/// replayed onto fresh ids, and vjp nodes are pointed at those copies, so the
+/// original intermediates can be freed once the forward pass is done with them.
+#[derive(Debug, Clone)]
+pub struct Remat<D> {
+    op: Rc<dyn Op<D>>,
+    inputs: Vec<Id>,
+    outputs: Vec<Id>,
+}
+
+impl<D: Floating + 'static> Remat<D> {
+    pub fn new(op: Rc<dyn Op<D>>, inputs: Vec<Id>, outputs: Vec<Id>) -> Self {
+        assert_eq!(
+            op.inputs().len(),
+            inputs.len(),
+            "remat: {} reads {} inputs, got {}",
+            op.name(),
+            op.inputs().len(),
+            inputs.len()
+        );
+        assert_eq!(
+            op.outputs().len(),
+            outputs.len(),
+            "remat: {} writes {} outputs, got {}",
+            op.name(),
+            op.outputs().len(),
+            outputs.len()
+        );
+        Self {
+            op,
+            inputs,
+            outputs,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for Remat<D> {
+    fn name(&self) -> &str {
+        "remat"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        // move the inputs under the ids `op` expects and back again, so
+        // nothing is copied.
+        let mut pairs: Vec<(Id, Id)> = self
+            .op
+            .inputs()
+            .into_iter()
+            .zip(self.inputs.clone())
+            .collect();
+        pairs.sort_unstable_by_key(|(own, _)| own.as_usize());
+        pairs.dedup();
+
+        let mut scratch = Context::new();
+        for (own, id) in &pairs {
+            let t = ctx
+                .take(id)
+                .unwrap_or_else(|| panic!("tensor({id:?}) was not found in context."));
+            scratch.insert(*own, t);
+        }
+        self.op.eval(&mut scratch);
+        for (own, id) in &pairs {
+            ctx.insert(
+                *id,
+                scratch.take(own).expect("remat: inputs are kept by the op"),
+            );
+        }
+        for (own, id) in self.op.outputs().iter().zip(&self.outputs) {
+            let t = scratch
+                .take(own)
+                .unwrap_or_else(|| panic!("remat: {} did not write {own:?}", self.op.name()));
+            ctx.insert(*id, t);
+        }
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // gradients are positional, and the values `op`'s vjp reads are the
+        // same under either set of ids.
+        self.op.vjp(g, out_grads)
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        self.inputs.clone()
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        self.outputs.clone()
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        self.op.shape_rule(input_shapes)
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], output_shapes: &[Vec<usize>]) -> u64 {
+        self.op.flops(input_shapes, output_shapes)
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(Remat::new(
+            Rc::from(self.op.retype_f64()),
+            self.inputs.clone(),
+            self.outputs.clone(),
+        ))
+    }
+
+    fn is_linear(&self) -> bool {
+        self.op.is_linear()
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(match self.op.attrs() {
+            Some(attrs) => format!("{} {attrs}", self.op.name()),
+            None => self.op.name().to_string(),
+        })
+    }
+}
@@ -0,0 +1,241 @@
+use ndarray::Axis;
+
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Op, normalize_axes, numel},
+};
+
+fn resolve_axis(op: &str, axis: isize, rank: usize) -> usize {
+    normalize_axes(op, &[axis], rank)[0]
+}
+
+/// Repeats every slice along `axis` `repeats` times in place, like NumPy's
+/// `repeat`: `[a, b]` becomes `[a, a, b, b]` rather than `tile`'s `[a, b, a, b]`.
+#[derive(Debug, Clone)]
+pub struct RepeatInterleave {
+    inp: Id,
+    out: Id,
+    repeats: usize,
+    axis: isize,
+}
+
+impl RepeatInterleave {
+    pub fn new(inp: Id, out: Id, repeats: usize, axis: isize) -> Self {
+        assert!(repeats > 0, "repeat_interleave: repeats should be positive");
+        Self {
+            inp,
+            out,
+            repeats,
+            axis,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for RepeatInterleave {
+    fn name(&self) -> &'static str {
+        "repeat_interleave"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let axis = resolve_axis("repeat_interleave", self.axis, x.ndim());
+        let source: Vec<usize> = (0..x.shape()[axis] * self.repeats)
+            .map(|i| i / self.repeats)
+            .collect();
+        ctx.insert(self.out, x.select(Axis(axis), &source));
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // each source slice was copied `repeats` times, so it collects the sum
+        // of their gradients.
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(RepeatInterleaveGrad::new(
+            og,
+            out,
+            self.repeats,
+            self.axis,
+        )));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let mut shape = input_shapes[0].clone();
+        let axis = resolve_axis("repeat_interleave", self.axis, shape.len());
+        shape[axis] *= self.repeats;
+        vec![shape]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("repeats={} axis={}", self.repeats, self.axis))
+    }
+}
+
+// Backward helper: sums each run of `repeats` consecutive slices along `axis`.
+#[derive(Debug, Clone)]
+pub struct RepeatInterleaveGrad {
+    inp: Id,
+    out: Id,
+    repeats: usize,
+    axis: isize,
+}
+
+impl RepeatInterleaveGrad {
+    pub fn new(inp: Id, out: Id, repeats: usize, axis: isize) -> Self {
+        Self {
+            inp,
+            out,
+            repeats,
+            axis,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for RepeatInterleaveGrad {
+    fn name(&self) -> &'static str {
+        "repeat_interleave_grad"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let og = ctx.checked_get(&self.inp);
+        let axis = resolve_axis("repeat_interleave_grad", self.axis, og.ndim());
+
+        // split the axis into (source, repeat) and sum the repeat part away.
+        let mut grouped = og.shape().to_vec();
+        grouped[axis] /= self.repeats;
+        grouped.insert(axis + 1, self.repeats);
+        let summed = og
+            .to_shape(grouped)
+            .expect("repeat_interleave_grad: axis length should be a multiple of repeats")
+            .sum_axis(Axis(axis + 1));
+        ctx.insert(self.out, summed);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // the adjoint of the group sum is repeating again
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(RepeatInterleave::new(
+            og,
+            out,
+            self.repeats,
+            self.axis,
+        )));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let mut shape = input_shapes[0].clone();
+        let axis = resolve_axis("repeat_interleave_grad", self.axis, shape.len());
+        shape[axis] /= self.repeats;
+        vec![shape]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("repeats={} axis={}", self.repeats, self.axis))
+    }
+}
+
+impl Tracer {
+    pub fn repeat_interleave(&self, _repeats: usize, _axis: isize) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    #[must_use]
+    pub fn repeat_interleave(&mut self, a: Tracer, repeats: usize, axis: isize) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(RepeatInterleave::new(a.id(), out, repeats, axis), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_repeat_interleave() {
+        #[trace]
+        fn rows(x: Tensor) -> Tensor {
+            x.repeat_interleave(2, 0)
+        }
+        #[trace]
+        fn cols(x: Tensor) -> Tensor {
+            x.repeat_interleave(3, -1)
+        }
+
+        let x = arr2(&[[1., 2.], [3., 4.]]).into_dyn();
+        let (out,) = trace_fn::<f32>(rows).eval()(&x);
+        assert_eq!(
+            out,
+            arr2(&[[1., 2.], [1., 2.], [3., 4.], [3., 4.]]).into_dyn()
+        );
+        let (out,) = trace_fn::<f32>(cols).eval()(&x);
+        assert_eq!(
+            out,
+            arr2(&[[1., 1., 1., 2., 2., 2.], [3., 3., 3., 4., 4., 4.]]).into_dyn()
+        );
+
+        // every source element feeds `repeats` outputs.
+        let (grad_x,) = trace_fn::<f32>(cols).grad().eval()(&x);
+        assert_eq!(grad_x, arr2(&[[3., 3.], [3., 3.]]).into_dyn());
+    }
+
+    #[test]
+    fn test_repeat_interleave_weighted_grad() {
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            (x.repeat_interleave(2, 0) * w).sum([], false)
+        }
+
+        let x = arr1(&[1., 2.]).into_dyn();
+        let w = arr1(&[1., 10., 100., 1000.]).into_dyn();
+        let (grad_x, _) = trace_fn::<f32>(f).grad().eval()((&x, &w));
+        assert_eq!(grad_x, arr1(&[11., 1100.]).into_dyn());
+    }
+}
@@ -1,4 +1,11 @@
-use crate::{Floating, Graph, Id, TraceSession, Tracer, context::Context, ops::Op};
+use ndarray::Axis;
+
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Op, normalize_axes},
+};
 
 #[derive(Debug, Clone)]
 pub struct Reshape {
@@ -45,19 +52,339 @@ impl<D: Floating> Op<D> for Reshape {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+
+    fn shape_rule(&self, _: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![self.target_shape.clone()]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("{:?}", self.target_shape))
+    }
+    fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        Some(Box::new(Self {
+            inp: inputs[0],
+            ..self.clone()
+        }))
+    }
+}
+
+impl Tracer {
+    /// `x.reshape(shape)` lowers to `sess.reshape(x, shape)`.
+    pub fn reshape(&self, _shape: impl Into<Vec<usize>>) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// `x.reshape_like(y)` lowers to `sess.reshape_like(x, y)`.
+    pub fn reshape_like(&self, _like: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// `x.flatten(start, end)` lowers to `sess.flatten(x, start, end)`.
+    pub fn flatten(&self, _start: isize, _end: isize) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// `x.as_shape(shape)` lowers to `sess.as_shape(x, shape)`.
+    pub fn as_shape(&self, _shape: impl Into<Vec<usize>>) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// `x.expand_dims(axis)` lowers to `sess.expand_dims(x, axis)`.
+    pub fn expand_dims(&self, _axis: isize) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
 }
 
-impl<D: Floating + 'static> TraceSession<'_, D> {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// The target shape is fixed at trace time, so a traced fn that reshapes
+    /// only accepts inputs matching it; use [`TraceSession::reshape_like`] to
+    /// follow a runtime shape such as a varying batch size.
     #[must_use]
     pub fn reshape(&mut self, t: Tracer, shape: impl Into<Vec<usize>>) -> Tracer {
         let out = self.g.fresh();
         self.emit(Reshape::new(t.id(), out, shape), out)
     }
+
+    /// Reshape `t` to whatever shape `like` has when the function is evaluated.
+    #[must_use]
+    pub fn reshape_like(&mut self, t: Tracer, like: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(ReshapeLike::new(t.id(), out, like.id()), out)
+    }
+
+    /// Reshape to a static `shape`, panicking with both shapes if the element
+    /// counts differ. Same as [`TraceSession::reshape`] followed by an
+    /// `assert_shape`, in a single node.
+    #[must_use]
+    pub fn as_shape(&mut self, t: Tracer, shape: impl Into<Vec<usize>>) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(AsShape::new(t.id(), out, shape), out)
+    }
+
+    /// Collapse dims `start..=end` (negative counts from the back) into one,
+    /// e.g. `(n, c, h, w)` to `(n, c*h*w)` with `flatten(x, 1, -1)`. The other
+    /// dims are read at runtime, so the batch size may vary.
+    #[must_use]
+    pub fn flatten(&mut self, t: Tracer, start: isize, end: isize) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Flatten::new(t.id(), out, start, end), out)
+    }
+
+    /// Insert a size-1 axis at `axis` (negative counts from the back of the
+    /// output), TensorFlow's `expand_dims` and torch's `unsqueeze`: `(3,)`
+    /// becomes `(1, 3)` with `expand_dims(x, 0)`. The other dims are read at
+    /// runtime.
+    #[must_use]
+    pub fn expand_dims(&mut self, t: Tracer, axis: isize) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(ExpandDims::new(t.id(), out, axis), out)
+    }
 }
 
-impl Tracer {
-    pub fn reshape(&self, _: impl Into<Vec<usize>>) -> Tracer {
-        panic!("dummy operation - only allowed inside #[trace] function")
+#[derive(Debug, Clone)]
+pub struct AsShape {
+    inp: Id,
+    out: Id,
+    shape: Vec<usize>,
+}
+
+impl AsShape {
+    pub fn new(inp: Id, out: Id, shape: impl Into<Vec<usize>>) -> Self {
+        Self {
+            inp,
+            out,
+            shape: shape.into(),
+        }
+    }
+
+    fn check(&self, shape: &[usize]) {
+        assert!(
+            shape.iter().product::<usize>() == self.shape.iter().product::<usize>(),
+            "as_shape: cannot view {shape:?} as {:?} (tensor {:?})",
+            self.shape,
+            self.inp
+        );
+    }
+}
+
+impl<D: Floating> Op<D> for AsShape {
+    fn name(&self) -> &'static str {
+        "as_shape"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let t = ctx.checked_get(&self.inp);
+        self.check(t.shape());
+        let y = t
+            .to_shape(&*self.shape)
+            .expect("as_shape should succeed as the number of elements was checked")
+            .to_owned();
+        ctx.insert(self.out, y);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d/dx as_shape(x) = reshape_like(og, like=x)
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(ReshapeLike::new(og, out, self.inp)));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        self.check(&input_shapes[0]);
+        vec![self.shape.clone()]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("{:?}", self.shape))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Flatten {
+    inp: Id,
+    out: Id,
+    start: isize,
+    end: isize,
+}
+
+impl Flatten {
+    pub fn new(inp: Id, out: Id, start: isize, end: isize) -> Self {
+        Self {
+            inp,
+            out,
+            start,
+            end,
+        }
+    }
+
+    fn flattened_shape(&self, shape: &[usize]) -> Vec<usize> {
+        // a scalar flattens to a single element, as in torch.
+        if shape.is_empty() {
+            return vec![1];
+        }
+        let start = normalize_axes("flatten", &[self.start], shape.len())[0];
+        let end = normalize_axes("flatten", &[self.end], shape.len())[0];
+        assert!(
+            start <= end,
+            "flatten: start dim {} comes after end dim {} for shape {shape:?}",
+            self.start,
+            self.end
+        );
+        let mut out = shape[..start].to_vec();
+        out.push(shape[start..=end].iter().product());
+        out.extend_from_slice(&shape[end + 1..]);
+        out
+    }
+}
+
+impl<D: Floating> Op<D> for Flatten {
+    fn name(&self) -> &'static str {
+        "flatten"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let t = ctx.checked_get(&self.inp);
+        let target = self.flattened_shape(t.shape());
+        let y = t
+            .to_shape(target)
+            .expect("flatten should succeed as the number of elements is preserved")
+            .to_owned();
+        ctx.insert(self.out, y);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d/dx flatten(x) = reshape_like(og, like=x)
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(ReshapeLike::new(og, out, self.inp)));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![self.flattened_shape(&input_shapes[0])]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("{}..={}", self.start, self.end))
+    }
+}
+
+/// inserts a size-1 axis at `axis`, which counts positions in the output, so
+/// `-1` appends a trailing axis.
+#[derive(Debug, Clone)]
+pub struct ExpandDims {
+    inp: Id,
+    out: Id,
+    axis: isize,
+}
+
+impl ExpandDims {
+    pub fn new(inp: Id, out: Id, axis: isize) -> Self {
+        Self { inp, out, axis }
+    }
+
+    fn expanded_shape(&self, shape: &[usize]) -> Vec<usize> {
+        let axis = normalize_axes("expand_dims", &[self.axis], shape.len() + 1)[0];
+        let mut out = shape.to_vec();
+        out.insert(axis, 1);
+        out
+    }
+}
+
+impl<D: Floating> Op<D> for ExpandDims {
+    fn name(&self) -> &'static str {
+        "expand_dims"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let t = ctx.checked_get(&self.inp);
+        let axis = normalize_axes("expand_dims", &[self.axis], t.ndim() + 1)[0];
+        ctx.insert(self.out, t.clone().insert_axis(Axis(axis)));
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // d/dx expand_dims(x) = reshape_like(og, like=x)
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(ReshapeLike::new(og, out, self.inp)));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![self.expanded_shape(&input_shapes[0])]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("axis={}", self.axis))
     }
 }
 
@@ -105,4 +432,174 @@ impl<D: Floating> Op<D> for ReshapeLike {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        // [inp, like]
+        vec![input_shapes[1].clone()]
+    }
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+    fn is_linear(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{TraceSession, Tracer};
+
+    #[test]
+    fn test_reshape_dummy_and_session_signatures_agree() {
+        use crate::prelude::*;
+        use ndarray::{arr1, arr2};
+
+        // the dummy is the session method minus its leading tracer.
+        fn dummy(x: &Tracer, shape: Vec<usize>) -> Tracer {
+            x.reshape(shape)
+        }
+        fn session(sess: &mut TraceSession<f32>, x: Tracer, shape: Vec<usize>) -> Tracer {
+            sess.reshape(x, shape)
+        }
+        let _ = (dummy, session);
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.reshape(vec![2, 2]).reshape([4])
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let x = arr2(&[[1., 2.], [3., 4.]]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, arr1(&[1., 2., 3., 4.]).into_dyn());
+    }
+
+    #[test]
+    #[should_panic(expected = "only allowed inside #[trace] function")]
+    fn test_reshape_dummy_panics_outside_trace() {
+        let x = Tracer::new(crate::Graph::<f32>::new().fresh());
+        let _ = x.reshape(vec![1]);
+    }
+
+    #[test]
+    fn test_flatten() {
+        use crate::prelude::*;
+        use ndarray::Array;
+
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            (x.flatten(1, 2) * w).sum(vec![], false)
+        }
+
+        let x = Array::from_shape_fn((2, 3, 4), |(i, j, k)| (i * 12 + j * 4 + k) as f32).into_dyn();
+        let w = Array::from_shape_fn((2, 12), |(i, j)| (i * 12 + j) as f32 * 0.5).into_dyn();
+        let traced = trace_fn::<f32>(f);
+        assert_eq!(
+            traced.output_shapes(&[vec![2, 3, 4], vec![2, 12]]),
+            vec![Vec::<usize>::new()]
+        );
+
+        let (gx, gw) = traced.grad().eval()((&x, &w));
+        assert_eq!(gx, w.to_shape((2, 3, 4)).unwrap().to_owned().into_dyn());
+        assert_eq!(gw, x.to_shape((2, 12)).unwrap().to_owned().into_dyn());
+    }
+
+    #[test]
+    fn test_flatten_negative_end() {
+        use crate::prelude::*;
+        use ndarray::Array;
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.flatten(1, -1)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        assert_eq!(traced.output_shapes(&[vec![5, 2, 3, 4]]), vec![vec![5, 24]]);
+        let x = Array::from_shape_fn((2, 3, 4), |(i, j, k)| (i * 12 + j * 4 + k) as f32).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(
+            out,
+            Array::from_shape_fn((2, 12), |(i, j)| (i * 12 + j) as f32).into_dyn()
+        );
+    }
+
+    #[test]
+    fn test_as_shape() {
+        use crate::prelude::*;
+        use ndarray::Array;
+
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            (x.as_shape([10, 20]) * w).sum(vec![], false)
+        }
+
+        let x = Array::from_shape_fn(200, |i| i as f32).into_dyn();
+        let w = Array::from_shape_fn((10, 20), |(i, j)| (i * 20 + j) as f32 * 0.5).into_dyn();
+        let traced = trace_fn::<f32>(f);
+        assert_eq!(traced.graph.op_histogram()["as_shape"], 1);
+
+        let (gx, gw) = traced.grad().eval()((&x, &w));
+        assert_eq!(gx, w.to_shape(200).unwrap().to_owned().into_dyn());
+        assert_eq!(gw, x.to_shape((10, 20)).unwrap().to_owned().into_dyn());
+    }
+
+    #[test]
+    #[should_panic(expected = "as_shape: cannot view [199] as [10, 20]")]
+    fn test_as_shape_element_count_mismatch() {
+        use crate::prelude::*;
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.as_shape([10, 20])
+        }
+
+        let x = ndarray::Array::zeros(199).into_dyn();
+        let _: (ndarray::ArrayD<f32>,) = trace_fn::<f32>(f).eval()(&x);
+    }
+
+    #[test]
+    fn test_expand_dims() {
+        use crate::prelude::*;
+        use ndarray::{arr1, arr2};
+
+        #[trace]
+        fn f(x: Tensor, w: Tensor) -> Tensor {
+            (x.expand_dims(0) * w).sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        assert_eq!(
+            traced.output_shapes(&[vec![3], vec![2, 3]]),
+            vec![Vec::<usize>::new()]
+        );
+
+        let x = arr1(&[1., 2., 3.]).into_dyn();
+        let w = arr2(&[[1., 2., 3.], [10., 20., 30.]]).into_dyn();
+        let (out,) = traced.eval()((&x, &w));
+        assert_eq!(out[[]], 154.);
+
+        // (1, 3) broadcasts over w's rows, so the gradient sums them back.
+        let (gx, gw) = traced.grad().eval()((&x, &w));
+        assert_eq!(gx, arr1(&[11., 22., 33.]).into_dyn());
+        assert_eq!(gw, arr2(&[[1., 2., 3.], [1., 2., 3.]]).into_dyn());
+    }
+
+    #[test]
+    fn test_expand_dims_shape() {
+        use crate::prelude::*;
+
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.expand_dims(0).expand_dims(-1)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        assert_eq!(traced.output_shapes(&[vec![3]]), vec![vec![1, 3, 1]]);
+        let x = ndarray::arr1(&[1., 2., 3.]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out.shape(), &[1, 3, 1]);
+    }
 }
@@ -0,0 +1,67 @@
+use crate::{
+    Floating, TraceSession, Tracer,
+    context::Context,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+    ops::Op,
+};
+
+/// elementwise round to the nearest integer, halves away from zero.
+#[derive(Debug, Clone)]
+pub struct Round {
+    inp: Id,
+    out: Id,
+}
+
+impl Round {
+    pub fn new(inp: Id, out: Id) -> Self {
+        Self { inp, out }
+    }
+}
+
+impl<D: Floating> Op<D> for Round {
+    fn name(&self) -> &'static str {
+        "round"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let t = ctx.checked_get(&self.inp);
+        ctx.insert(self.out, t.mapv(|a| a.round()));
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, _out_grads: &[Id]) -> Option<Vec<Id>> {
+        // piecewise constant, so the gradient is zero almost everywhere;
+        // see `straight_through` for passing one through anyway.
+        None
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+impl Tracer {
+    pub fn round(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    #[must_use]
+    pub fn round(&mut self, a: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Round::new(a.id(), out), out)
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    identity::IdGenerator,
+    ops::{Const, Mul},
+    simple_unary_op,
+    tracing::TensorData,
+};
+
+simple_unary_op!(
+    Rsqrt,
+    disp: "rsqrt",
+    fwd: |x: &TensorData<D>| x.mapv(|a| a.sqrt().recip()),
+    vjp: |this: &Rsqrt, g: &mut Graph<D>, og: Id| {
+        // d/dx x^(-1/2) = -0.5 * x^(-3/2) = -0.5 * out^3, reusing the forward output
+        let sq = g.fresh();
+        g.push(Box::new(Mul::new(this.out, this.out, sq)));
+        let cube = g.fresh();
+        g.push(Box::new(Mul::new(sq, this.out, cube)));
+        let half = g.fresh();
+        g.push(Box::new(Const::new(D::from_f64(-0.5), half)));
+        let deriv = g.fresh();
+        g.push(Box::new(Mul::new(cube, half, deriv)));
+        let out = g.fresh();
+        g.push(Box::new(Mul::new(og, deriv, out)));
+        out
+    }
+);
+
+impl Tracer {
+    pub fn rsqrt(&self) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// `1 / sqrt(a)` as a single node, e.g. for `(var + eps).rsqrt()`.
+    #[must_use]
+    pub fn rsqrt(&mut self, a: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Rsqrt::new(a.id(), out), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr1;
+
+    use crate::{prelude::*, tests::assert_grad_matches_fd};
+
+    #[test]
+    fn test_rsqrt_grad_matches_finite_differences() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.rsqrt()
+        }
+
+        let traced = trace_fn::<f64>(f);
+        let x = arr1(&[0.04, 0.5, 1., 4., 9.]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(
+            out,
+            arr1(&[5., 0.5f64.sqrt().recip(), 1., 0.5, 1. / 3.]).into_dyn()
+        );
+
+        let (grad,) = traced.grad().eval()(&x);
+        // exactly -0.5 * x^-1.5 at x = 4
+        assert_eq!(grad[3], -0.0625);
+        assert_grad_matches_fd(&traced, &[x], 1e-7, 1e-5);
+    }
+}
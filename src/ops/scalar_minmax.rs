@@ -0,0 +1,248 @@
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Mul, Op},
+};
+
+/// elementwise clamp against a scalar `bound`, with its gradient mask.
+///
+/// the gradient flows only where `x` is strictly on the passing side of `bound`,
+/// matching the `relu` convention at the boundary.
+macro_rules! scalar_bound_op {
+    ($name:ident, disp: $strname:expr, pick: $pick:ident, above: $above:expr) => {
+        #[derive(Debug, Clone)]
+        pub struct $name<D: Floating> {
+            pub inp: Id,
+            pub out: Id,
+            pub bound: D,
+        }
+
+        impl<D: Floating> $name<D> {
+            pub fn new(inp: Id, out: Id, bound: D) -> Self {
+                Self { inp, out, bound }
+            }
+        }
+
+        impl<D: Floating + 'static> Op<D> for $name<D> {
+            fn name(&self) -> &'static str {
+                $strname
+            }
+
+            fn eval(&self, ctx: &mut Context<D>) {
+                let x = ctx.checked_get(&self.inp);
+                let bound = self.bound;
+                ctx.insert(self.out, x.mapv(|a| a.$pick(bound)));
+            }
+
+            fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+                let og = *out_grads.first()?;
+                let mask = g.fresh();
+                g.push(Box::new(ScalarBoundMask::new(
+                    self.inp, mask, self.bound, $above,
+                )));
+                let prod = g.fresh();
+                g.push(Box::new(Mul::new(og, mask, prod)));
+                Some(vec![prod])
+            }
+
+            fn inputs(&self) -> Vec<Id> {
+                vec![self.inp]
+            }
+
+            fn outputs(&self) -> Vec<Id> {
+                vec![self.out]
+            }
+
+            fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+                vec![input_shapes[0].clone()]
+            }
+
+            fn retype_f64(&self) -> Box<dyn Op<f64>> {
+                Box::new($name::new(self.inp, self.out, self.bound.as_f64()))
+            }
+
+            fn attrs(&self) -> Option<String> {
+                Some(format!("bound={:?}", self.bound))
+            }
+        }
+    };
+}
+
+scalar_bound_op!(MaximumScalar, disp: "maximum_scalar", pick: max, above: true);
+scalar_bound_op!(MinimumScalar, disp: "minimum_scalar", pick: min, above: false);
+
+// Backward helper: 1.0 where x is strictly above (or below) `bound`, else 0.0
+#[derive(Debug, Clone)]
+pub struct ScalarBoundMask<D: Floating> {
+    inp: Id,
+    out: Id,
+    bound: D,
+    above: bool,
+}
+
+impl<D: Floating> ScalarBoundMask<D> {
+    pub fn new(inp: Id, out: Id, bound: D, above: bool) -> Self {
+        Self {
+            inp,
+            out,
+            bound,
+            above,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for ScalarBoundMask<D> {
+    fn name(&self) -> &'static str {
+        "scalar_bound_mask"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let passes = |a: D| {
+            if self.above {
+                a > self.bound
+            } else {
+                a < self.bound
+            }
+        };
+        let mask = x.mapv(|a| if passes(a) { D::one() } else { D::zero() });
+        ctx.insert(self.out, mask);
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, _out_grads: &[Id]) -> Option<Vec<Id>> {
+        // the indicator is piecewise constant, so no backward pass
+        None
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![input_shapes[0].clone()]
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(ScalarBoundMask::new(
+            self.inp,
+            self.out,
+            self.bound.as_f64(),
+            self.above,
+        ))
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("bound={:?} above={}", self.bound, self.above))
+    }
+}
+
+impl Tracer {
+    pub fn maximum_scalar(&self, _bound: f64) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn minimum_scalar(&self, _bound: f64) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn clamp(&self, _lo: f64, _hi: f64) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    #[must_use]
+    pub fn maximum_scalar(&mut self, a: Tracer, bound: D) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(MaximumScalar::new(a.id(), out, bound), out)
+    }
+
+    #[must_use]
+    pub fn minimum_scalar(&mut self, a: Tracer, bound: D) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(MinimumScalar::new(a.id(), out, bound), out)
+    }
+
+    /// `maximum_scalar(lo)` followed by `minimum_scalar(hi)`.
+    #[must_use]
+    pub fn clamp(&mut self, a: Tracer, lo: D, hi: D) -> Tracer {
+        assert!(lo <= hi, "clamp: lo should not exceed hi");
+        let floored = self.maximum_scalar(a, lo);
+        self.minimum_scalar(floored, hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr1;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_maximum_minimum_scalar() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.maximum_scalar(0.5).minimum_scalar(2.0).sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let x = arr1(&[-1., 0.5, 1., 3.]).into_dyn();
+
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, ndarray::arr0(0.5 + 0.5 + 1. + 2.).into_dyn());
+
+        // no gradient at or beyond either bound
+        let (grad_x,) = traced.grad().eval()(&x);
+        assert_eq!(grad_x, arr1(&[0., 0., 1., 0.]).into_dyn());
+    }
+
+    #[test]
+    fn test_maximum_scalar_agrees_with_relu() {
+        #[trace]
+        fn relu(x: Tensor) -> Tensor {
+            x.relu()
+        }
+        #[trace]
+        fn maximum(x: Tensor) -> Tensor {
+            x.maximum_scalar(0.0)
+        }
+
+        // the tie at 0 gets no gradient either way.
+        let x = arr1(&[-1., 0., 2.]).into_dyn();
+        let relu = trace_fn::<f32>(relu);
+        let maximum = trace_fn::<f32>(maximum);
+        let (a,) = relu.eval()(&x);
+        let (b,) = maximum.eval()(&x);
+        assert_eq!(a, b);
+        let (ga,) = relu.grad().eval()(&x);
+        let (gb,) = maximum.grad().eval()(&x);
+        assert_eq!(ga, arr1(&[0., 0., 1.]).into_dyn());
+        assert_eq!(ga, gb);
+    }
+
+    #[test]
+    fn test_clip_grad_value() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            (x * x * 50.0).sum([], false)
+        }
+
+        let x = arr1(&[0.001, 1., -2.]).into_dyn();
+        let grad = trace_fn::<f32>(f).grad();
+        let (raw,) = grad.eval()(&x);
+        assert_eq!(raw, arr1(&[0.1, 100., -200.]).into_dyn());
+
+        let (clipped,) = grad.clip_grad_value(1.0).eval()(&x);
+        assert_eq!(clipped, arr1(&[0.1, 1., -1.]).into_dyn());
+
+        #[trace]
+        fn g(x: Tensor) -> Tensor {
+            x.clamp(-1.0, 1.0)
+        }
+        let (out,) = trace_fn::<f32>(g).eval()(&x);
+        assert_eq!(out, arr1(&[0.001, 1., -1.]).into_dyn());
+    }
+}
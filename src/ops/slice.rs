@@ -0,0 +1,334 @@
+use core::ops::Range;
+
+use ndarray::{Axis, Slice as AxisSlice};
+
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Op, normalize_axes},
+    tracing::TensorData,
+};
+
+/// where a slice lands along its axis, shared by [`Slice`] and [`SliceGrad`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SliceSpec {
+    pub axis: isize,
+    /// negative values count from the end of the axis.
+    pub start: isize,
+    pub len: usize,
+    /// drop the sliced axis; only valid for `len == 1`, as in `x[i]`.
+    pub drop_axis: bool,
+}
+
+impl SliceSpec {
+    fn attrs(&self) -> String {
+        let SliceSpec {
+            axis,
+            start,
+            len,
+            drop_axis,
+        } = *self;
+        let drop = if drop_axis { " drop_axis" } else { "" };
+        format!("axis={axis}, {start}+{len}{drop}")
+    }
+
+    /// concrete `(axis, start)` for an input of `shape`.
+    fn resolve(&self, shape: &[usize]) -> (usize, usize) {
+        let axis = normalize_axes("slice", &[self.axis], shape.len())[0];
+        let dim = shape[axis];
+        let start = if self.start < 0 {
+            dim.checked_sub(self.start.unsigned_abs())
+        } else {
+            Some(self.start as usize)
+        };
+        match start {
+            Some(start) if start + self.len <= dim => (axis, start),
+            _ => panic!(
+                "slice: {} elements from {} are out of bounds for axis {} of {shape:?}",
+                self.len, self.start, self.axis
+            ),
+        }
+    }
+
+    fn out_shape(&self, shape: &[usize]) -> Vec<usize> {
+        let (axis, _) = self.resolve(shape);
+        let mut out = shape.to_vec();
+        if self.drop_axis {
+            out.remove(axis);
+        } else {
+            out[axis] = self.len;
+        }
+        out
+    }
+}
+
+/// A contiguous run of `inp` along one axis.
+#[derive(Debug, Clone)]
+pub struct Slice {
+    inp: Id,
+    out: Id,
+    spec: SliceSpec,
+}
+
+impl Slice {
+    pub fn new(inp: Id, out: Id, spec: SliceSpec) -> Self {
+        assert!(
+            !spec.drop_axis || spec.len == 1,
+            "slice: can only drop an axis sliced to length 1, got {}",
+            spec.len
+        );
+        Self { inp, out, spec }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for Slice {
+    fn name(&self) -> &'static str {
+        "slice"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let (axis, start) = self.spec.resolve(x.shape());
+        let y = if self.spec.drop_axis {
+            x.index_axis(Axis(axis), start).to_owned()
+        } else {
+            x.slice_axis(Axis(axis), AxisSlice::from(start..start + self.spec.len))
+                .to_owned()
+        };
+        ctx.insert(self.out, y);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // scatter og back into zeros shaped like the input
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(SliceGrad::new(og, self.inp, out, self.spec)));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![self.spec.out_shape(&input_shapes[0])]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(self.spec.attrs())
+    }
+}
+
+/// Zeros shaped like `like`, with `og` written where [`Slice`] read from.
+#[derive(Debug, Clone)]
+pub struct SliceGrad {
+    og: Id,
+    like: Id,
+    out: Id,
+    spec: SliceSpec,
+}
+
+impl SliceGrad {
+    pub fn new(og: Id, like: Id, out: Id, spec: SliceSpec) -> Self {
+        Self {
+            og,
+            like,
+            out,
+            spec,
+        }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for SliceGrad {
+    fn name(&self) -> &'static str {
+        "slice_grad"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let og = ctx.checked_get(&self.og);
+        let like = ctx.checked_get(&self.like);
+        let (axis, start) = self.spec.resolve(like.shape());
+        let mut out = TensorData::zeros(like.shape());
+        let mut window =
+            out.slice_axis_mut(Axis(axis), AxisSlice::from(start..start + self.spec.len));
+        if self.spec.drop_axis {
+            window.assign(&og.view().insert_axis(Axis(axis)));
+        } else {
+            window.assign(og);
+        }
+        ctx.insert(self.out, out);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // only og is differentiable; `like` just supplies the shape
+        let grad = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(Slice::new(grad, out, self.spec)));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.og, self.like]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        // [og, like]
+        vec![input_shapes[1].clone()]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(self.spec.attrs())
+    }
+}
+
+impl Tracer {
+    pub fn slice(&self, _axis: isize, _range: Range<usize>) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn index(&self, _index: isize) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn select_axis(&self, _axis: isize, _index: isize) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// the elements of `a` at `range` along `axis`, keeping the axis.
+    #[must_use]
+    pub fn slice(&mut self, a: Tracer, axis: isize, range: Range<usize>) -> Tracer {
+        let spec = SliceSpec {
+            axis,
+            start: range.start as isize,
+            len: range.len(),
+            drop_axis: false,
+        };
+        let out = self.g.fresh();
+        self.emit(Slice::new(a.id(), out, spec), out)
+    }
+
+    /// `a[index]`: entry `index` along the first axis, with that axis
+    /// removed. negative indices count from the end. this is what `x[i]`
+    /// lowers to inside `#[trace]`, where `i` must be a constant.
+    #[must_use]
+    pub fn index(&mut self, a: Tracer, index: isize) -> Tracer {
+        self.select_axis(a, 0, index)
+    }
+
+    /// entry `index` along `axis`, with that axis removed, like NumPy's
+    /// `x.take(index, axis)` with an integer index; `x[:, k]` is
+    /// `select_axis(x, 1, k)`. the gradient is `og` scattered back at `index`.
+    #[must_use]
+    pub fn select_axis(&mut self, a: Tracer, axis: isize, index: isize) -> Tracer {
+        let spec = SliceSpec {
+            axis,
+            start: index,
+            len: 1,
+            drop_axis: true,
+        };
+        let out = self.g.fresh();
+        self.emit(Slice::new(a.id(), out, spec), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{arr1, arr2, s};
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_index_first_row_grad_is_one_hot() {
+        #[trace]
+        fn first_row(x: Tensor, w: Tensor) -> Tensor {
+            (x[0] * w).sum(vec![], false)
+        }
+
+        let traced = trace_fn::<f32>(first_row);
+        let x = arr2(&[[1., 2., 3.], [4., 5., 6.]]).into_dyn();
+        let w = arr1(&[1., 10., 100.]).into_dyn();
+        let (out,) = traced.eval()((&x, &w));
+        assert_eq!(out[[]], 321.);
+
+        let (gx, gw) = traced.grad().eval()((&x, &w));
+        assert_eq!(gx, arr2(&[[1., 10., 100.], [0., 0., 0.]]).into_dyn());
+        assert_eq!(gw, arr1(&[1., 2., 3.]).into_dyn());
+    }
+
+    #[test]
+    fn test_slice_and_negative_index() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x.slice(1, 1..3) + x[-1].slice(0, 0..2)
+        }
+
+        let traced = trace_fn::<f32>(f);
+        let x = arr2(&[[1., 2., 3.], [4., 5., 6.]]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        let expected = &x.slice(s![.., 1..3]) + &x.slice(s![1, 0..2]);
+        assert_eq!(out, expected.into_dyn());
+
+        let (gx,) = traced.grad().eval()(&x);
+        assert_eq!(gx, arr2(&[[0., 1., 1.], [2., 3., 1.]]).into_dyn());
+    }
+
+    #[test]
+    fn test_select_axis_column_grad_is_one_hot() {
+        #[trace]
+        fn column(x: Tensor) -> Tensor {
+            x.select_axis(1, 1)
+        }
+
+        let traced = trace_fn::<f32>(column);
+        let x = arr2(&[[1., 2., 3.], [4., 5., 6.]]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, arr1(&[2., 5.]).into_dyn());
+
+        let (gx,) = traced.grad().eval()(&x);
+        assert_eq!(gx, arr2(&[[0., 1., 0.], [0., 1., 0.]]).into_dyn());
+    }
+
+    #[test]
+    #[should_panic(expected = "slice: 1 elements from 2 are out of bounds for axis 0 of [2, 3]")]
+    fn test_index_out_of_bounds() {
+        #[trace]
+        fn f(x: Tensor) -> Tensor {
+            x[2]
+        }
+        let x = arr2(&[[1., 2., 3.], [4., 5., 6.]]).into_dyn();
+        let _: (ndarray::ArrayD<f32>,) = trace_fn::<f32>(f).eval()(&x);
+    }
+}
@@ -0,0 +1,197 @@
+use ndarray::Axis;
+
+use crate::{
+    Floating, TraceSession, Tracer,
+    context::Context,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+    ops::{
+        MatMul, Op,
+        linalg::{Lu, check_square},
+        neg::Neg,
+        transpose::TransposeDefault,
+    },
+    tracing::TensorData,
+};
+
+/// `x` with `a @ x = b`, for each square `a` and `[n, k]` `b` over the last
+/// two axes. Leading (batch) axes of `a` and `b` must match.
+#[derive(Debug, Clone)]
+pub struct Solve {
+    a: Id,
+    b: Id,
+    out: Id,
+}
+
+impl Solve {
+    pub fn new(a: Id, b: Id, out: Id) -> Self {
+        Self { a, b, out }
+    }
+
+    fn check_shapes(&self, a: &[usize], b: &[usize]) {
+        check_square("solve", a);
+        let rank = a.len();
+        assert!(
+            b.len() == rank && b[..rank - 2] == a[..rank - 2] && b[rank - 2] == a[rank - 1],
+            "solve: cannot solve {a:?} (tensor {:?}) against {b:?} (tensor {:?})",
+            self.a,
+            self.b
+        );
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for Solve {
+    fn name(&self) -> &'static str {
+        "solve"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let a = ctx.checked_get(&self.a);
+        let b = ctx.checked_get(&self.b);
+        self.check_shapes(a.shape(), b.shape());
+
+        let shape = b.shape().to_vec();
+        let rank = shape.len();
+        let (n, k) = (shape[rank - 2], shape[rank - 1]);
+        let batch: usize = shape[..rank - 2].iter().product();
+
+        let a_stacked = a
+            .to_shape((batch, n, n))
+            .expect("reshape should succeed because the number of elements is preserved");
+        let b_stacked = b
+            .to_shape((batch, n, k))
+            .expect("reshape should succeed because the number of elements is preserved");
+        let mut result = TensorData::zeros(shape.clone());
+        {
+            let mut out = result
+                .view_mut()
+                .into_shape_with_order((batch, n, k))
+                .expect("reshape should succeed because the number of elements is preserved");
+            for (i, (am, bm)) in a_stacked
+                .axis_iter(Axis(0))
+                .zip(b_stacked.axis_iter(Axis(0)))
+                .enumerate()
+            {
+                let x = Lu::new(am).solve(bm).unwrap_or_else(|| {
+                    panic!("solve: matrix {i} of a {:?} input is singular", a.shape())
+                });
+                out.index_axis_mut(Axis(0), i).assign(&x);
+            }
+        }
+        ctx.insert(self.out, result);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // grad_b = solve(a^T, og), grad_a = -grad_b @ x^T
+        let og = *out_grads.first()?;
+        let a_t = g.fresh();
+        g.push(Box::new(TransposeDefault::new(self.a, a_t)));
+        let grad_b = g.fresh();
+        g.push(Box::new(Solve::new(a_t, og, grad_b)));
+
+        let x_t = g.fresh();
+        g.push(Box::new(TransposeDefault::new(self.out, x_t)));
+        let outer = g.fresh();
+        g.push(Box::new(MatMul::new(grad_b, x_t, outer)));
+        let grad_a = g.fresh();
+        g.push(Box::new(Neg::new(outer, grad_a)));
+        Some(vec![grad_a, grad_b])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.a, self.b]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        self.check_shapes(&input_shapes[0], &input_shapes[1]);
+        vec![input_shapes[1].clone()]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        // LU of a plus a forward and back substitution per column of b
+        let b = &input_shapes[1];
+        let rank = b.len();
+        let (n, k) = (b[rank - 2] as u64, b[rank - 1] as u64);
+        let batch = b[..rank - 2].iter().product::<usize>() as u64;
+        batch * n * n * (n + 2 * k)
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+}
+
+impl Tracer {
+    pub fn solve(&self, _b: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// `x` with `a @ x = b`, without forming the inverse of `a`.
+    /// `b` is `[..., n, k]`; panics at eval if any `a` is singular.
+    #[must_use]
+    pub fn solve(&mut self, a: Tracer, b: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Solve::new(a.id(), b.id(), out), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{Array, arr2};
+
+    use crate::{prelude::*, tests::assert_grad_matches_fd, tracing::TensorData};
+
+    #[trace]
+    fn weighted_solve(a: Tensor, b: Tensor, w: Tensor) -> Tensor {
+        (a.solve(b) * w).sum(vec![], false)
+    }
+
+    #[test]
+    fn test_solve_forward() {
+        let a = Array::from_shape_vec(
+            (2, 3, 3),
+            vec![
+                4., 1., 0., 1., 3., 1., 0., 1., 2., //
+                0., 2., 1., 1., 0., 0., 3., 1., 5., //
+            ],
+        )
+        .unwrap()
+        .into_dyn();
+        let x = Array::from_shape_vec((2, 3, 2), (0..12).map(f64::from).collect())
+            .unwrap()
+            .into_dyn();
+
+        #[trace]
+        fn f(a: Tensor, x: Tensor) -> Tensor {
+            a.solve(a.matmul(x))
+        }
+        let (out,) = trace_fn::<f64>(f).eval()((&a, &x));
+        for (got, want) in out.iter().zip(x.iter()) {
+            assert!((got - want).abs() < 1e-12, "{out:?}");
+        }
+    }
+
+    #[test]
+    fn test_solve_grads_match_finite_differences() {
+        let a = arr2(&[[4., 1., 0.5], [1., 3., 1.], [0., 1., 2.]]).into_dyn();
+        let b = arr2(&[[1., 2.], [-1., 0.5], [3., 1.]]).into_dyn();
+        let w = arr2(&[[1., -2.], [0.25, 1.], [-1., 2.]]).into_dyn();
+        let traced = trace_fn::<f64>(weighted_solve);
+        assert_grad_matches_fd(&traced, &[a, b, w], 1e-6, 1e-6);
+    }
+
+    #[test]
+    #[should_panic(expected = "solve: cannot solve [2, 2]")]
+    fn test_solve_shape_mismatch() {
+        let a = arr2(&[[1., 0.], [0., 1.]]).into_dyn();
+        let b = arr2(&[[1.], [2.], [3.]]).into_dyn();
+        let w = arr2(&[[1.], [1.], [1.]]).into_dyn();
+        let _: (TensorData<f64>,) = trace_fn::<f64>(weighted_solve).eval()((&a, &b, &w));
+    }
+}
@@ -0,0 +1,379 @@
+use ndarray::{Axis, Slice};
+
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    context::Context,
+    identity::IdGenerator,
+    ops::{Op, normalize_axes},
+    tracing::TensorData,
+};
+
+fn resolve_axis(op: &str, axis: isize, rank: usize) -> usize {
+    normalize_axes(op, &[axis], rank)[0]
+}
+
+fn slice_pieces<D: Floating>(
+    x: &TensorData<D>,
+    axis: usize,
+    sizes: impl IntoIterator<Item = usize>,
+) -> Vec<TensorData<D>> {
+    let mut start = 0;
+    sizes
+        .into_iter()
+        .map(|size| {
+            let piece = x
+                .slice_axis(Axis(axis), Slice::from(start..start + size))
+                .to_owned();
+            start += size;
+            piece
+        })
+        .collect()
+}
+
+/// Splits `inp` along `axis` into consecutive pieces of the given `sizes`.
+#[derive(Debug, Clone)]
+pub struct Split {
+    inp: Id,
+    outs: Vec<Id>,
+    sizes: Vec<usize>,
+    axis: isize,
+}
+
+impl Split {
+    pub fn new(inp: Id, outs: Vec<Id>, sizes: impl Into<Vec<usize>>, axis: isize) -> Self {
+        let sizes = sizes.into();
+        assert_eq!(
+            outs.len(),
+            sizes.len(),
+            "split: expected one output per size"
+        );
+        Self {
+            inp,
+            outs,
+            sizes,
+            axis,
+        }
+    }
+
+    fn check(&self, shape: &[usize]) -> usize {
+        let axis = resolve_axis("split", self.axis, shape.len());
+        let total: usize = self.sizes.iter().sum();
+        assert!(
+            total == shape[axis],
+            "split: sizes {:?} add up to {total}, but axis {} has length {}",
+            self.sizes,
+            self.axis,
+            shape[axis]
+        );
+        axis
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for Split {
+    fn name(&self) -> &'static str {
+        "split"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let axis = self.check(x.shape());
+        let pieces = slice_pieces(x, axis, self.sizes.iter().copied());
+        for (out, piece) in self.outs.iter().zip(pieces) {
+            ctx.insert(*out, piece);
+        }
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // concat the per-piece gradients back along `axis`
+        let out = g.fresh();
+        g.push(Box::new(Concat::new(out_grads.to_vec(), out, self.axis)));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.inp]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        self.outs.clone()
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let axis = self.check(&input_shapes[0]);
+        self.sizes
+            .iter()
+            .map(|&size| {
+                let mut shape = input_shapes[0].clone();
+                shape[axis] = size;
+                shape
+            })
+            .collect()
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("sizes={:?} axis={}", self.sizes, self.axis))
+    }
+}
+
+/// Joins `inps` end to end along `axis`; all other dims must agree.
+#[derive(Debug, Clone)]
+pub struct Concat {
+    inps: Vec<Id>,
+    out: Id,
+    axis: isize,
+}
+
+impl Concat {
+    pub fn new(inps: Vec<Id>, out: Id, axis: isize) -> Self {
+        assert!(!inps.is_empty(), "concat: needs at least one input");
+        Self { inps, out, axis }
+    }
+}
+
+impl<D: Floating + 'static> Op<D> for Concat {
+    fn name(&self) -> &'static str {
+        "concat"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let parts: Vec<_> = self
+            .inps
+            .iter()
+            .map(|id| ctx.checked_get(id).view())
+            .collect();
+        let axis = resolve_axis("concat", self.axis, parts[0].ndim());
+        let joined = ndarray::concatenate(Axis(axis), &parts).unwrap_or_else(|_| {
+            let shapes: Vec<_> = parts.iter().map(|p| p.shape().to_vec()).collect();
+            panic!(
+                "concat: cannot join shapes {shapes:?} along axis {}",
+                self.axis
+            )
+        });
+        ctx.insert(self.out, joined);
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // slice og back into pieces shaped like each input
+        let og = *out_grads.first()?;
+        let outs: Vec<Id> = self.inps.iter().map(|_| g.fresh()).collect();
+        g.push(Box::new(SplitLike {
+            inp: og,
+            likes: self.inps.clone(),
+            outs: outs.clone(),
+            axis: self.axis,
+        }));
+        Some(outs)
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        self.inps.clone()
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let axis = resolve_axis("concat", self.axis, input_shapes[0].len());
+        let mut shape = input_shapes[0].clone();
+        for other in &input_shapes[1..] {
+            let agrees = other.len() == shape.len()
+                && (0..shape.len()).all(|d| d == axis || other[d] == shape[d]);
+            assert!(
+                agrees,
+                "concat: cannot join shapes {input_shapes:?} along axis {}",
+                self.axis
+            );
+            shape[axis] += other[axis];
+        }
+        vec![shape]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("axis={}", self.axis))
+    }
+}
+
+// Backward helper: split `inp` along `axis` into pieces shaped like `likes`
+#[derive(Debug, Clone)]
+pub struct SplitLike {
+    inp: Id,
+    likes: Vec<Id>,
+    outs: Vec<Id>,
+    axis: isize,
+}
+
+impl<D: Floating + 'static> Op<D> for SplitLike {
+    fn name(&self) -> &'static str {
+        "split_like"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let x = ctx.checked_get(&self.inp);
+        let axis = resolve_axis("split_like", self.axis, x.ndim());
+        let sizes: Vec<usize> = self
+            .likes
+            .iter()
+            .map(|id| ctx.checked_get(id).shape()[axis])
+            .collect();
+        let pieces = slice_pieces(x, axis, sizes);
+        for (out, piece) in self.outs.iter().zip(pieces) {
+            ctx.insert(*out, piece);
+        }
+    }
+
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // the likes only provide shapes, so only `inp` gets a gradient
+        let out = g.fresh();
+        g.push(Box::new(Concat::new(out_grads.to_vec(), out, self.axis)));
+        Some(vec![out])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        std::iter::once(self.inp)
+            .chain(self.likes.clone())
+            .collect()
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        self.outs.clone()
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        input_shapes[1..].to_vec()
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("axis={}", self.axis))
+    }
+}
+
+impl Tracer {
+    pub fn split(&self, _sizes: impl Into<Vec<usize>>, _axis: isize) -> Vec<Tracer> {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// one tracer per piece of `a`, cut along `axis` at the given `sizes`.
+    pub fn split(&mut self, a: Tracer, sizes: impl Into<Vec<usize>>, axis: isize) -> Vec<Tracer> {
+        let sizes = sizes.into();
+        let outs: Vec<Id> = sizes.iter().map(|_| self.g.fresh()).collect();
+        self.g
+            .push(Box::new(Split::new(a.id(), outs.clone(), sizes, axis)));
+        outs.into_iter().map(Tracer::new).collect()
+    }
+
+    pub fn concat(&mut self, parts: &[Tracer], axis: isize) -> Tracer {
+        let out = self.g.fresh();
+        let inps = parts.iter().map(Tracer::id).collect();
+        self.emit(Concat::new(inps, out, axis), out)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{Graph, TraceSession, TraceableFn};
+    use ndarray::{arr2, s};
+
+    #[test]
+    fn test_split_then_concat_is_identity() {
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let x = sess.input();
+        let parts = sess.split(x, vec![1, 2], -1);
+        let joined = sess.concat(&parts, 1);
+
+        let traced = TraceableFn {
+            graph: g,
+            inputs: vec![x.id()],
+            outputs: vec![joined.id()],
+        };
+        assert_eq!(traced.output_shapes(&[vec![2, 3]]), vec![vec![2, 3]]);
+
+        let x = arr2(&[[1., 2., 3.], [4., 5., 6.]]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, x);
+
+        let (grad_x,) = traced.grad().eval()(&x);
+        assert_eq!(grad_x, ndarray::ArrayD::<f32>::ones(vec![2, 3]));
+    }
+
+    #[test]
+    fn test_split_grad_with_unused_piece() {
+        // loss = sum(second_piece^2); the first piece gets a zero gradient
+        let mut g = Graph::<f32>::new();
+        let mut sess = TraceSession::new(&mut g);
+        let x = sess.input();
+        let parts = sess.split(x, vec![1, 2], 1);
+        let sq = sess.mul(parts[1], parts[1]);
+        let loss = sess.sum(sq, vec![], false);
+
+        let traced = TraceableFn {
+            graph: g,
+            inputs: vec![x.id()],
+            outputs: vec![loss.id()],
+        };
+
+        let x = arr2(&[[1., 2., 3.], [4., 5., 6.]]).into_dyn();
+        let (grad_x,) = traced.grad().eval()(&x);
+        let mut expected = x.mapv(|v| 2. * v);
+        expected.slice_mut(s![.., 0]).fill(0.);
+        assert_eq!(grad_x, expected);
+    }
+
+    #[test]
+    fn test_index_split_result_inside_trace() {
+        use crate::prelude::*;
+
+        // `parts[1]` is plain Vec indexing, not a tensor index, and
+        // `parts[0]` stays assignable.
+        #[trace]
+        fn second(x: Tensor) -> Tensor {
+            let mut parts = x.split(vec![1, 2], 0);
+            parts[0] = parts[1];
+            parts[0]
+        }
+
+        let traced = trace_fn::<f32>(second);
+        let x = arr2(&[[1., 2.], [3., 4.], [5., 6.]]).into_dyn();
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, x.slice(s![1.., ..]).to_owned().into_dyn());
+    }
+}
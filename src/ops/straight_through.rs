@@ -0,0 +1,128 @@
+use crate::{
+    Floating, TraceSession, Tracer,
+    context::Context,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+    ops::Op,
+};
+
+/// Straight-through estimator: evaluates to `fwd_value`, but differentiates as
+/// if it were `grad_source`.
+///
+/// `x.round().straight_through(x)` rounds on the way forward and passes the
+/// gradient through unchanged, the usual building block for quantizers.
+#[derive(Debug, Clone)]
+pub struct StraightThrough {
+    fwd_value: Id,
+    grad_source: Id,
+    out: Id,
+}
+
+impl StraightThrough {
+    pub fn new(fwd_value: Id, grad_source: Id, out: Id) -> Self {
+        Self {
+            fwd_value,
+            grad_source,
+            out,
+        }
+    }
+}
+
+impl<D: Floating> Op<D> for StraightThrough {
+    fn name(&self) -> &'static str {
+        "straight_through"
+    }
+
+    fn eval(&self, ctx: &mut Context<D>) {
+        let fwd = ctx.checked_get(&self.fwd_value);
+        let src = ctx.checked_get(&self.grad_source);
+        assert_eq!(
+            fwd.shape(),
+            src.shape(),
+            "straight_through: forward value and gradient source must have the same shape"
+        );
+        ctx.insert(self.out, fwd.clone());
+    }
+
+    fn vjp(&self, _g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // `grad_source` is listed first, so it alone receives `og`; nothing
+        // flows back into `fwd_value`.
+        let og = *out_grads.first()?;
+        Some(vec![og])
+    }
+
+    fn inputs(&self) -> Vec<Id> {
+        vec![self.grad_source, self.fwd_value]
+    }
+
+    fn outputs(&self) -> Vec<Id> {
+        vec![self.out]
+    }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        // [grad_source, fwd_value]
+        vec![input_shapes[1].clone()]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+}
+
+impl Tracer {
+    /// `fwd.straight_through(src)` lowers to `sess.straight_through(fwd, src)`.
+    pub fn straight_through(&self, _grad_source: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    #[must_use]
+    pub fn straight_through(&mut self, fwd_value: Tracer, grad_source: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(
+            StraightThrough::new(fwd_value.id(), grad_source.id(), out),
+            out,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr1;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_straight_through_round() {
+        #[trace]
+        fn quantize(x: Tensor) -> Tensor {
+            x.round().straight_through(x) * 3.0
+        }
+
+        #[trace]
+        fn plain_round(x: Tensor) -> Tensor {
+            x.round() * 3.0
+        }
+
+        let x = arr1(&[0.2, 1.7, -2.6]).into_dyn();
+
+        let traced = trace_fn::<f32>(quantize);
+        let (out,) = traced.eval()(&x);
+        assert_eq!(out, arr1(&[0., 6., -9.]).into_dyn());
+        let (grad_x,) = traced.grad().eval()(&x);
+        assert_eq!(grad_x, arr1(&[3., 3., 3.]).into_dyn());
+
+        // without it, round blocks the gradient entirely
+        let (grad_x,): (ndarray::ArrayD<f32>,) = trace_fn::<f32>(plain_round).grad().eval()(&x);
+        assert!(grad_x.iter().all(|&g| g == 0.));
+    }
+}
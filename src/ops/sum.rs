@@ -1,31 +1,66 @@
-use itertools::Itertools as _;
-use ndarray::Axis;
-
 use crate::{
     Floating, Graph, Id, TraceSession, Tracer,
     context::Context,
-    ops::{Op, broadcast::BroadcastLike},
+    identity::IdGenerator,
+    ops::{
+        Op, broadcast::BroadcastLike, normalize_axes, numel, reduce::reduce_axes_with,
+        reduced_shape, reshape::ReshapeLike, unique_axes,
+    },
+    tracing::TensorData,
 };
+use itertools::Itertools as _;
 
 #[derive(Debug, Clone)]
 pub struct Sum {
     inp: Id,
     out: Id,
-    axis: Vec<usize>,
+    axis: Vec<isize>,
     keep_dims: bool,
+    stable: bool,
 }
 
 impl Sum {
-    pub fn new(inp: Id, out: Id, axis: impl Into<Vec<usize>>, keep_dims: bool) -> Self {
-        let mut axis = axis.into();
-        axis.sort_unstable_by(|a, b| b.cmp(a));
+    pub fn new(inp: Id, out: Id, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Self {
         Self {
             inp,
             out,
-            axis,
+            axis: unique_axes("sum", axis),
             keep_dims,
+            stable: false,
         }
     }
+
+    /// like [`Sum::new`], but accumulates in `f64` and rounds back once.
+    pub fn new_stable(inp: Id, out: Id, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Self {
+        Self {
+            stable: true,
+            ..Self::new(inp, out, axis, keep_dims)
+        }
+    }
+}
+
+/// sums `t` over `axis`; see [`reduce_axes`](crate::ops::reduce::reduce_axes)
+/// for the axis conventions.
+pub(crate) fn sum_over<T>(t: TensorData<T>, axis: &[usize], keep_dims: bool) -> TensorData<T>
+where
+    T: Clone + num_traits::Zero,
+{
+    reduce_axes_with(t, axis, keep_dims, |t| t.sum(), |t, a| t.sum_axis(a))
+}
+
+/// [`sum_over`] accumulated in `f64`; the caller rounds back to `D`.
+pub(crate) fn sum_over_stable<D: Floating>(
+    t: &TensorData<D>,
+    axis: &[usize],
+    keep_dims: bool,
+) -> TensorData<f64> {
+    sum_over(t.mapv(|a| a.as_f64()), axis, keep_dims)
+}
+
+/// `attrs` shared by the axis reductions.
+pub(crate) fn reduce_attrs(axis: &[isize], keep_dims: bool, stable: bool) -> String {
+    let stable = if stable { " stable" } else { "" };
+    format!("axis={axis:?} keep_dims={keep_dims}{stable}")
 }
 
 impl<D: Floating> Op<D> for Sum {
@@ -34,24 +69,13 @@ impl<D: Floating> Op<D> for Sum {
     }
 
     fn eval(&self, ctx: &mut Context<D>) {
-        let t_in = ctx.checked_get(&self.inp).clone();
+        let t_in = ctx.checked_get(&self.inp);
+        let axis = normalize_axes("sum", &self.axis, t_in.ndim());
 
-        let result = if self.axis.is_empty() {
-            // If no axes are specified, sum all elements to a scalar.
-            let sum_val = t_in.sum();
-            ndarray::arr0(sum_val).into_dyn()
+        let result = if self.stable {
+            sum_over_stable(t_in, &axis, self.keep_dims).mapv(D::from_f64)
         } else {
-            // else sum along the specified axes.
-            let mut t = t_in;
-            for axis in &self.axis {
-                let a = Axis(*axis);
-                t = if self.keep_dims {
-                    t.sum_axis(a).insert_axis(a)
-                } else {
-                    t.sum_axis(a)
-                }
-            }
-            t
+            sum_over(t_in.clone(), &axis, self.keep_dims)
         };
 
         ctx.insert(self.out, result);
@@ -86,19 +110,69 @@ impl<D: Floating> Op<D> for Sum {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let axis = normalize_axes("sum", &self.axis, input_shapes[0].len());
+        vec![reduced_shape(&input_shapes[0], &axis, self.keep_dims)]
+    }
+
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        numel(&input_shapes[0])
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
+    fn attrs(&self) -> Option<String> {
+        Some(reduce_attrs(&self.axis, self.keep_dims, self.stable))
+    }
+    fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        Some(Box::new(Self {
+            inp: inputs[0],
+            ..self.clone()
+        }))
+    }
 }
 
 impl Tracer {
-    pub fn sum(&self, _axis: impl Into<Vec<usize>>, _keep_dims: bool) -> Tracer {
+    pub fn sum(&self, _axis: impl Into<Vec<isize>>, _keep_dims: bool) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    pub fn sum_stable(&self, _axis: impl Into<Vec<isize>>, _keep_dims: bool) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// `x.sum_all()` is `x.sum(vec![], false)`.
+    pub fn sum_all(&self) -> Tracer {
         panic!("dummy operation - only allowed inside #[trace] function")
     }
 }
 
-impl<D: Floating + 'static> TraceSession<'_, D> {
-    pub fn sum(&mut self, a: Tracer, axis: impl Into<Vec<usize>>, keep_dims: bool) -> Tracer {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    pub fn sum(&mut self, a: Tracer, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Tracer {
         let out = self.g.fresh();
         self.emit(Sum::new(a.id(), out, axis, keep_dims), out)
     }
+
+    /// sum of every element, as a scalar.
+    pub fn sum_all(&mut self, a: Tracer) -> Tracer {
+        self.sum(a, Vec::new(), false)
+    }
+
+    /// `sum` accumulated in `f64`, for long reductions over `f32` data.
+    pub fn sum_stable(
+        &mut self,
+        a: Tracer,
+        axis: impl Into<Vec<isize>>,
+        keep_dims: bool,
+    ) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(Sum::new_stable(a.id(), out, axis, keep_dims), out)
+    }
 }
 
 // Reduce (sum) runtime `inp` down to the runtime shape of `like`.
@@ -183,18 +257,34 @@ impl<D: Floating> Op<D> for ReduceToLike {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        // [inp, like]
+        vec![input_shapes[1].clone()]
+    }
+    fn flops(&self, input_shapes: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        numel(&input_shapes[0])
+    }
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+    fn is_linear(&self) -> bool {
+        true
+    }
+    fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        Some(Box::new(Self::new(inputs[0], inputs[1], self.out)))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ReshapeForBroadcast {
     inp_grad: Id,
     out: Id,
-    axis: Vec<usize>,
+    axis: Vec<isize>,
     keep_dims: bool,
 }
 
 impl ReshapeForBroadcast {
-    pub fn new(inp_grad: Id, out: Id, axis: impl Into<Vec<usize>>, keep_dims: bool) -> Self {
+    pub fn new(inp_grad: Id, out: Id, axis: impl Into<Vec<isize>>, keep_dims: bool) -> Self {
         Self {
             inp_grad,
             out,
@@ -202,6 +292,26 @@ impl ReshapeForBroadcast {
             keep_dims,
         }
     }
+
+    // Re-insert the reduced axes as size-1 dims of the upstream gradient.
+    fn broadcastable_shape(&self, grad_shape: &[usize]) -> Vec<usize> {
+        let mut shape = grad_shape.to_vec();
+        // If keep_dims was true, or if it was a full reduction to a scalar,
+        // the shape is already correct for broadcasting.
+        if self.keep_dims || self.axis.is_empty() {
+            return shape;
+        }
+
+        // the reduced input had one extra dim per reduced axis.
+        let rank = grad_shape.len() + self.axis.len();
+        let mut sorted_axes = normalize_axes("reshape_for_broadcast", &self.axis, rank);
+        sorted_axes.sort_unstable(); // Sort to insert into the correct positions
+
+        for axis in sorted_axes {
+            shape.insert(axis, 1);
+        }
+        shape
+    }
 }
 
 impl<D: Floating> Op<D> for ReshapeForBroadcast {
@@ -214,27 +324,29 @@ impl<D: Floating> Op<D> for ReshapeForBroadcast {
     fn outputs(&self) -> Vec<Id> {
         vec![self.out]
     }
-    fn vjp(&self, _: &mut Graph<D>, _: &[Id]) -> Option<Vec<Id>> {
-        None
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        vec![self.broadcastable_shape(&input_shapes[0])]
+    }
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+    fn is_linear(&self) -> bool {
+        true
+    }
+    fn vjp(&self, g: &mut Graph<D>, out_grads: &[Id]) -> Option<Vec<Id>> {
+        // a pure reshape, undone by reshaping back to the input's shape.
+        let og = *out_grads.first()?;
+        let out = g.fresh();
+        g.push(Box::new(ReshapeLike::new(og, out, self.inp_grad)));
+        Some(vec![out])
     }
 
     fn eval(&self, ctx: &mut Context<D>) {
         let inp_grad_tensor = ctx.checked_get(&self.inp_grad).clone();
-
-        // If keep_dims was true, or if it was a full reduction to a scalar,
-        // the shape is already correct for broadcasting. No op needed.
-        if self.keep_dims || self.axis.is_empty() {
-            ctx.insert(self.out, inp_grad_tensor);
-            return;
-        }
-
-        let mut intermediate_shape = inp_grad_tensor.shape().to_vec();
-        let mut sorted_axes = self.axis.clone();
-        sorted_axes.sort_unstable(); // Sort to insert into the correct positions
-
-        for &axis in &sorted_axes {
-            intermediate_shape.insert(axis, 1);
-        }
+        let intermediate_shape = self.broadcastable_shape(inp_grad_tensor.shape());
 
         let reshaped_tensor = inp_grad_tensor
             .to_shape(intermediate_shape)
@@ -243,4 +355,8 @@ impl<D: Floating> Op<D> for ReshapeForBroadcast {
             .into_dyn();
         ctx.insert(self.out, reshaped_tensor);
     }
+
+    fn attrs(&self) -> Option<String> {
+        Some(reduce_attrs(&self.axis, self.keep_dims, false))
+    }
 }
@@ -1,4 +1,9 @@
-use crate::{Tracer, context::Context, graph::Graph, identity::Id};
+use crate::{
+    Tracer,
+    context::Context,
+    graph::Graph,
+    identity::{Id, IdGenerator},
+};
 
 use crate::{Floating, ops::Op};
 
@@ -27,6 +32,27 @@ impl<D: Floating + 'static> Op<D> for TransposeDefault {
         vec![self.out]
     }
 
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let mut shape = input_shapes[0].clone();
+        let rank = shape.len();
+        if rank > 1 {
+            shape.swap(rank - 1, rank - 2);
+        }
+        vec![shape]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
     fn eval(&self, ctx: &mut Context<D>) {
         let mut t = ctx.checked_get(&self.inp).clone();
         let shape = t.shape();
@@ -44,6 +70,12 @@ impl<D: Floating + 'static> Op<D> for TransposeDefault {
         g.push(transpose);
         Some(vec![out])
     }
+    fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        Some(Box::new(Self {
+            inp: inputs[0],
+            ..self.clone()
+        }))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -73,6 +105,24 @@ impl<D: Floating + 'static> Op<D> for Transpose {
         vec![self.out]
     }
 
+    fn shape_rule(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let mut shape = input_shapes[0].clone();
+        shape.swap(self.a1, self.a2);
+        vec![shape]
+    }
+
+    fn flops(&self, _: &[Vec<usize>], _: &[Vec<usize>]) -> u64 {
+        0
+    }
+
+    fn retype_f64(&self) -> Box<dyn Op<f64>> {
+        Box::new(self.clone())
+    }
+
+    fn is_linear(&self) -> bool {
+        true
+    }
+
     fn eval(&self, ctx: &mut Context<D>) {
         let mut t = ctx.checked_get(&self.inp).clone();
         t.swap_axes(self.a1, self.a2);
@@ -85,9 +135,21 @@ impl<D: Floating + 'static> Op<D> for Transpose {
         g.push(Box::new(Transpose::new(og, out, self.a1, self.a2)));
         Some(vec![out])
     }
+
+    fn attrs(&self) -> Option<String> {
+        Some(format!("axes=({}, {})", self.a1, self.a2))
+    }
+    fn with_inputs(&self, inputs: &[Id]) -> Option<Box<dyn Op<D>>> {
+        Some(Box::new(Self {
+            inp: inputs[0],
+            ..self.clone()
+        }))
+    }
 }
 
-impl<D: Floating + 'static> crate::tracing::session::TraceSession<'_, D> {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>>
+    crate::tracing::session::TraceSession<'_, D, G>
+{
     #[must_use]
     pub fn t(&mut self, a: Tracer) -> Tracer {
         let out = self.g.fresh();
@@ -111,7 +173,7 @@ impl Tracer {
 }
 #[cfg(test)]
 mod tests {
-    use ndarray::arr2;
+    use ndarray::{Array, arr0, arr1, arr2};
 
     use crate::prelude::*;
 
@@ -129,4 +191,50 @@ mod tests {
         let expected = x.t().into_owned().into_dyn();
         assert_eq!(out, expected);
     }
+
+    #[trace]
+    fn weighted_t(x: Tensor, w: Tensor) -> Tensor {
+        (x.t() * w).sum(vec![], false)
+    }
+
+    #[test]
+    fn test_transpose_default_grad_rank0() {
+        let traced = trace_fn::<f32>(weighted_t);
+        let x = arr0(2.).into_dyn();
+        let w = arr0(3.).into_dyn();
+
+        let (gx, gw) = traced.grad().eval()((&x, &w));
+        assert_eq!(gx, arr0(3.).into_dyn());
+        assert_eq!(gw, arr0(2.).into_dyn());
+    }
+
+    #[test]
+    fn test_transpose_default_grad_rank1() {
+        // a vector is its own transpose, so the gradient is w with x's shape.
+        let traced = trace_fn::<f32>(weighted_t);
+        let x = arr1(&[1., 2., 3.]).into_dyn();
+        let w = arr1(&[4., 5., 6.]).into_dyn();
+
+        let (gx, gw) = traced.grad().eval()((&x, &w));
+        assert_eq!(gx, w);
+        assert_eq!(gw, x);
+    }
+
+    #[test]
+    fn test_transpose_default_grad_rank3() {
+        // only the last two axes swap, so d/dx sum(x.t() * w) = w.t() over those axes.
+        let traced = trace_fn::<f32>(weighted_t);
+        let x = Array::from_shape_fn((2, 3, 4), |(i, j, k)| (i * 12 + j * 4 + k) as f32).into_dyn();
+        let w = Array::from_shape_fn((2, 4, 3), |(i, j, k)| (i * 12 + j * 3 + k) as f32 * 0.5)
+            .into_dyn();
+
+        let (gx, gw) = traced.grad().eval()((&x, &w));
+        let mut expected_gx = w.clone();
+        expected_gx.swap_axes(1, 2);
+        let mut expected_gw = x.clone();
+        expected_gw.swap_axes(1, 2);
+        assert_eq!(gx.shape(), &[2, 3, 4]);
+        assert_eq!(gx, expected_gx);
+        assert_eq!(gw, expected_gw);
+    }
 }
@@ -0,0 +1,105 @@
+use ndarray::Zip;
+
+use crate::{
+    Floating, Graph, Id, TraceSession, Tracer,
+    identity::IdGenerator,
+    ops::{Const, Mul, Sub, broadcast_shapes, compare::Greater, sum::ReduceToLike},
+    primitive_binary_op,
+    tracing::TensorData,
+};
+
+// `x` where `x > 0`, else `other`: relu is `x.where_positive(zeros)`, leaky relu
+// is `x.where_positive(x * slope)`. the condition is piecewise constant, so only
+// the selected branch receives gradient.
+primitive_binary_op!(
+    WherePositive,
+    disp: "where_positive",
+    fwd: |x: &TensorData<D>, other: &TensorData<D>| {
+        let shape = broadcast_shapes(x.shape(), other.shape()).unwrap_or_else(|| {
+            panic!(
+                "where_positive: cannot broadcast {:?} with {:?}",
+                x.shape(),
+                other.shape()
+            )
+        });
+        let (x, other) = (x.broadcast(&*shape).unwrap(), other.broadcast(&*shape).unwrap());
+        Zip::from(&x)
+            .and(&other)
+            .map_collect(|&a, &b| if a > D::zero() { a } else { b })
+    },
+    vjp: |this: &WherePositive, g: &mut Graph<D>, og: Id| {
+        let zero = g.fresh();
+        g.push(Box::new(Const::new(D::zero(), zero)));
+        let mask = g.fresh();
+        g.push(Box::new(Greater::new(this.lhs, zero, mask)));
+
+        let grad_lhs = {
+            let prod = g.fresh();
+            g.push(Box::new(Mul::new(og, mask, prod)));
+            let out = g.fresh();
+            g.push(Box::new(ReduceToLike::new(prod, this.lhs, out)));
+            out
+        };
+        let grad_rhs = {
+            let one = g.fresh();
+            g.push(Box::new(Const::new(D::one(), one)));
+            let complement = g.fresh();
+            g.push(Box::new(Sub::new(one, mask, complement)));
+            let prod = g.fresh();
+            g.push(Box::new(Mul::new(og, complement, prod)));
+            let out = g.fresh();
+            g.push(Box::new(ReduceToLike::new(prod, this.rhs, out)));
+            out
+        };
+        vec![grad_lhs, grad_rhs]
+    }
+);
+
+impl Tracer {
+    pub fn where_positive(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+}
+
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceSession<'_, D, G> {
+    /// `x` where `x > 0`, else `other`, broadcasting like the arithmetic ops.
+    #[must_use]
+    pub fn where_positive(&mut self, x: Tracer, other: Tracer) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(WherePositive::new(x.id(), other.id(), out), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::arr1;
+
+    use crate::prelude::*;
+
+    #[trace]
+    fn gated(x: Tensor, other: Tensor) -> Tensor {
+        (x.where_positive(other) * x).sum(vec![], false)
+    }
+
+    #[trace]
+    fn composed(x: Tensor, other: Tensor) -> Tensor {
+        let mask = x.gt_mask(x.zeros_like());
+        ((mask * x + (1.0 - mask) * other) * x).sum(vec![], false)
+    }
+
+    #[test]
+    fn test_where_positive_matches_composed_select() {
+        let x = arr1(&[1.5, -2., 0., 3.]).into_dyn();
+        let other = arr1(&[-0.5, 4., 2., -1.]).into_dyn();
+
+        let (out,) = trace_fn::<f32>(gated).eval()((&x, &other));
+        let (expected,) = trace_fn::<f32>(composed).eval()((&x, &other));
+        assert_eq!(out, expected);
+
+        let (gx, gother) = trace_fn::<f32>(gated).grad().eval()((&x, &other));
+        let (ex, eother) = trace_fn::<f32>(composed).grad().eval()((&x, &other));
+        assert_eq!(gx, ex);
+        assert_eq!(gother, eother);
+        assert_eq!(gother, arr1(&[0., -2., 0., 0.]).into_dyn());
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    Floating,
+    context::Context,
+    identity::{Id, IdGenerator},
+    ops::Op,
+    tracing::{
+        TensorData,
+        function::{EvalArgs, EvalOutputs, TraceableFn},
+    },
+};
+
+/// A [`TraceableFn`] lowered to a flat execution plan.
+///
+/// Built by [`TraceableFn::compile`]: nodes that cannot reach an output are
+/// dropped, every tensor lives in a preallocated slot indexed by its id, and
+/// each step knows which intermediates it consumes for the last time so they
+/// are freed straight away. Repeated small calls skip the per-call graph walk
+/// and hash lookups of [`TraceableFn::eval`].
+#[derive(Debug, Clone)]
+pub struct CompiledFn<D: Floating> {
+    steps: Vec<Step<D>>,
+    inputs: Vec<Id>,
+    outputs: Vec<Id>,
+    slots: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Step<D> {
+    op: Rc<dyn Op<D>>,
+    /// inputs whose last reader is this step.
+    release: Vec<Id>,
+}
+
+impl<D: Floating + 'static> CompiledFn<D> {
+    pub(crate) fn new<G: IdGenerator<Id = Id>>(f: &TraceableFn<D, G>) -> Self {
+        let nodes = &f.graph.nodes;
+
+        // graph nodes are kept in emission order, which is already a topological
+        // order, so the plan only has to drop nodes no output depends on.
+        let mut needed: HashSet<Id> = f.outputs.iter().copied().collect();
+        let mut keep = vec![false; nodes.len()];
+        for (i, node) in nodes.iter().enumerate().rev() {
+            if node.outputs().iter().any(|out| needed.contains(out)) {
+                keep[i] = true;
+                needed.extend(node.inputs());
+            }
+        }
+
+        let kept: Vec<&Rc<dyn Op<D>>> = nodes
+            .iter()
+            .zip(&keep)
+            .filter_map(|(node, &k)| k.then_some(node))
+            .collect();
+
+        let mut last_use: HashMap<Id, usize> = HashMap::new();
+        for (step, node) in kept.iter().enumerate() {
+            for inp in node.inputs() {
+                last_use.insert(inp, step);
+            }
+        }
+        let mut release = vec![vec![]; kept.len()];
+        for (id, step) in last_use {
+            if !f.outputs.contains(&id) {
+                release[step].push(id);
+            }
+        }
+
+        let slots = f
+            .inputs
+            .iter()
+            .copied()
+            .chain(kept.iter().flat_map(|node| node.outputs()))
+            .map(|id| id.as_usize() + 1)
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            steps: kept
+                .into_iter()
+                .zip(release)
+                .map(|(op, release)| Step {
+                    op: op.clone(),
+                    release,
+                })
+                .collect(),
+            inputs: f.inputs.clone(),
+            outputs: f.outputs.clone(),
+            slots,
+        }
+    }
+
+    pub(crate) fn run_packed(&self, packed: Vec<TensorData<D>>) -> Vec<TensorData<D>> {
+        assert_eq!(
+            packed.len(),
+            self.inputs.len(),
+            "compiled eval: expected {} inputs, got {}",
+            self.inputs.len(),
+            packed.len()
+        );
+        let mut ctx = Context::<D>::with_slots(self.slots);
+        for (id, val) in self.inputs.iter().zip(packed) {
+            ctx.insert(*id, val);
+        }
+
+        for step in &self.steps {
+            step.op.eval(&mut ctx);
+            for id in &step.release {
+                ctx.take(id);
+            }
+        }
+
+        self.outputs
+            .iter()
+            .map(|id| ctx.checked_get(id).clone())
+            .collect()
+    }
+
+    pub fn eval<T, O>(&self) -> impl Fn(T) -> O
+    where
+        T: EvalArgs<D>,
+        O: EvalOutputs<D>,
+    {
+        move |args: T| O::from_vec(self.run_packed(args.pack()))
+    }
+
+    /// number of ops left in the plan after pruning.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
@@ -1,46 +1,137 @@
-use std::collections::HashMap;
+use core::fmt::{Debug, Formatter, Result as FmtResult};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+    ops::Range,
+    rc::Rc,
+};
 
 use crate::{
     Floating,
     context::Context,
-    graph::Graph,
-    identity::Id,
-    ops::{Add, Const, Sum},
-    tracing::TensorData,
+    graph::{Graph, ssa_line},
+    identity::{Id, IdGenerator, generators::FreeList},
+    ops::{
+        Add, Const, ConstTensor, Input, Mul, Op, Sum, ZerosLike,
+        broadcast::BroadcastLike,
+        remat::Remat,
+        scalar_minmax::{MaximumScalar, MinimumScalar},
+    },
+    tracing::{TensorData, compiled::CompiledFn},
 };
 
-#[derive(Debug, Clone)]
-pub struct TraceableFn<D: Floating> {
-    pub graph: Graph<D>,
+#[derive(Clone)]
+pub struct TraceableFn<D: Floating, G: IdGenerator = FreeList> {
+    pub graph: Graph<D, G>,
     pub inputs: Vec<Id>,
     pub outputs: Vec<Id>,
 }
 
+/// Only counts, so `dbg!` stays readable; print `graph` (or `graph.to_ssa()`)
+/// for the full node list.
+impl<D: Floating, G: IdGenerator> Debug for TraceableFn<D, G> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("TraceableFn")
+            .field("ops", &self.graph.nodes.len())
+            .field("inputs", &self.inputs.len())
+            .field("outputs", &self.outputs.len())
+            .finish()
+    }
+}
+
+/// What the gradient of an input that no output depends on should be.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Unreached {
+    /// a scalar zero, which broadcasts against anything.
+    #[default]
+    Zero,
+    /// panic while building the gradient, as it usually means a bug in the traced fn.
+    Error,
+}
+
+/// Options for [`TraceableFn::grad_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GradOptions {
+    pub unreached: Unreached,
+}
+
 pub trait EvalArgs<D: Floating> {
     fn pack(self) -> Vec<TensorData<D>>;
 }
 
-impl<D: Floating + 'static> TraceableFn<D> {
+impl<D: Floating + 'static, G: IdGenerator<Id = Id>> TraceableFn<D, G> {
     fn run<T: EvalArgs<D>, O: EvalOutputs<D>>(&self, args: T) -> O {
-        let packed = args.pack();
+        O::from_vec(self.run_packed(args.pack()))
+    }
+
+    pub(crate) fn run_packed(&self, packed: Vec<TensorData<D>>) -> Vec<TensorData<D>> {
+        let ctx = self.forward(packed, None);
+        self.outputs
+            .iter()
+            .map(|id| ctx.checked_get(id).clone())
+            .collect()
+    }
+
+    /// runs the nodes in order, stopping early once `until` has been produced.
+    fn forward(&self, packed: Vec<TensorData<D>>, until: Option<Id>) -> Context<D> {
         let mut ctx = Context::<D>::new();
 
-        for (id, val) in self.inputs.iter().zip(packed.into_iter()) {
+        for (id, val) in self.inputs.iter().zip(packed) {
             ctx.insert(*id, val);
         }
+        if until.is_some_and(|id| self.inputs.contains(&id)) {
+            return ctx;
+        }
 
         for op in &self.graph.nodes {
             op.eval(&mut ctx);
+            if until.is_some_and(|id| op.outputs().contains(&id)) {
+                break;
+            }
         }
+        ctx
+    }
 
-        O::from_vec(
-            self.outputs
-                .iter()
-                .map(|id| ctx.checked_get(id).clone())
-                .collect(),
-        )
+    fn run_batched<T: EvalArgs<D>, O: EvalOutputs<D>>(&self, args: T) -> O {
+        let packed = args.pack();
+        let batch = packed
+            .first()
+            .and_then(|t| t.shape().first().copied())
+            .expect("eval_batched: inputs need a leading batch dim");
+        for t in &packed {
+            assert!(
+                t.shape().first() == Some(&batch),
+                "eval_batched: all inputs should share the leading batch dim {batch}, got shape {:?}",
+                t.shape()
+            );
+        }
+
+        let per_example: Vec<Vec<TensorData<D>>> = (0..batch)
+            .map(|b| {
+                let slices = packed
+                    .iter()
+                    .map(|t| t.index_axis(Axis(0), b).to_owned())
+                    .collect();
+                self.run_packed(slices)
+            })
+            .collect();
+
+        let stacked = (0..self.outputs.len())
+            .map(|i| {
+                let views: Vec<_> = per_example.iter().map(|outs| outs[i].view()).collect();
+                ndarray::stack(Axis(0), &views)
+                    .expect("eval_batched: per-example outputs should share a shape")
+            })
+            .collect();
+        O::from_vec(stacked)
     }
 
+    /// Evaluate the traced function on concrete inputs.
+    ///
+    /// Shapes are only known at runtime, so the same function can be called
+    /// with a different batch size each time. Ops that store a shape from
+    /// trace time (`reshape`, `broadcast`) are the exception and only accept
+    /// inputs matching it; `reshape_like` follows the runtime shape instead.
     pub fn eval<T, O>(&self) -> impl Fn(T) -> O
     where
         T: EvalArgs<D>,
@@ -49,16 +140,470 @@ impl<D: Floating + 'static> TraceableFn<D> {
         move |args: T| self.run(args)
     }
 
+    /// Like [`TraceableFn::eval`] for a function with a single rank-0 output,
+    /// such as a loss, returning the value as a plain `D`.
+    pub fn eval_scalar<T: EvalArgs<D>>(&self) -> impl Fn(T) -> D {
+        assert_eq!(
+            self.outputs.len(),
+            1,
+            "eval_scalar: expected a single output, got {}",
+            self.outputs.len()
+        );
+        move |args: T| {
+            let out = self.run_packed(args.pack()).remove(0);
+            assert!(
+                out.ndim() == 0,
+                "eval_scalar: expected a rank-0 output, got shape {:?}",
+                out.shape()
+            );
+            out.into_iter()
+                .next()
+                .expect("a rank-0 tensor holds exactly one element")
+        }
+    }
+
+    /// Value of an arbitrary intermediate `target`, for debugging.
+    ///
+    /// Runs the graph only up to the node producing `target` and returns that
+    /// tensor instead of the function's outputs.
+    pub fn eval_at<T: EvalArgs<D>>(&self, args: T, target: Id) -> TensorData<D> {
+        let produced = self.inputs.contains(&target)
+            || self
+                .graph
+                .nodes
+                .iter()
+                .any(|op| op.outputs().contains(&target));
+        assert!(
+            produced,
+            "eval_at: no node in the graph produces {target:?}"
+        );
+        self.forward(args.pack(), Some(target))
+            .checked_get(&target)
+            .clone()
+    }
+
+    /// Evaluate over a leading batch dim by looping over examples.
+    ///
+    /// Every input is sliced along axis 0, the graph is run once per example,
+    /// and each output is stacked back along a new leading axis.
+    pub fn eval_batched<T, O>(&self) -> impl Fn(T) -> O
+    where
+        T: EvalArgs<D>,
+        O: EvalOutputs<D>,
+    {
+        move |args: T| self.run_batched(args)
+    }
+
+    /// Lower to a [`CompiledFn`] with a precomputed, pruned execution plan,
+    /// for functions evaluated many times.
+    pub fn compile(&self) -> CompiledFn<D> {
+        CompiledFn::new(self)
+    }
+
+    /// Ids of the function's inputs, in argument order.
+    pub fn input_ids(&self) -> &[Id] {
+        &self.inputs
+    }
+
+    /// Ids of the function's outputs, in return order.
+    pub fn output_ids(&self) -> &[Id] {
+        &self.outputs
+    }
+
+    /// Every op in evaluation order as `(name, inputs, outputs)`, for tooling
+    /// that wants the graph structure without the `Op` objects themselves.
+    pub fn ops(&self) -> impl Iterator<Item = (&str, Vec<Id>, Vec<Id>)> {
+        self.graph
+            .nodes
+            .iter()
+            .map(|op| (op.name(), op.inputs(), op.outputs()))
+    }
+
+    /// Number of nodes emitted by vjp rules, i.e. the backward pass proper
+    /// without the seed and gradient-accumulation adds. Zero for a function
+    /// that has not been differentiated.
+    pub fn count_backward_nodes(&self) -> usize {
+        self.graph.grad_sources.len()
+    }
+
+    /// The graph as SSA text with each line tagged `[fwd]` or `[bwd]`,
+    /// depending on whether the node existed before the latest reverse pass.
+    /// A function that was never differentiated is all `[fwd]`.
+    pub fn print_annotated(&self) -> String {
+        let forward_len = self.graph.forward_len.unwrap_or(self.graph.nodes.len());
+        self.graph
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| {
+                let tag = if i < forward_len { "[fwd]" } else { "[bwd]" };
+                format!("{tag} {}\n", ssa_line(node.as_ref()))
+            })
+            .collect()
+    }
+
+    /// Hash of the graph's structure, for keying a cache of compiled functions.
+    ///
+    /// Covers every node's name, wiring and [`Op::attrs`](crate::ops::Op::attrs)
+    /// (e.g. constant values, axes, shapes) in evaluation order, with ids
+    /// renumbered by first appearance, so separately traced copies of a
+    /// function hash equal. Ops report all of their parameters through
+    /// `attrs`, so graphs that differ only in a parameter hash differently.
+    pub fn structural_hash(&self) -> u64 {
+        let mut canonical: HashMap<Id, usize> = HashMap::new();
+        let mut index = |id: Id| {
+            let next = canonical.len();
+            *canonical.entry(id).or_insert(next)
+        };
+
+        let mut hasher = DefaultHasher::new();
+        for &id in &self.inputs {
+            index(id).hash(&mut hasher);
+        }
+        for node in &self.graph.nodes {
+            node.name().hash(&mut hasher);
+            for id in node.inputs() {
+                index(id).hash(&mut hasher);
+            }
+            for id in node.outputs() {
+                index(id).hash(&mut hasher);
+            }
+            node.attrs().hash(&mut hasher);
+        }
+        for &id in &self.outputs {
+            index(id).hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    /// Shapes of every output given the shape of each input,
+    /// computed from the ops' shape rules without allocating any tensors.
+    pub fn output_shapes(&self, input_shapes: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        let shapes = self.graph.infer_shapes(&self.inputs, input_shapes);
+        self.outputs.iter().map(|id| shapes[id].clone()).collect()
+    }
+
+    /// Rough floating point operation count for one forward pass,
+    /// given the shape of each input.
+    pub fn flops(&self, input_shapes: &[Vec<usize>]) -> u64 {
+        let shapes = self.graph.infer_shapes(&self.inputs, input_shapes);
+        let shapes_of =
+            |ids: Vec<Id>| -> Vec<Vec<usize>> { ids.iter().map(|id| shapes[id].clone()).collect() };
+        self.graph
+            .nodes
+            .iter()
+            .map(|node| node.flops(&shapes_of(node.inputs()), &shapes_of(node.outputs())))
+            .sum()
+    }
+
+    /// Peak number of tensor elements alive at once during a forward pass,
+    /// given the shape of each input.
+    ///
+    /// Walks the nodes in order, freeing each intermediate right after its
+    /// last reader as [`CompiledFn`] does. Inputs and outputs count for the
+    /// whole run. Multiply by `size_of::<D>()` for bytes.
+    pub fn peak_memory(&self, input_shapes: &[Vec<usize>]) -> usize {
+        let shapes = self.graph.infer_shapes(&self.inputs, input_shapes);
+        let size = |id: &Id| shapes[id].iter().product::<usize>();
+
+        let mut last_use: HashMap<Id, usize> = HashMap::new();
+        for (step, node) in self.graph.nodes.iter().enumerate() {
+            for inp in node.inputs() {
+                last_use.insert(inp, step);
+            }
+        }
+
+        let mut live: HashSet<Id> = self.inputs.iter().copied().collect();
+        let mut current: usize = live.iter().map(size).sum();
+        let mut peak = current;
+        for (step, node) in self.graph.nodes.iter().enumerate() {
+            for out in node.outputs() {
+                if live.insert(out) {
+                    current += size(&out);
+                }
+            }
+            peak = peak.max(current);
+
+            // intermediates read here for the last time, or never read at all.
+            for id in node.inputs().into_iter().chain(node.outputs()) {
+                let dead = !self.inputs.contains(&id)
+                    && !self.outputs.contains(&id)
+                    && last_use.get(&id).is_none_or(|&last| last <= step);
+                if dead && live.remove(&id) {
+                    current -= size(&id);
+                }
+            }
+        }
+        peak
+    }
+
+    /// Rebuild this function at `f64` precision without re-tracing.
+    ///
+    /// Useful as a high-precision reference when checking `f32` gradients.
+    pub fn to_f64(&self) -> TraceableFn<f64, G>
+    where
+        G: Clone,
+    {
+        TraceableFn {
+            graph: self.graph.to_f64(),
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+        }
+    }
+}
+
+/// Transformations that emit new ops. vjps build into a `Graph<D>`, so these
+/// need a graph on the default id generator.
+impl<D: Floating + 'static> TraceableFn<D> {
+    /// Gradient of the function summed over several microbatches.
+    ///
+    /// Builds the gradient graph once, evaluates it per batch and adds the
+    /// results elementwise into one buffer per input, as if all batches had
+    /// been one larger batch of a function that sums over it.
+    pub fn grad_accumulate<T: EvalArgs<D>>(
+        &self,
+        batches: impl IntoIterator<Item = T>,
+    ) -> Vec<TensorData<D>> {
+        let grad = self.grad();
+        let mut batches = batches.into_iter();
+        let first = batches
+            .next()
+            .expect("grad_accumulate: expected at least one batch");
+        let mut total = grad.run_packed(first.pack());
+        for batch in batches {
+            for (acc, g) in total.iter_mut().zip(grad.run_packed(batch.pack())) {
+                acc.zip_mut_with(&g, |a, &b| *a = *a + b);
+            }
+        }
+        total
+    }
+
+    /// Fix some inputs to concrete values, like a partial application.
+    ///
+    /// Each `(position, value)` pair turns the input at `position` into a
+    /// constant holding `value`; the returned function takes only the
+    /// remaining inputs, in their original order.
+    pub fn bind_inputs(&self, bound: &[(usize, TensorData<D>)]) -> Self {
+        let mut values: HashMap<Id, TensorData<D>> = HashMap::new();
+        for (pos, value) in bound {
+            let id = *self.inputs.get(*pos).unwrap_or_else(|| {
+                panic!(
+                    "bind_inputs: position {pos} is out of range for {} inputs",
+                    self.inputs.len()
+                )
+            });
+            assert!(
+                values.insert(id, value.clone()).is_none(),
+                "bind_inputs: input {pos} is bound more than once"
+            );
+        }
+
+        let mut graph = self.graph.clone();
+        graph.params.retain(|id| !values.contains_key(id));
+        let inputs = self
+            .inputs
+            .iter()
+            .copied()
+            .filter(|id| !values.contains_key(id))
+            .collect();
+
+        for node in graph.nodes.iter_mut().filter(|node| node.is_input()) {
+            let out = node.outputs()[0];
+            if let Some(value) = values.remove(&out) {
+                *node = Rc::new(ConstTensor::new(value, out));
+            }
+        }
+
+        Self {
+            graph,
+            inputs,
+            outputs: self.outputs.clone(),
+        }
+    }
+
+    /// Evaluates every node that depends only on constants once, replacing
+    /// it with a `const_tensor` holding the result, then drops nodes no
+    /// output needs any more. Inputs are kept even when unused, so the
+    /// calling convention does not change.
+    pub fn fold_constants(&self) -> Self {
+        let mut ctx = Context::<D>::new();
+        let mut known: HashSet<Id> = HashSet::new();
+        let mut nodes: Vec<Rc<dyn Op<D>>> = Vec::with_capacity(self.graph.nodes.len());
+        for node in &self.graph.nodes {
+            let constant = !node.is_input() && node.inputs().iter().all(|id| known.contains(id));
+            if !constant {
+                nodes.push(node.clone());
+                continue;
+            }
+            node.eval(&mut ctx);
+            known.extend(node.outputs());
+            if node.const_value().is_some() {
+                nodes.push(node.clone());
+            } else {
+                for out in node.outputs() {
+                    let value = ctx.checked_get(&out).clone();
+                    nodes.push(Rc::new(ConstTensor::new(value, out)));
+                }
+            }
+        }
+
+        let mut needed: HashSet<Id> = self.outputs.iter().copied().collect();
+        let mut keep = vec![false; nodes.len()];
+        for (i, node) in nodes.iter().enumerate().rev() {
+            if node.is_input() || node.outputs().iter().any(|out| needed.contains(out)) {
+                keep[i] = true;
+                needed.extend(node.inputs());
+            }
+        }
+        let mut position: HashMap<Id, usize> = HashMap::new();
+        let mut kept = Vec::new();
+        for (node, k) in nodes.into_iter().zip(keep) {
+            if k {
+                position.insert(node.outputs()[0], kept.len());
+                kept.push(node);
+            }
+        }
+        // a folded node was replaced rather than kept, so it maps to nothing.
+        let new_index: Vec<Option<usize>> = self
+            .graph
+            .nodes
+            .iter()
+            .map(|node| {
+                let first = node.outputs()[0];
+                let replaced = known.contains(&first) && node.const_value().is_none();
+                if replaced {
+                    None
+                } else {
+                    position.get(&first).copied()
+                }
+            })
+            .collect();
+
+        let mut graph = self.graph.clone();
+        graph.set_nodes(kept, &new_index);
+        Self {
+            graph,
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+        }
+    }
+
+    /// [`Graph::deduplicate_constants`] with the function's input and output
+    /// ids remapped, so an output that was a merged-away constant now reads
+    /// the surviving one.
+    pub fn deduplicate_constants(&self) -> Self {
+        let mut graph = self.graph.clone();
+        let merged = graph.deduplicate_constants();
+        let remap = |ids: &[Id]| -> Vec<Id> {
+            ids.iter()
+                .map(|id| merged.get(id).copied().unwrap_or(*id))
+                .collect()
+        };
+        Self {
+            inputs: remap(&self.inputs),
+            outputs: remap(&self.outputs),
+            graph,
+        }
+    }
+
+    /// [`TraceableFn::bind_inputs`] followed by [`TraceableFn::fold_constants`],
+    /// e.g. to serve a model with a frozen sub-model collapsed into constants.
+    pub fn specialize(&self, bound: &[(usize, TensorData<D>)]) -> Self {
+        self.bind_inputs(bound).fold_constants()
+    }
+
+    /// The inverse of [`TraceableFn::bind_inputs`]: every `const` and
+    /// `const_tensor` node becomes a graph input, so values baked into the
+    /// graph can be perturbed or differentiated against.
+    ///
+    /// The lifted inputs come after the original ones, in graph order, and
+    /// the caller now has to supply their values.
+    pub fn lift_constants(&self) -> Self {
+        let mut graph = self.graph.clone();
+        let mut inputs = self.inputs.clone();
+        for node in graph.nodes.iter_mut() {
+            if node.const_value().is_some() {
+                let out = node.outputs()[0];
+                *node = Rc::new(Input::new(out));
+                inputs.push(out);
+            }
+        }
+
+        Self {
+            graph,
+            inputs,
+            outputs: self.outputs.clone(),
+        }
+    }
+
+    /// Like [`TraceableFn::grad`], with [`GradOptions`] controlling the edge cases.
+    pub fn grad_with(&self, opts: GradOptions) -> Self {
+        self.grad_wrt(&self.inputs, &self.outputs, opts.unreached)
+    }
+
     pub fn grad(&self) -> Self {
-        let mut g = self.graph.clone();
+        self.grad_wrt(&self.inputs, &self.outputs, Unreached::Zero)
+    }
 
-        let mut final_output_id = *self
+    /// Like [`TraceableFn::grad`], but differentiates only output `index`
+    /// (summed to a scalar); every other output contributes nothing.
+    pub fn grad_output(&self, index: usize) -> Self {
+        let output = *self.outputs.get(index).unwrap_or_else(|| {
+            panic!(
+                "grad_output: index {index} is out of range for {} outputs",
+                self.outputs.len()
+            )
+        });
+        self.grad_wrt(&self.inputs, &[output], Unreached::Zero)
+    }
+
+    /// Like [`TraceableFn::grad`], but only returns gradients for inputs
+    /// tagged via `TraceSession::parameter`, in input order.
+    pub fn grad_params(&self) -> Self {
+        let params: Vec<Id> = self
+            .inputs
+            .iter()
+            .copied()
+            .filter(|&i| self.graph.is_param(i))
+            .collect();
+        self.grad_wrt(&params, &self.outputs, Unreached::Zero)
+    }
+
+    /// Clamp every output elementwise to `[-c, c]`, e.g. `f.grad().clip_grad_value(c)`
+    /// to clip each gradient by value rather than by norm.
+    pub fn clip_grad_value(&self, c: D) -> Self {
+        assert!(c >= D::zero(), "clip_grad_value: c should be non-negative");
+        let mut graph = self.graph.clone();
+        let outputs = self
             .outputs
+            .iter()
+            .map(|&out| {
+                let floored = graph.fresh();
+                graph.push(Box::new(MaximumScalar::new(out, floored, -c)));
+                let clipped = graph.fresh();
+                graph.push(Box::new(MinimumScalar::new(floored, clipped, c)));
+                clipped
+            })
+            .collect();
+
+        Self {
+            graph,
+            inputs: self.inputs.clone(),
+            outputs,
+        }
+    }
+
+    /// reverse pass from the sum of `roots` back to each id in `wrt`.
+    fn grad_wrt(&self, wrt: &[Id], roots: &[Id], unreached: Unreached) -> Self {
+        // cheap: the forward nodes are shared with `self`, only appended to.
+        let mut g = self.graph.clone();
+
+        let mut final_output_id = *roots
             .first()
             .expect("Cannot differentiate a function with no outputs");
 
-        if self.outputs.len() > 1 {
-            for &output_id in self.outputs.iter().skip(1) {
+        if roots.len() > 1 {
+            for &output_id in roots.iter().skip(1) {
                 let new_sum_id = g.fresh();
                 g.push(Box::new(Add::new(final_output_id, output_id, new_sum_id)));
                 final_output_id = new_sum_id;
@@ -78,9 +623,167 @@ impl<D: Floating + 'static> TraceableFn<D> {
         g.push(Const::boxed(D::one(), seed));
         gradients.insert(scalar_output_id, seed);
 
+        self.backprop(g, wrt, gradients, unreached)
+    }
+
+    /// Vector-jacobian product with an explicit cotangent per output.
+    ///
+    /// `seeds[i]` is the gradient flowing into output `i` and must match its
+    /// shape; the result holds one gradient per input. Seeding every output
+    /// with ones reproduces [`TraceableFn::grad`].
+    pub fn grad_with_seeds(&self, seeds: Vec<TensorData<D>>) -> Self {
+        assert_eq!(
+            seeds.len(),
+            self.outputs.len(),
+            "grad_with_seeds: expected {} seeds, got {}",
+            self.outputs.len(),
+            seeds.len()
+        );
+        let mut g = self.graph.clone();
+        let mut gradients: HashMap<Id, Id> = HashMap::new();
+        for (&out, seed) in self.outputs.iter().zip(seeds) {
+            let id = g.fresh();
+            g.push(Box::new(ConstTensor::new(seed, id)));
+            // an id returned twice receives both seeds.
+            let id = match gradients.get(&out) {
+                Some(&existing) => {
+                    let sum = g.fresh();
+                    g.push(Box::new(Add::new(existing, id, sum)));
+                    sum
+                }
+                None => id,
+            };
+            gradients.insert(out, id);
+        }
+
+        self.backprop(g, &self.inputs, gradients, Unreached::Zero)
+    }
+
+    /// One-shot Jacobian-vector product: evaluates at `primals` and returns the
+    /// outputs together with their directional derivative along `tangents`.
+    ///
+    /// See [`TraceableFn::jvp`]; the graph is built and evaluated once.
+    pub fn jvp_eval(
+        &self,
+        primals: Vec<TensorData<D>>,
+        tangents: Vec<TensorData<D>>,
+    ) -> (Vec<TensorData<D>>, Vec<TensorData<D>>) {
+        assert_eq!(
+            primals.len(),
+            tangents.len(),
+            "jvp_eval: expected one tangent per primal, got {} primals and {} tangents",
+            primals.len(),
+            tangents.len()
+        );
+        for (p, t) in primals.iter().zip(&tangents) {
+            assert_eq!(
+                p.shape(),
+                t.shape(),
+                "jvp_eval: tangent shape should match its primal"
+            );
+        }
+
+        let n_outputs = self.outputs.len();
+        let mut out = self
+            .jvp()
+            .compile()
+            .run_packed(primals.into_iter().chain(tangents).collect());
+        let tangent_out = out.split_off(n_outputs);
+        (out, tangent_out)
+    }
+
+    /// Forward-mode derivative as a function of the primals followed by one
+    /// tangent per primal, returning the outputs followed by `J v`.
+    ///
+    /// There are no forward rules, so this transposes the vjp instead: with
+    /// a cotangent `u` per output, the vjp is `J^T u`, linear in `u`, and the
+    /// gradient of `<J^T u, v>` with respect to `u` is `J v`. This needs the
+    /// vjps of the backward ops, so it works wherever `grad().grad()` does.
+    pub fn jvp(&self) -> Self {
+        let mut g = self.graph.clone();
+        let mut cotangents: HashMap<Id, Id> = HashMap::new();
+        let mut us = Vec::with_capacity(self.outputs.len());
+        for &out in &self.outputs {
+            // the value of `u` never matters, only that `J^T u` is linear in it.
+            let u = g.fresh();
+            g.push(Box::new(ZerosLike::new(out, u)));
+            us.push(u);
+            // an id returned twice receives both cotangents.
+            let u = match cotangents.get(&out) {
+                Some(&existing) => {
+                    let sum = g.fresh();
+                    g.push(Box::new(Add::new(existing, u, sum)));
+                    sum
+                }
+                None => u,
+            };
+            cotangents.insert(out, u);
+        }
+        let vjp = self.backprop(g, &self.inputs, cotangents, Unreached::Zero);
+
+        // <J^T u, v>, summed over the inputs.
+        let mut g = vjp.graph;
+        let mut inner = None;
+        let mut tangents = Vec::with_capacity(self.inputs.len());
+        for &grad in &vjp.outputs {
+            let v = g.fresh();
+            g.push(Box::new(Input::new(v)));
+            tangents.push(v);
+            let prod = g.fresh();
+            g.push(Box::new(Mul::new(grad, v, prod)));
+            let dot = g.fresh();
+            g.push(Box::new(Sum::new(prod, dot, vec![], false)));
+            inner = Some(match inner {
+                Some(acc) => {
+                    let sum = g.fresh();
+                    g.push(Box::new(Add::new(acc, dot, sum)));
+                    sum
+                }
+                None => dot,
+            });
+        }
+        let inner = inner.expect("jvp: the function has no inputs");
+
+        let inputs: Vec<Id> = self.inputs.iter().copied().chain(tangents).collect();
+        let transposed = TraceableFn {
+            graph: g,
+            inputs: inputs.clone(),
+            outputs: vec![inner],
+        }
+        .grad_wrt(&us, &[inner], Unreached::Zero);
+
+        // outputs no input reaches get a scalar zero; give it the output's shape.
+        let mut g = transposed.graph;
+        let jv: Vec<Id> = transposed
+            .outputs
+            .iter()
+            .zip(&self.outputs)
+            .map(|(&jv, &out)| {
+                let shaped = g.fresh();
+                g.push(Box::new(BroadcastLike::new(jv, out, shaped)));
+                shaped
+            })
+            .collect();
+
+        TraceableFn {
+            graph: g,
+            inputs,
+            outputs: self.outputs.iter().copied().chain(jv).collect(),
+        }
+    }
+
+    /// walks `g` backwards from the cotangents already in `gradients`,
+    /// appending the vjp nodes, and returns the gradient of each id in `wrt`.
+    fn backprop(
+        &self,
+        mut g: Graph<D>,
+        wrt: &[Id],
+        mut gradients: HashMap<Id, Id>,
+        unreached: Unreached,
+    ) -> Self {
         let vjp_nodes = g.nodes.clone();
 
-        for node in vjp_nodes.iter().rev() {
+        for (fwd_idx, node) in vjp_nodes.iter().enumerate().rev() {
             let out_ids = node.outputs();
             let out_grads: Vec<_> = out_ids
                 .iter()
@@ -91,8 +794,30 @@ impl<D: Floating + 'static> TraceableFn<D> {
                 continue;
             }
 
+            // multi-output ops get one gradient per output, zeros where none flowed back.
+            let out_grads = if out_ids.len() > 1 {
+                out_ids
+                    .iter()
+                    .map(|out| {
+                        gradients.get(out).copied().unwrap_or_else(|| {
+                            let z = g.fresh();
+                            g.push(Box::new(ZerosLike::new(*out, z)));
+                            z
+                        })
+                    })
+                    .collect()
+            } else {
+                out_grads
+            };
+
             // This is now valid because the loop isn't borrowing `g`.
-            if let Some(inp_grad) = node.vjp(&mut g, &out_grads) {
+            let first_grad_node = g.nodes.len();
+            let inp_grad = node.vjp(&mut g, &out_grads);
+            for grad_idx in first_grad_node..g.nodes.len() {
+                g.grad_sources.insert(grad_idx, fwd_idx);
+            }
+
+            if let Some(inp_grad) = inp_grad {
                 for (inp, grad_contrib) in node.inputs().into_iter().zip(inp_grad) {
                     if let Some(existing) = gradients.get(&inp).copied() {
                         let out = g.fresh();
@@ -105,11 +830,14 @@ impl<D: Floating + 'static> TraceableFn<D> {
             }
         }
 
-        let grads_out: Vec<_> = self
-            .inputs
+        let grads_out: Vec<_> = wrt
             .iter()
             .map(|i| {
                 gradients.get(i).copied().unwrap_or_else(|| {
+                    if unreached == Unreached::Error {
+                        let pos = self.inputs.iter().position(|id| id == i).unwrap();
+                        panic!("grad: input {pos} does not reach any differentiated output");
+                    }
                     let z = g.fresh();
                     g.push(Box::new(Const::new(D::zero(), z)));
                     z
@@ -117,19 +845,122 @@ impl<D: Floating + 'static> TraceableFn<D> {
             })
             .collect();
 
+        g.forward_len = Some(self.graph.nodes.len());
         Self {
-            graph: g,
+            graph: rematerialize(g, vjp_nodes.len()),
             inputs: self.inputs.clone(),
             outputs: grads_out,
         }
     }
 }
 
+/// points the backward nodes (from `fwd_len` on) at replayed copies of every
+/// checkpointed region they read from, so the forward values in those regions
+/// are dead once the forward pass is done with them. each region is replayed
+/// once, just before its first backward reader.
+fn rematerialize<D: Floating + 'static>(mut g: Graph<D>, fwd_len: usize) -> Graph<D> {
+    if g.checkpoints.is_empty() {
+        return g;
+    }
+    let regions = g.checkpoints.clone();
+    let old_nodes = std::mem::take(&mut g.nodes);
+
+    let mut region_of: HashMap<Id, usize> = HashMap::new();
+    for (r, range) in regions.iter().enumerate() {
+        for node in &old_nodes[range.clone()] {
+            // inputs are supplied by the caller and cannot be replayed.
+            if !node.is_input() {
+                region_of.extend(node.outputs().into_iter().map(|out| (out, r)));
+            }
+        }
+    }
+
+    struct Replay<'a, D> {
+        nodes: &'a [Rc<dyn Op<D>>],
+        regions: &'a [Range<usize>],
+        region_of: &'a HashMap<Id, usize>,
+        replayed: Vec<bool>,
+        remap: HashMap<Id, Id>,
+    }
+
+    impl<D: Floating + 'static> Replay<'_, D> {
+        fn mapped(&self, ids: Vec<Id>) -> Vec<Id> {
+            ids.into_iter()
+                .map(|id| self.remap.get(&id).copied().unwrap_or(id))
+                .collect()
+        }
+
+        // replays region `r`, after any earlier region it reads from.
+        fn region(&mut self, r: usize, g: &mut Graph<D>) {
+            self.replayed[r] = true;
+            for i in self.regions[r].clone() {
+                let node = self.nodes[i].clone();
+                for id in node.inputs() {
+                    if let Some(&dep) = self.region_of.get(&id)
+                        && !self.replayed[dep]
+                    {
+                        self.region(dep, g);
+                    }
+                }
+                if node.is_input() {
+                    continue;
+                }
+                let inputs = self.mapped(node.inputs());
+                let outputs: Vec<Id> = node
+                    .outputs()
+                    .into_iter()
+                    .map(|out| {
+                        let fresh = g.fresh();
+                        self.remap.insert(out, fresh);
+                        fresh
+                    })
+                    .collect();
+                g.push(Box::new(Remat::new(node, inputs, outputs)));
+            }
+        }
+    }
+
+    let mut replay = Replay {
+        nodes: &old_nodes,
+        regions: &regions,
+        region_of: &region_of,
+        replayed: vec![false; regions.len()],
+        remap: HashMap::new(),
+    };
+
+    g.nodes = old_nodes[..fwd_len].to_vec();
+    let mut new_index: HashMap<usize, usize> = HashMap::new();
+    for (i, node) in old_nodes.iter().enumerate().skip(fwd_len) {
+        let inputs = node.inputs();
+        for id in &inputs {
+            if let Some(&r) = region_of.get(id)
+                && !replay.replayed[r]
+            {
+                replay.region(r, &mut g);
+            }
+        }
+        new_index.insert(i, g.nodes.len());
+        if inputs.iter().any(|id| replay.remap.contains_key(id)) {
+            let inputs = replay.mapped(inputs);
+            g.push(Box::new(Remat::new(node.clone(), inputs, node.outputs())));
+        } else {
+            g.nodes.push(node.clone());
+        }
+    }
+
+    g.grad_sources = g
+        .grad_sources
+        .iter()
+        .map(|(grad, &fwd)| (new_index.get(grad).copied().unwrap_or(*grad), fwd))
+        .collect();
+    g
+}
+
 pub trait EvalOutputs<D> {
     fn from_vec(f: Vec<TensorData<D>>) -> Self;
 }
 
-use ndarray::{ArrayBase, Data, Dimension};
+use ndarray::{ArrayBase, Axis, Data, Dimension};
 
 pub trait ToTensorData<D: Floating> {
     fn to_tensor(&self) -> TensorData<D>;
@@ -144,6 +975,19 @@ where
         self.to_owned().into_dyn()
     }
 }
+
+/// a flat row-major buffer plus its shape, for callers without `ndarray`.
+impl<D: Floating> ToTensorData<D> for (Vec<D>, Vec<usize>) {
+    fn to_tensor(&self) -> TensorData<D> {
+        let (data, shape) = self;
+        TensorData::from_shape_vec(shape.clone(), data.clone()).unwrap_or_else(|_| {
+            panic!(
+                "eval: {} values do not fill a tensor of shape {shape:?}",
+                data.len()
+            )
+        })
+    }
+}
 mod macros {
     use super::{EvalArgs, EvalOutputs, Floating, TensorData};
     macro_rules! as_owned_ty {
@@ -1,6 +1,7 @@
+pub mod compiled;
 pub mod function;
 pub mod session;
 pub mod tracer;
 
-pub use session::TraceSession;
+pub use session::{ScalarArg, TraceIndex, TraceSession};
 pub use tracer::{Tensor, TensorData, Tracer};
@@ -1,21 +1,143 @@
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+};
+
 use crate::{
     Floating,
+    context::Context,
     graph::Graph,
-    identity::Id,
-    ops::{Add, Const, Input, Mul, Neg, Op, Sub, div::Div},
-    tracing::Tracer,
+    identity::{Id, IdGenerator, generators::FreeList},
+    ops::{Add, Const, ConstTensor, Input, Mul, Neg, Op, Sub, div::Div},
+    tracing::{TensorData, Tracer},
 };
 
-pub struct TraceSession<'graph, DType: Floating> {
-    pub g: &'graph mut Graph<DType>,
+/// A trace-time scalar: a plain float, or a tracer holding a constant such as
+/// an `f32`/`f64` argument of a `#[trace]` function, which is lifted into the
+/// graph. Ops taking scalar parameters (`clamp`, `powf`, `layer_norm`'s eps,
+/// ...) accept either.
+pub trait ScalarArg {
+    fn scalar<D: Floating + 'static, G: IdGenerator<Id = Id>>(
+        self,
+        sess: &TraceSession<'_, D, G>,
+    ) -> D;
+}
+
+impl ScalarArg for f64 {
+    fn scalar<D: Floating + 'static, G: IdGenerator<Id = Id>>(
+        self,
+        _sess: &TraceSession<'_, D, G>,
+    ) -> D {
+        D::from_f64(self)
+    }
+}
+
+impl ScalarArg for f32 {
+    fn scalar<D: Floating + 'static, G: IdGenerator<Id = Id>>(
+        self,
+        _sess: &TraceSession<'_, D, G>,
+    ) -> D {
+        D::from_f64(f64::from(self))
+    }
+}
+
+impl ScalarArg for Tracer {
+    fn scalar<D: Floating + 'static, G: IdGenerator<Id = Id>>(
+        self,
+        sess: &TraceSession<'_, D, G>,
+    ) -> D {
+        let value = sess.static_value(self).unwrap_or_else(|| {
+            panic!(
+                "scalar argument: {:?} depends on an input, not only on constants",
+                self.id()
+            )
+        });
+        assert_eq!(
+            value.len(),
+            1,
+            "scalar argument: expected a single value, got shape {:?}",
+            value.shape()
+        );
+        value.iter().copied().next().unwrap()
+    }
+}
+
+/// `a[i]` inside `#[trace]`, which lowers to `TraceIndex::trace_index(&a, i,
+/// sess)`: a tracer takes entry `i` along its first axis, as in
+/// `sess.index`, while anything else indexable, such as the `Vec` returned by
+/// `split`, is indexed as ordinary Rust.
+pub trait TraceIndex<I> {
+    type Output;
+    fn trace_index<D: Floating + 'static, G: IdGenerator<Id = Id>>(
+        &self,
+        index: I,
+        sess: &mut TraceSession<'_, D, G>,
+    ) -> Self::Output;
+}
+
+impl TraceIndex<isize> for Tracer {
+    type Output = Tracer;
+    fn trace_index<D: Floating + 'static, G: IdGenerator<Id = Id>>(
+        &self,
+        index: isize,
+        sess: &mut TraceSession<'_, D, G>,
+    ) -> Tracer {
+        sess.index(*self, index)
+    }
+}
+
+impl<T: std::ops::Index<I> + ?Sized, I> TraceIndex<I> for T
+where
+    T::Output: Clone + Sized,
+{
+    type Output = T::Output;
+    fn trace_index<D: Floating + 'static, G: IdGenerator<Id = Id>>(
+        &self,
+        index: I,
+        _sess: &mut TraceSession<'_, D, G>,
+    ) -> T::Output {
+        self[index].clone()
+    }
+}
+
+/// Records ops into `g` as a `#[trace]` function runs. Generic over the
+/// graph's id generator, so a function can be traced into e.g. a
+/// [`Monotonic`](crate::identity::generators::Monotonic) graph.
+pub struct TraceSession<'graph, DType: Floating, G: IdGenerator = FreeList> {
+    pub g: &'graph mut Graph<DType, G>,
+    known: RefCell<Known<DType>>,
+}
+
+/// what is fixed at trace time, for `static_shape` and `static_value`.
+/// each lookup first catches up on the nodes emitted since the last one, so
+/// every node is visited once per session rather than once per lookup.
+struct Known<D> {
+    /// nodes `..shaped` have been through their shape rule.
+    shaped: usize,
+    shapes: HashMap<Id, Vec<usize>>,
+    /// nodes `..valued` have been evaluated if they depend only on constants.
+    valued: usize,
+    values: Context<D>,
+    constant: HashSet<Id>,
 }
 
-impl<D> TraceSession<'_, D>
+impl<D, G> TraceSession<'_, D, G>
 where
     D: Floating + 'static,
+    G: IdGenerator<Id = Id>,
 {
-    pub const fn new(g: &mut Graph<D>) -> TraceSession<'_, D> {
-        TraceSession { g }
+    pub fn new(g: &mut Graph<D, G>) -> TraceSession<'_, D, G> {
+        let known = Known {
+            shaped: 0,
+            shapes: HashMap::new(),
+            valued: 0,
+            values: Context::new(),
+            constant: HashSet::new(),
+        };
+        TraceSession {
+            g,
+            known: RefCell::new(known),
+        }
     }
 
     #[must_use]
@@ -30,12 +152,79 @@ where
         self.emit(Input::new(out), out)
     }
 
+    /// an input tagged as a trainable parameter. it is still supplied at eval,
+    /// but `TraceableFn::grad_params` differentiates only w.r.t. these.
+    #[must_use]
+    pub fn parameter(&mut self) -> Tracer {
+        let p = self.input();
+        self.g.params.push(p.id());
+        p
+    }
+
     #[must_use]
     pub fn constant(&mut self, val: D) -> Tracer {
         let out = self.g.fresh();
         self.emit(Const::new(val, out), out)
     }
 
+    /// embeds a fixed tensor in the graph, so it need not be passed as an input.
+    #[must_use]
+    pub fn constant_tensor(&mut self, val: TensorData<D>) -> Tracer {
+        let out = self.g.fresh();
+        self.emit(ConstTensor::new(val, out), out)
+    }
+
+    /// shape of `t` if it is already fixed at trace time, i.e. `t` depends only
+    /// on constants. inputs are shaped at eval, so anything downstream of one
+    /// is `None`.
+    pub fn static_shape(&self, t: Tracer) -> Option<Vec<usize>> {
+        let known = &mut *self.known.borrow_mut();
+        for node in &self.g.nodes[known.shaped..] {
+            let in_shapes: Option<Vec<_>> = node
+                .inputs()
+                .iter()
+                .map(|id| known.shapes.get(id).cloned())
+                .collect();
+            if let Some(in_shapes) = in_shapes.filter(|_| !node.is_input()) {
+                let outs = node.outputs().into_iter().zip(node.shape_rule(&in_shapes));
+                known.shapes.extend(outs);
+            }
+        }
+        known.shaped = self.g.nodes.len();
+        known.shapes.get(&t.id()).cloned()
+    }
+
+    /// value of `t` if it depends only on constants, e.g. a hyperparameter
+    /// that `#[trace]` lifted into the graph. anything downstream of an input
+    /// is `None`.
+    pub fn static_value(&self, t: Tracer) -> Option<TensorData<D>> {
+        let known = &mut *self.known.borrow_mut();
+        for node in &self.g.nodes[known.valued..] {
+            if !node.is_input() && node.inputs().iter().all(|id| known.constant.contains(id)) {
+                node.eval(&mut known.values);
+                known.constant.extend(node.outputs());
+            }
+        }
+        known.valued = self.g.nodes.len();
+        known
+            .constant
+            .contains(&t.id())
+            .then(|| known.values.checked_get(&t.id()).clone())
+    }
+
+    /// traces `f` as a rematerialized block, like JAX's `remat`: the backward
+    /// pass replays the nodes emitted inside it instead of keeping their
+    /// outputs alive from the forward pass, trading compute for memory.
+    pub fn checkpoint<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let start = self.g.nodes.len();
+        let out = f(self);
+        let end = self.g.nodes.len();
+        if end > start {
+            self.g.checkpoints.push(start..end);
+        }
+        out
+    }
+
     #[must_use]
     pub fn add(&mut self, a: Tracer, b: Tracer) -> Tracer {
         let out = self.g.fresh();
@@ -8,6 +8,12 @@ use std::ops::Div;
 pub type TensorData<T = f32> = ndarray::ArrayD<T>;
 pub type Tensor = Tracer;
 
+/// Handle to a tensor node while a `#[trace]` function is being built.
+///
+/// Op methods on `Tracer` are receiver-first dummies: `x.op(args..)` is
+/// rewritten by `#[trace]` into `sess.op(x, args..)`, so every dummy takes
+/// exactly the `TraceSession` method's arguments after its leading `Tracer`.
+/// Called outside a trace, the dummies panic.
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, PartialOrd, Ord)]
 pub struct Tracer {
     id: Id,
@@ -24,6 +30,24 @@ impl Tracer {
     pub fn shape(&self) -> Vec<usize> {
         panic!("dummy shape function - only allowed inside #[trace] functions")
     }
+
+    /// method form of `x + other`; both lower to `sess.add(x, other)`. the
+    /// `_t` suffix keeps it from shadowing `std::ops::Add::add` in `#[trace]`.
+    pub fn add_t(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// method form of `x - other`.
+    pub fn sub_t(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// method form of `x * other`.
+    pub fn mul_t(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
+    /// method form of `x / other`.
+    pub fn div_t(&self, _other: Tracer) -> Tracer {
+        panic!("dummy operation - only allowed inside #[trace] function")
+    }
 }
 
 impl Add for Tracer {